@@ -0,0 +1,60 @@
+class FfiError(Exception):
+    """Error reported by the native library through an `FfiResult`."""
+
+    def __init__(self, error_code, description):
+        super().__init__("Error code: {}. Description: {}".format(error_code, description))
+        self.error_code = error_code
+        self.description = description
+
+
+_pending = {}
+_user_data = itertools.count(1)
+
+
+def _prepare_future():
+    loop = asyncio.get_event_loop()
+    future = loop.create_future()
+    user_data = next(_user_data)
+    _pending[user_data] = (loop, future)
+    return future, ctypes.c_void_p(user_data)
+
+
+def _complete_future(user_data, result, values):
+    # Runs on a thread of the native library, so the values are copied before it returns.
+    loop, future = _pending.pop(user_data)
+    if result.contents.error_code != 0:
+        error = FfiError(result.contents.error_code, _decode(result.contents.description))
+        loop.call_soon_threadsafe(_set_exception, future, error)
+    else:
+        loop.call_soon_threadsafe(_set_result, future, values())
+
+
+def _set_result(future, value):
+    if not future.cancelled():
+        future.set_result(value)
+
+
+def _set_exception(future, error):
+    if not future.cancelled():
+        future.set_exception(error)
+
+
+def _encode(value):
+    return value.encode() if isinstance(value, str) else value
+
+
+def _decode(value):
+    return value.decode() if value is not None else None
+
+
+def _copy(ptr):
+    return type(ptr.contents).from_buffer_copy(ptr.contents) if ptr else None
+
+
+def _copy_array(ptr, length):
+    if not ptr or not length:
+        return b"" if ptr._type_ is ctypes.c_uint8 else []
+    if ptr._type_ is ctypes.c_uint8:
+        return ctypes.string_at(ptr, length)
+    array = ctypes.cast(ptr, ctypes.POINTER(ptr._type_ * length)).contents
+    return list(type(array).from_buffer_copy(array))
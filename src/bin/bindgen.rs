@@ -1,11 +1,107 @@
-use clap::{self, crate_version};
-use jni::signature::{JavaType, Primitive};
-use sn_bindgen::{Bindgen, LangC, LangCSharp, LangJava};
+use clap::{self, crate_version, ArgMatches};
+use sn_bindgen::config::{ConstConfig, Filter, SectionConfig};
+use sn_bindgen::{
+    java_type_from_str, Bindgen, Config, FilterMode, LangC, LangCSharp, LangCpp, LangJava,
+    LangKotlin, LangPython, LangSwift, MessageFormat,
+};
 use std::collections::HashMap;
+use std::fs;
+use std::process;
 use unwrap::unwrap;
 
+/// Java types used by the Safe Network libraries, enabled with `--preset safe`.
+const SAFE_JAVA_TYPE_MAP: &[(&str, &str)] = &[
+    ("XorNameArray", "byte[]"),
+    ("SignSecretKey", "byte[]"),
+    ("SignPublicKey", "byte[]"),
+    ("SymSecretKey", "byte[]"),
+    ("SymNonce", "byte[]"),
+    ("AsymPublicKey", "byte[]"),
+    ("AsymSecretKey", "byte[]"),
+    ("AsymNonce", "byte[]"),
+    ("CipherOptHandle", "long"),
+    ("EncryptPubKeyHandle", "long"),
+    ("EncryptSecKeyHandle", "long"),
+    ("MDataEntriesHandle", "long"),
+    ("MDataEntryActionsHandle", "long"),
+    ("MDataPermissionsHandle", "long"),
+    ("SelfEncryptorReaderHandle", "long"),
+    ("SelfEncryptorWriterHandle", "long"),
+    ("SEReaderHandle", "long"),
+    ("SEWriterHandle", "long"),
+    ("SignPubKeyHandle", "long"),
+    ("SignSecKeyHandle", "long"),
+    ("FileContextHandle", "long"),
+    ("App", "long"),
+    ("Authenticator", "long"),
+];
+
 fn main() {
-    let matches = clap::App::new("bindgen")
+    let matches = app().get_matches();
+    let mut bindgen = Bindgen::with_config().expect("configuration could not be read");
+    let lang = unwrap!(matches.value_of("LANG"));
+
+    if let Some(file) = matches.value_of("FILE") {
+        bindgen.source_file(&file);
+    }
+
+    if let Some(roots) = matches.values_of("ROOT") {
+        bindgen.set_roots(roots);
+    }
+
+    if matches.is_present("STRICT") {
+        bindgen.set_strict(true);
+    }
+
+    if matches.is_present("KEEP_STALE") {
+        bindgen.set_remove_stale(false);
+    }
+
+    if matches.value_of("MESSAGE_FORMAT") == Some("json") {
+        bindgen.set_message_format(MessageFormat::Json);
+    }
+
+    apply_args(&matches, bindgen.config_mut());
+
+    let output_dir = if let Some(output) = matches.value_of("OUTPUT") {
+        output.to_string()
+    } else {
+        format!("bind-gen/{}", lang)
+    };
+
+    let check = matches.is_present("CHECK");
+    // Builds the bindings, or only checks the existing ones in check mode.
+    macro_rules! run {
+        ($lang:expr) => {{
+            let mut lang = $lang;
+            bindgen.configure(&mut lang);
+            if check {
+                bindgen.run_check(&mut lang, &output_dir)
+            } else {
+                bindgen.run_build(&mut lang, &output_dir);
+                true
+            }
+        }};
+    }
+
+    let up_to_date = match lang {
+        "c" => run!(LangC::new()),
+        "cpp" => run!(LangCpp::new()),
+        "csharp" => run!(LangCSharp::new()),
+        "java" => run!(LangJava::new(HashMap::new())),
+        "kotlin" => run!(LangKotlin::new(HashMap::new())),
+        "python" => run!(LangPython::new()),
+        "swift" => run!(LangSwift::new()),
+        _ => unreachable!(),
+    };
+    if !up_to_date {
+        process::exit(1);
+    }
+}
+
+/// Command line interface of the binary.
+fn app() -> clap::App<'static, 'static> {
+    clap::App::new("bindgen")
         .version(crate_version!())
         .author("Sean Marshallsay <srm.1708@gmail.com>, MaidSafe Developers <dev@maidsafe.net>")
         .about("create binding files using a Rust source file")
@@ -24,115 +120,546 @@ fn main() {
                 .takes_value(true)
                 .required(true)
                 .help("target language")
-                .possible_values(&["csharp", "java", "c"]),
+                .possible_values(&["csharp", "java", "c", "cpp", "python", "swift", "kotlin"]),
         )
         .arg(
             clap::Arg::with_name("LIB")
                 .long("--lib")
                 .takes_value(true)
-                .required(true)
                 .help("name of the native library to link"),
         )
+        .arg(
+            clap::Arg::with_name("ROOT")
+                .long("--root")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("MODULE")
+                .help("module containing the FFI (default: `ffi`)"),
+        )
+        .arg(
+            clap::Arg::with_name("PRESET")
+                .long("--preset")
+                .takes_value(true)
+                .help("predefined settings for a family of libraries")
+                .possible_values(&["safe"]),
+        )
+        .arg(
+            clap::Arg::with_name("FILTER")
+                .long("--filter")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("IDENT")
+                .conflicts_with("WHITELIST")
+                .help("ignore the item (Java, Kotlin and C# only)"),
+        )
+        .arg(
+            clap::Arg::with_name("WHITELIST")
+                .long("--whitelist")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("IDENT")
+                .help("ignore all items except the given ones (Java, Kotlin and C# only)"),
+        )
+        .arg(
+            clap::Arg::with_name("CUSTOM_CODE")
+                .long("--custom-code")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("add the contents of the file to the top-level header (C only)"),
+        )
+        .arg(
+            clap::Arg::with_name("SINGLE_HEADER")
+                .long("--single-header")
+                .help("generate one self-contained header instead of one per module (C only)"),
+        )
+        .arg(
+            clap::Arg::with_name("GUARD_PREFIX")
+                .long("--guard-prefix")
+                .takes_value(true)
+                .value_name("PREFIX")
+                .help("prefix of the include-guard macros (C only)"),
+        )
+        .arg(
+            clap::Arg::with_name("INCLUDE")
+                .long("--include")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("HEADER")
+                .help("header to include instead of `stdint.h` and `stdbool.h` (C only)"),
+        )
+        .arg(
+            clap::Arg::with_name("NAMESPACE")
+                .long("--namespace")
+                .takes_value(true)
+                .help("namespace of the bindings (Java, Kotlin, C++ and C#)"),
+        )
+        .arg(
+            clap::Arg::with_name("MODEL_NAMESPACE")
+                .long("--model-namespace")
+                .takes_value(true)
+                .value_name("NAMESPACE")
+                .help("namespace of the structures (Java and Kotlin only, default: --namespace)"),
+        )
+        .arg(
+            clap::Arg::with_name("FREE_FUNCTION")
+                .long("--free-function")
+                .takes_value(true)
+                .value_name("IDENT")
+                .help("native function freeing returned pointers (Java and Kotlin only)"),
+        )
+        .arg(
+            clap::Arg::with_name("OWNED_RETURNS")
+                .long("--owned-returns")
+                .requires("FREE_FUNCTION")
+                .help("free returned pointers with the free function (Java and Kotlin only)"),
+        )
+        .arg(
+            clap::Arg::with_name("CONSTS_CLASS")
+                .long("--consts-class")
+                .takes_value(true)
+                .value_name("CLASS")
+                .help("class holding the constants (Java and Kotlin only)"),
+        )
+        .arg(
+            clap::Arg::with_name("CONSTS_NAMESPACE")
+                .long("--consts-namespace")
+                .takes_value(true)
+                .value_name("NAMESPACE")
+                .help("namespace of the constants class (Java and Kotlin only)"),
+        )
+        .arg(
+            clap::Arg::with_name("FREE_SUFFIX")
+                .long("--free-suffix")
+                .takes_value(true)
+                .value_name("SUFFIX")
+                .help("suffix of the functions freeing handles (C++ only)"),
+        )
+        .arg(
+            clap::Arg::with_name("C_MODULE")
+                .long("--c-module")
+                .takes_value(true)
+                .value_name("MODULE")
+                .help("name of the clang module of the C header (Swift only)"),
+        )
+        .arg(
+            clap::Arg::with_name("TYPE_MAP")
+                .long("--type-map")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("NAME=JAVATYPE")
+                .validator(|value| split_pair(&value, '=').map(|_| ()))
+                .help("use the Java type for the Rust type (Java and Kotlin only)"),
+        )
+        .arg(
+            clap::Arg::with_name("OPAQUE")
+                .long("--opaque")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("TYPE")
+                .help("type represented by an opaque pointer (C and C# only)"),
+        )
+        .arg(
+            clap::Arg::with_name("WRAPPER_BLACKLIST")
+                .long("--wrapper-blacklist")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("IDENT")
+                .help("do not generate a wrapper for the function (C# only)"),
+        )
+        .arg(
+            clap::Arg::with_name("CONST")
+                .long("--const")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("TYPE,NAME,VALUE")
+                .validator(|value| parse_const(&value).map(|_| ()))
+                .help("add a constant (C# only)"),
+        )
+        .args(&section_args())
+        .arg(
+            clap::Arg::with_name("NO_CONSTS")
+                .long("--no-consts")
+                .help("do not generate the constants section (C# only)"),
+        )
+        .arg(
+            clap::Arg::with_name("NO_TYPES")
+                .long("--no-types")
+                .help("do not generate the types section (C# only)"),
+        )
+        .arg(
+            clap::Arg::with_name("NO_UTILS")
+                .long("--no-utils")
+                .help("do not generate the utils section (C# only)"),
+        )
+        .arg(
+            clap::Arg::with_name("MESSAGE_FORMAT")
+                .long("--message-format")
+                .takes_value(true)
+                .default_value("human")
+                .help("error format")
+                .possible_values(&["human", "json"]),
+        )
+        .arg(
+            clap::Arg::with_name("STRICT")
+                .long("--strict")
+                .help("treat skipped items as errors"),
+        )
+        .arg(
+            clap::Arg::with_name("KEEP_STALE")
+                .long("--keep-stale")
+                .help("report files generated by earlier runs instead of removing them"),
+        )
+        .arg(
+            clap::Arg::with_name("CHECK")
+                .long("--check")
+                .help("check the output directory is up to date instead of writing to it"),
+        )
         .arg(
             clap::Arg::with_name("OUTPUT")
                 .index(1)
                 .help("set the output directory"),
         )
-        .get_matches();
+}
 
-    let mut bindgen = Bindgen::new().expect("cargo manifest could not be read");
-    let lang = unwrap!(matches.value_of("LANG"));
-    let lib = unwrap!(matches.value_of("LIB"));
+/// Arguments setting the C# output sections.
+fn section_args() -> Vec<clap::Arg<'static, 'static>> {
+    const SECTION: &str = "PATH[,NAMESPACE[,CLASS]]";
+    [
+        (
+            "INTERFACE_SECTION",
+            "--interface-section",
+            SECTION,
+            "path, namespace and name of the interface (C# only)",
+        ),
+        (
+            "FUNCTIONS_SECTION",
+            "--functions-section",
+            SECTION,
+            "path, namespace and class of the functions (C# only)",
+        ),
+        (
+            "CONSTS_SECTION",
+            "--consts-section",
+            SECTION,
+            "path, namespace and class of the constants (C# only)",
+        ),
+        (
+            "TYPES_SECTION",
+            "--types-section",
+            "PATH[,NAMESPACE]",
+            "path and namespace of the types (C# only)",
+        ),
+        (
+            "UTILS_SECTION",
+            "--utils-section",
+            SECTION,
+            "path, namespace and class of the utils (C# only)",
+        ),
+    ]
+    .iter()
+    .map(|&(name, long, value_name, help)| {
+        clap::Arg::with_name(name)
+            .long(long)
+            .takes_value(true)
+            .value_name(value_name)
+            .help(help)
+    })
+    .collect()
+}
 
-    if let Some(file) = matches.value_of("FILE") {
-        bindgen.source_file(&file);
+/// Overrides the configuration with the command line arguments.
+fn apply_args(matches: &ArgMatches, config: &mut Config) {
+    if let Some(lib) = matches.value_of("LIB") {
+        config.lib_name = Some(lib.to_string());
+        config.c.lib_name = None;
+        config.cpp.lib_name = None;
+        config.java.lib_name = None;
+        config.csharp.lib_name = None;
+        config.python.lib_name = None;
+        config.swift.lib_name = None;
     }
 
-    let output_dir = if let Some(output) = matches.value_of("OUTPUT") {
-        output.to_string()
-    } else {
-        format!("bind-gen/{}", lang)
+    let filter = match (matches.values_of("FILTER"), matches.values_of("WHITELIST")) {
+        (Some(idents), _) => Some((FilterMode::Blacklist, idents)),
+        (_, Some(idents)) => Some((FilterMode::Whitelist, idents)),
+        _ => None,
     };
+    if let Some((mode, idents)) = filter {
+        let filter = Filter {
+            mode,
+            idents: idents.map(str::to_string).collect(),
+        };
+        config.java.filter = Some(filter.clone());
+        config.csharp.filter = Some(filter);
+    }
+
+    if let Some(path) = matches.value_of("CUSTOM_CODE") {
+        let code = fs::read_to_string(path).unwrap_or_else(|err| {
+            clap::Error::with_description(
+                &format!("could not read `{}`: {}", path, err),
+                clap::ErrorKind::Io,
+            )
+            .exit()
+        });
+        config.c.custom_code = Some(config.c.custom_code.take().unwrap_or_default() + &code);
+        config.files.push(path.into());
+    }
 
-    match lang {
-        "c" => {
-            let mut lang = LangC::new();
-            lang.set_lib_name(lib);
-            bindgen.run_build(&mut lang, &output_dir)
+    if matches.is_present("SINGLE_HEADER") {
+        config.c.single_header = Some(true);
+    }
+    if let Some(prefix) = matches.value_of("GUARD_PREFIX") {
+        config.c.guard_prefix = Some(prefix.to_string());
+    }
+    if let Some(includes) = matches.values_of("INCLUDE") {
+        config.c.includes = Some(includes.map(str::to_string).collect());
+    }
+
+    if let Some(namespace) = matches.value_of("NAMESPACE") {
+        config.java.namespace = Some(namespace.to_string());
+        config.cpp.namespace = Some(namespace.to_string());
+        for section in &mut [
+            &mut config.csharp.interface_section,
+            &mut config.csharp.functions_section,
+            &mut config.csharp.consts_section,
+            &mut config.csharp.types_section,
+            &mut config.csharp.utils_section,
+        ] {
+            let section = section.get_or_insert_with(SectionConfig::default);
+            let _ = section
+                .namespace
+                .get_or_insert_with(|| namespace.to_string());
         }
-        "csharp" => {
-            let mut lang = LangCSharp::new();
-            lang.set_lib_name(lib);
-            bindgen.run_build(&mut lang, &output_dir)
+    }
+    let java = &mut config.java;
+    if let Some(namespace) = matches.value_of("MODEL_NAMESPACE") {
+        java.model_namespace = Some(namespace.to_string());
+    }
+    if let Some(name) = matches.value_of("FREE_FUNCTION") {
+        java.free_function = Some(name.to_string());
+    }
+    if matches.is_present("OWNED_RETURNS") {
+        java.owned_returns = Some(true);
+    }
+    if let Some(class) = matches.value_of("CONSTS_CLASS") {
+        java.consts_class = Some(class.to_string());
+    }
+    if let Some(namespace) = matches.value_of("CONSTS_NAMESPACE") {
+        java.consts_namespace = Some(namespace.to_string());
+    }
+    for mapping in matches.values_of("TYPE_MAP").into_iter().flatten() {
+        let (name, ty) = unwrap!(split_pair(mapping, '='));
+        let _ = java
+            .type_map
+            .insert(name.to_string(), java_type_from_str(ty));
+    }
+
+    if matches.value_of("PRESET") == Some("safe") {
+        for &(name, ty) in SAFE_JAVA_TYPE_MAP {
+            let _ = java
+                .type_map
+                .entry(name.to_string())
+                .or_insert_with(|| java_type_from_str(ty));
         }
-        "java" => {
-            let mut type_map = HashMap::new();
-            type_map.insert(
-                "XorNameArray",
-                JavaType::Array(Box::new(JavaType::Primitive(Primitive::Byte))),
-            );
-            type_map.insert(
-                "SignSecretKey",
-                JavaType::Array(Box::new(JavaType::Primitive(Primitive::Byte))),
-            );
-            type_map.insert(
-                "SignPublicKey",
-                JavaType::Array(Box::new(JavaType::Primitive(Primitive::Byte))),
-            );
-            type_map.insert(
-                "SymSecretKey",
-                JavaType::Array(Box::new(JavaType::Primitive(Primitive::Byte))),
-            );
-            type_map.insert(
-                "SymNonce",
-                JavaType::Array(Box::new(JavaType::Primitive(Primitive::Byte))),
-            );
-            type_map.insert(
-                "AsymPublicKey",
-                JavaType::Array(Box::new(JavaType::Primitive(Primitive::Byte))),
-            );
-            type_map.insert(
-                "AsymSecretKey",
-                JavaType::Array(Box::new(JavaType::Primitive(Primitive::Byte))),
-            );
-            type_map.insert(
-                "AsymNonce",
-                JavaType::Array(Box::new(JavaType::Primitive(Primitive::Byte))),
-            );
-            type_map.insert("CipherOptHandle", JavaType::Primitive(Primitive::Long));
-            type_map.insert("EncryptPubKeyHandle", JavaType::Primitive(Primitive::Long));
-            type_map.insert("EncryptSecKeyHandle", JavaType::Primitive(Primitive::Long));
-            type_map.insert("MDataEntriesHandle", JavaType::Primitive(Primitive::Long));
-            type_map.insert(
-                "MDataEntryActionsHandle",
-                JavaType::Primitive(Primitive::Long),
-            );
-            type_map.insert(
-                "MDataPermissionsHandle",
-                JavaType::Primitive(Primitive::Long),
-            );
-            type_map.insert(
-                "SelfEncryptorReaderHandle",
-                JavaType::Primitive(Primitive::Long),
-            );
-            type_map.insert(
-                "SelfEncryptorWriterHandle",
-                JavaType::Primitive(Primitive::Long),
-            );
-            type_map.insert("SEReaderHandle", JavaType::Primitive(Primitive::Long));
-            type_map.insert("SEWriterHandle", JavaType::Primitive(Primitive::Long));
-            type_map.insert("SignPubKeyHandle", JavaType::Primitive(Primitive::Long));
-            type_map.insert("SignSecKeyHandle", JavaType::Primitive(Primitive::Long));
-            type_map.insert("FileContextHandle", JavaType::Primitive(Primitive::Long));
-            type_map.insert("App", JavaType::Primitive(Primitive::Long));
-            type_map.insert("Authenticator", JavaType::Primitive(Primitive::Long));
-
-            let mut java = LangJava::new(type_map);
-            java.set_namespace(format!("net.maidsafe.{}", lib));
-            java.set_model_namespace(format!("net.maidsafe.{}", lib));
-            java.set_lib_name(lib);
-            bindgen.run_build(&mut java, &output_dir);
+        if java.namespace.is_none() && java.model_namespace.is_none() {
+            if let Some(lib) = java.lib_name.as_ref().or(config.lib_name.as_ref()) {
+                java.namespace = Some(format!("net.maidsafe.{}", lib));
+            }
         }
-        _ => unreachable!(),
+    }
+    // A single namespace holds the structures as well, like the `NativeBindings` class.
+    match (&java.namespace, &java.model_namespace) {
+        (Some(namespace), None) => java.model_namespace = Some(namespace.clone()),
+        (None, Some(namespace)) => java.namespace = Some(namespace.clone()),
+        _ => (),
+    }
+
+    if let Some(suffix) = matches.value_of("FREE_SUFFIX") {
+        config.cpp.free_suffix = Some(suffix.to_string());
+    }
+    if let Some(module) = matches.value_of("C_MODULE") {
+        config.swift.c_module = Some(module.to_string());
+    }
+
+    let opaque_types: Vec<_> = matches
+        .values_of("OPAQUE")
+        .into_iter()
+        .flatten()
+        .map(str::to_string)
+        .collect();
+    config.c.opaque_types.extend(opaque_types.iter().cloned());
+
+    let csharp = &mut config.csharp;
+    csharp.opaque_types.extend(opaque_types);
+    csharp.wrapper_function_blacklist.extend(
+        matches
+            .values_of("WRAPPER_BLACKLIST")
+            .into_iter()
+            .flatten()
+            .map(str::to_string),
+    );
+    for value in matches.values_of("CONST").into_iter().flatten() {
+        csharp.consts.push(unwrap!(parse_const(value)));
+    }
+    for (name, section) in [
+        ("INTERFACE_SECTION", &mut csharp.interface_section),
+        ("FUNCTIONS_SECTION", &mut csharp.functions_section),
+        ("CONSTS_SECTION", &mut csharp.consts_section),
+        ("TYPES_SECTION", &mut csharp.types_section),
+        ("UTILS_SECTION", &mut csharp.utils_section),
+    ] {
+        if let Some(value) = matches.value_of(name) {
+            let section = section.get_or_insert_with(SectionConfig::default);
+            let mut parts = value.splitn(3, ',').map(str::to_string);
+            section.path = parts.next();
+            if let Some(namespace) = parts.next() {
+                section.namespace = Some(namespace);
+            }
+            if let Some(class) = parts.next() {
+                section.class = Some(class);
+            }
+        }
+    }
+    if matches.is_present("NO_CONSTS") {
+        csharp.consts_enabled = Some(false);
+    }
+    if matches.is_present("NO_TYPES") {
+        csharp.types_enabled = Some(false);
+    }
+    if matches.is_present("NO_UTILS") {
+        csharp.utils_enabled = Some(false);
+    }
+}
+
+/// Splits `NAME=VALUE` into its parts.
+fn split_pair(value: &str, separator: char) -> Result<(&str, &str), String> {
+    let mut parts = value.splitn(2, separator);
+    match (parts.next(), parts.next()) {
+        (Some(name), Some(value)) if !name.is_empty() && !value.is_empty() => Ok((name, value)),
+        _ => Err(format!(
+            "expected NAME{}VALUE, found `{}`",
+            separator, value
+        )),
+    }
+}
+
+/// Parses a C# constant given as `TYPE,NAME,VALUE`.
+fn parse_const(value: &str) -> Result<ConstConfig, String> {
+    let (ty, rest) = split_pair(value, ',')?;
+    let (name, value) = split_pair(rest, ',')?;
+    Ok(ConstConfig {
+        ty: ty.to_string(),
+        name: name.to_string(),
+        value: value.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_from_args(args: &[&str]) -> Config {
+        let matches = app().get_matches_from(["bindgen"].iter().chain(args));
+        let mut config = Config::default();
+        apply_args(&matches, &mut config);
+        config
+    }
+
+    #[test]
+    fn flags() {
+        let config = config_from_args(&[
+            "-l",
+            "java",
+            "--lib",
+            "app",
+            "--namespace",
+            "com.example",
+            "--free-function",
+            "app_free",
+            "--owned-returns",
+            "--consts-class",
+            "AppConstants",
+            "--consts-namespace",
+            "com.example.consts",
+            "--free-suffix",
+            "_release",
+            "--c-module",
+            "CApp",
+            "--types-section",
+            "Types.cs",
+            "--utils-section",
+            "Utils.cs,Example.Utils",
+        ]);
+
+        assert_eq!(config.lib_name.as_deref(), Some("app"));
+        assert_eq!(config.java.namespace.as_deref(), Some("com.example"));
+        assert_eq!(config.java.model_namespace.as_deref(), Some("com.example"));
+        assert_eq!(config.java.free_function.as_deref(), Some("app_free"));
+        assert_eq!(config.java.owned_returns, Some(true));
+        assert_eq!(config.java.consts_class.as_deref(), Some("AppConstants"));
+        assert_eq!(
+            config.java.consts_namespace.as_deref(),
+            Some("com.example.consts")
+        );
+        assert!(config.java.type_map.is_empty());
+        assert_eq!(config.cpp.namespace.as_deref(), Some("com.example"));
+        assert_eq!(config.cpp.free_suffix.as_deref(), Some("_release"));
+        assert_eq!(config.swift.c_module.as_deref(), Some("CApp"));
+
+        let types = unwrap!(config.csharp.types_section);
+        assert_eq!(types.path.as_deref(), Some("Types.cs"));
+        assert_eq!(types.namespace.as_deref(), Some("com.example"));
+        let utils = unwrap!(config.csharp.utils_section);
+        assert_eq!(utils.namespace.as_deref(), Some("Example.Utils"));
+        assert_eq!(utils.class, None);
+    }
+
+    #[test]
+    fn model_namespace() {
+        let config = config_from_args(&["-l", "java", "--model-namespace", "com.example"]);
+        assert_eq!(config.java.namespace.as_deref(), Some("com.example"));
+        assert_eq!(config.java.model_namespace.as_deref(), Some("com.example"));
+
+        let config = config_from_args(&["-l", "java", "--lib", "app"]);
+        assert_eq!(config.java.namespace, None);
+        assert_eq!(config.java.model_namespace, None);
+    }
+
+    #[test]
+    fn safe_preset() {
+        let config = config_from_args(&["-l", "java", "--lib", "safe_app", "--preset", "safe"]);
+        assert_eq!(
+            config.java.namespace.as_deref(),
+            Some("net.maidsafe.safe_app")
+        );
+        assert_eq!(
+            config.java.model_namespace.as_deref(),
+            Some("net.maidsafe.safe_app")
+        );
+        assert_eq!(config.java.type_map.len(), SAFE_JAVA_TYPE_MAP.len());
+        assert_eq!(config.java.type_map["App"], java_type_from_str("long"));
+
+        let config = config_from_args(&[
+            "-l",
+            "java",
+            "--lib",
+            "safe_app",
+            "--preset",
+            "safe",
+            "--namespace",
+            "com.example",
+            "--type-map",
+            "App=int",
+        ]);
+        assert_eq!(config.java.model_namespace.as_deref(), Some("com.example"));
+        assert_eq!(config.java.type_map["App"], java_type_from_str("int"));
     }
 }
@@ -0,0 +1,186 @@
+//! Comparison of generated bindings with the files already on disk.
+
+use std::fmt::Write;
+
+/// Number of unchanged lines shown around each change.
+const CONTEXT: usize = 3;
+
+/// Renders the differences between `old` and `new` as a unified diff, as printed by `diff -u`.
+///
+/// `None` stands for a file which doesn't exist, so creating or removing an empty file results
+/// in a diff without hunks. Returns `None` if the contents are the same.
+pub fn unified_diff(
+    old_name: &str,
+    new_name: &str,
+    old: Option<&str>,
+    new: Option<&str>,
+) -> Option<String> {
+    if old == new {
+        return None;
+    }
+
+    // Each line together with its prefix and its line number in the old and the new file. Lines
+    // keep their terminator, so that a missing newline at the end of a file counts as a change.
+    let mut lines = Vec::new();
+    let (mut old_line, mut new_line) = (1, 1);
+    let old_lines: Vec<_> = old.unwrap_or_default().split_inclusive('\n').collect();
+    let new_lines: Vec<_> = new.unwrap_or_default().split_inclusive('\n').collect();
+    for result in diff::slice(&old_lines, &new_lines) {
+        let (prefix, &line) = match result {
+            diff::Result::Left(line) => ('-', line),
+            diff::Result::Right(line) => ('+', line),
+            diff::Result::Both(line, _) => (' ', line),
+        };
+        lines.push((prefix, line, old_line, new_line));
+        if prefix != '+' {
+            old_line += 1;
+        }
+        if prefix != '-' {
+            new_line += 1;
+        }
+    }
+
+    let mut output = String::new();
+    let _ = writeln!(output, "--- {}", old_name);
+    let _ = writeln!(output, "+++ {}", new_name);
+
+    let changes: Vec<usize> = (0..lines.len()).filter(|&i| lines[i].0 != ' ').collect();
+    let mut index = 0;
+    while index < changes.len() {
+        // Extend the hunk for as long as the next change is close enough to share context.
+        let start = changes[index].saturating_sub(CONTEXT);
+        let mut last = changes[index];
+        while index + 1 < changes.len() && changes[index + 1] - last <= 2 * CONTEXT {
+            index += 1;
+            last = changes[index];
+        }
+        let end = (last + CONTEXT + 1).min(lines.len());
+        index += 1;
+
+        let hunk = &lines[start..end];
+        let old_count = hunk.iter().filter(|line| line.0 != '+').count();
+        let new_count = hunk.iter().filter(|line| line.0 != '-').count();
+        let _ = writeln!(
+            output,
+            "@@ -{} +{} @@",
+            hunk_range(hunk[0].2, old_count),
+            hunk_range(hunk[0].3, new_count)
+        );
+        for &(prefix, line, _, _) in hunk {
+            match line.strip_suffix('\n') {
+                Some(line) => {
+                    let _ = writeln!(output, "{}{}", prefix, line);
+                }
+                None => {
+                    let _ = writeln!(output, "{}{}\n\\ No newline at end of file", prefix, line);
+                }
+            }
+        }
+    }
+
+    Some(output)
+}
+
+/// Formats the start and length of a hunk, omitting the length when it is 1.
+fn hunk_range(start: usize, count: usize) -> String {
+    match count {
+        // An empty range refers to the line before it.
+        0 => format!("{},0", start - 1),
+        1 => start.to_string(),
+        _ => format!("{},{}", start, count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Outputs;
+    use crate::Bindgen;
+    use std::env;
+    use std::fs;
+    use unwrap::unwrap;
+
+    #[test]
+    fn unified() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\n";
+
+        assert_eq!(unified_diff("old", "new", Some(old), Some(old)), None);
+        assert_eq!(
+            unwrap!(unified_diff("old", "new", Some(old), Some(new))),
+            "--- old\n\
+             +++ new\n\
+             @@ -1,5 +1,5 @@\n\
+             \x20a\n\
+             -b\n\
+             +B\n\
+             \x20c\n\
+             \x20d\n\
+             \x20e\n\
+             @@ -10,3 +10,4 @@\n\
+             \x20j\n\
+             \x20k\n\
+             \x20l\n\
+             +m\n"
+        );
+        assert_eq!(
+            unwrap!(unified_diff("/dev/null", "new", None, Some("a\n"))),
+            "--- /dev/null\n+++ new\n@@ -0,0 +1 @@\n+a\n"
+        );
+    }
+
+    #[test]
+    fn unified_newlines() {
+        assert_eq!(
+            unwrap!(unified_diff("old", "new", Some("a\nb\n"), Some("a\nb"))),
+            "--- old\n\
+             +++ new\n\
+             @@ -1,2 +1,2 @@\n\
+             \x20a\n\
+             -b\n\
+             +b\n\
+             \\ No newline at end of file\n"
+        );
+        // Creating or removing an empty file is a change as well.
+        assert_eq!(
+            unwrap!(unified_diff("/dev/null", "new", None, Some(""))),
+            "--- /dev/null\n+++ new\n"
+        );
+        assert_eq!(unified_diff("old", "new", Some(""), Some("")), None);
+    }
+
+    #[test]
+    fn check_outputs() {
+        let root = env::temp_dir().join("sn_bindgen_check_outputs");
+        let _ = fs::remove_dir_all(&root);
+
+        let bindgen = unwrap!(Bindgen::new());
+        let mut outputs = Outputs::default();
+        let _ = outputs.insert("a.h".to_string(), "int a;\n".to_string());
+        let _ = outputs.insert("nested/b.h".to_string(), "int b;\n".to_string());
+        unwrap!(bindgen.write_outputs(&root, &outputs));
+        assert!(unwrap!(bindgen.check_outputs(&root, &outputs)).is_empty());
+
+        let _ = outputs.insert("a.h".to_string(), "int c;\n".to_string());
+        let _ = outputs.insert("d.h".to_string(), "int d;\n".to_string());
+        let diffs = unwrap!(bindgen.check_outputs(&root, &outputs));
+        assert_eq!(
+            diffs,
+            vec![
+                format!(
+                    "--- {}\n+++ {0}\n@@ -1 +1 @@\n-int a;\n+int c;\n",
+                    root.join("a.h").display()
+                ),
+                format!(
+                    "--- /dev/null\n+++ {}\n@@ -0,0 +1 @@\n+int d;\n",
+                    root.join("d.h").display()
+                ),
+            ]
+        );
+        // Nothing is written in check mode.
+        assert_eq!(unwrap!(fs::read_to_string(root.join("a.h"))), "int a;\n");
+        assert!(!root.join("d.h").exists());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -1,8 +1,10 @@
 //! Functions common for all target languages.
 
-use crate::Error;
+use crate::{Config, Error, Level};
 use std::collections::hash_map::{Entry, HashMap};
+use std::convert::TryFrom;
 use syn::export::ToTokens;
+use syn::spanned::Spanned;
 use unwrap::unwrap;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -33,6 +35,10 @@ pub trait Lang {
         outputs: &mut Outputs,
     ) -> Result<(), Error>;
 
+    /// Declare a Rust enum before any item is converted, so that the conversion of the items
+    /// using it doesn't depend on the order of the declarations.
+    fn declare_enum(&mut self, _item: &syn::ItemEnum) {}
+
     /// Convert a Rust enum into a target language enum.
     fn parse_enum(
         &mut self,
@@ -59,6 +65,9 @@ pub trait Lang {
 
     /// Add extra and custom code after the code generation part is done.
     fn finalise_output(&mut self, _outputs: &mut Outputs) -> Result<(), Error>;
+
+    /// Apply the settings for this language from a configuration file.
+    fn apply_config(&mut self, _config: &Config) {}
 }
 
 /// Append or create new output file
@@ -248,6 +257,59 @@ pub fn check_repr_c(attr: &syn::Attribute) -> bool {
     }
 }
 
+/// Primitive representations an enum can have.
+const INT_REPRS: &[&str] = &[
+    "u8", "u16", "u32", "u64", "usize", "i8", "i16", "i32", "i64", "isize",
+];
+
+/// Layout of an enum, as given by its `#[repr(...)]` attributes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EnumRepr {
+    /// Whether the enum is `#[repr(C)]`.
+    pub c: bool,
+    /// Primitive integer type of the discriminant (e.g. `u8` for `#[repr(u8)]`), if any.
+    pub int: Option<String>,
+}
+
+impl EnumRepr {
+    /// Returns whether the enum has a layout which is defined for C.
+    pub fn is_defined(&self) -> bool {
+        self.c || self.int.is_some()
+    }
+}
+
+/// Collects the layout of an enum from all of its `#[repr(...)]` attributes, including the
+/// combined form `#[repr(C, u8)]`.
+pub fn enum_repr(attrs: &[syn::Attribute]) -> EnumRepr {
+    let mut repr = EnumRepr::default();
+
+    for attr in attrs {
+        if attr.path.clone().into_token_stream().to_string() != "repr" {
+            continue;
+        }
+        if let Ok(syn::Meta::List(ref list)) = attr.parse_meta() {
+            for nested in &list.nested {
+                if let syn::NestedMeta::Meta(syn::Meta::Word(ref word)) = *nested {
+                    match word.to_string().as_str() {
+                        "C" => repr.c = true,
+                        int if INT_REPRS.contains(&int) => repr.int = Some(int.to_string()),
+                        _ => (),
+                    }
+                }
+            }
+        }
+    }
+
+    repr
+}
+
+/// Returns whether any of the enum variants carries data.
+pub fn has_data_variants(item: &syn::ItemEnum) -> bool {
+    item.variants
+        .iter()
+        .any(|variant| variant.fields != syn::Fields::Unit)
+}
+
 /// If the attribute is a docstring, indent it the required amount and return it.
 pub fn retrieve_docstring(attr: &syn::Attribute, prepend: &str) -> Option<String> {
     match unwrap!(attr.parse_meta()) {
@@ -262,7 +324,9 @@ pub fn retrieve_docstring(attr: &syn::Attribute, prepend: &str) -> Option<String
         {
             match val.lit {
                 // Docstring attributes omit the trailing newline.
-                syn::Lit::Str(ref docs) => Some(format!("///{}{}", prepend, docs.value().as_str())),
+                syn::Lit::Str(ref docs) => {
+                    Some(format!("{}///{}\n", prepend, docs.value().as_str()))
+                }
                 _ => unreachable!("docs must be literal strings"),
             }
         }
@@ -272,10 +336,14 @@ pub fn retrieve_docstring(attr: &syn::Attribute, prepend: &str) -> Option<String
 
 /// Returns whether the calling convention of the function is compatible with C (i.e. `extern "C"`).
 pub fn is_extern(abi: syn::Abi) -> bool {
-    matches!(
-        unwrap!(abi.name).value().as_str(),
-        "C" | "Cdecl" | "Stdcall" | "Fastcall" | "System"
-    )
+    // A plain `extern` defaults to the C ABI.
+    match abi.name {
+        Some(name) => matches!(
+            name.value().as_str(),
+            "C" | "Cdecl" | "Stdcall" | "Fastcall" | "System"
+        ),
+        None => true,
+    }
 }
 
 /// Extracts the int literal from the expression, if it exists.
@@ -287,6 +355,54 @@ pub fn extract_int_literal(lit: &syn::ExprLit) -> Option<i64> {
     }
 }
 
+/// Computes the discriminants of the variants of an enum, numbering the variants without an
+/// explicit discriminant after the previous one like Rust does.
+///
+/// Only integer literals, possibly negated, are supported as explicit discriminants.
+pub fn enum_variant_values(item: &syn::ItemEnum) -> Result<Vec<i64>, Error> {
+    let mut values = Vec::new();
+    let mut value = 0;
+    for variant in &item.variants {
+        if let Some((_, ref expr)) = variant.discriminant {
+            value = discriminant_value(expr).ok_or_else(|| Error {
+                level: Level::Error,
+                span: Some(expr.span()),
+                file: None,
+                item: None,
+                message: format!(
+                    "bindgen cannot handle the discriminant of `{}::{}` (use an integer literal)",
+                    item.ident, variant.ident
+                ),
+            })?;
+        }
+        values.push(value);
+        value = value.wrapping_add(1);
+    }
+    Ok(values)
+}
+
+fn discriminant_value(expr: &syn::Expr) -> Option<i64> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(ref lit),
+            ..
+        }) => i64::try_from(lit.value()).ok(),
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            ref expr,
+            ..
+        }) => match **expr {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Int(ref lit),
+                ..
+            }) => 0i64.checked_sub_unsigned(lit.value()),
+            _ => None,
+        },
+        syn::Expr::Paren(ref paren) => discriminant_value(&paren.expr),
+        _ => None,
+    }
+}
+
 /// Extracts the enum variant value/discriminant, if it exists.
 pub fn extract_enum_variant_value(variant: &syn::Variant) -> Option<i64> {
     if let Some(ref expr) = variant.discriminant {
@@ -0,0 +1,830 @@
+//! Configuration of the code generators, read from `bindgen.toml` or from the
+//! `[package.metadata.bindgen]` table of the cargo manifest.
+//!
+//! ```toml
+//! lib_name = "safe_app"
+//! roots = ["ffi"]
+//!
+//! [c]
+//! opaque_types = ["Authenticator"]
+//! custom_code = "typedef struct App App;"
+//! custom_code_file = "include/custom.h"
+//! single_header = true
+//! guard_prefix = "SAFE_APP_"
+//! includes = ["stdint.h", "stdbool.h", "\"safe_core.h\""]
+//!
+//! [cpp]
+//! namespace = "safe::app"
+//!
+//! [java]
+//! namespace = "net.maidsafe.safe_app"
+//! model_namespace = "net.maidsafe.safe_app"
+//! free_function = "safe_app_free"
+//! owned_returns = true
+//! consts_class = "SafeAppConstants"
+//! blacklist = ["app_unregistered"]
+//!
+//! [java.type_map]
+//! XorNameArray = "byte[]"
+//! App = "long"
+//!
+//! [csharp]
+//! opaque_types = ["App"]
+//! whitelist = ["app_registered", "app_free"]
+//! wrapper_function_blacklist = ["app_free"]
+//! utils_enabled = false
+//!
+//! [csharp.interface_section]
+//! path = "IAppBindings.cs"
+//! namespace = "SafeApp.AppBindings"
+//! class = "IAppBindings"
+//!
+//! [[csharp.consts]]
+//! type = "ulong"
+//! name = "asym_key_len"
+//! value = 32
+//!
+//! [python]
+//! lib_name = "safe_app_py"
+//!
+//! [swift]
+//! c_module = "CSafeApp"
+//! ```
+//!
+//! Kotlin is configured by the `[java]` table, as its bindings are built on top of the Java ones.
+//! Unknown keys are rejected, so that typos don't go unnoticed.
+
+use crate::java::types::java_type_from_str;
+use crate::{Error, FilterMode, Level};
+use jni::signature::JavaType;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the configuration file, looked up next to the cargo manifest.
+pub const CONFIG_FILE: &str = "bindgen.toml";
+
+/// Settings for all language backends.
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    /// Name of the native library, unless overridden for a particular language.
+    pub lib_name: Option<String>,
+    /// Modules containing the FFI (e.g. `api::ffi`).
+    pub roots: Vec<String>,
+    /// Settings for `LangC`.
+    pub c: CConfig,
+    /// Settings for `LangCpp`, in addition to the ones for `LangC`.
+    pub cpp: CppConfig,
+    /// Settings for `LangJava`.
+    pub java: JavaConfig,
+    /// Settings for `LangCSharp`.
+    pub csharp: CSharpConfig,
+    /// Settings for `LangPython`.
+    pub python: PythonConfig,
+    /// Settings for `LangSwift`, in addition to the ones for `LangC`.
+    pub swift: SwiftConfig,
+    /// Files the configuration was read from, including custom code files.
+    pub files: Vec<PathBuf>,
+}
+
+/// Settings for `LangC`.
+#[derive(Clone, Debug, Default)]
+pub struct CConfig {
+    pub lib_name: Option<String>,
+    /// Types represented by opaque pointers, which are declared even if no binding uses them.
+    pub opaque_types: Vec<String>,
+    /// Code added to the top-level header, followed by the contents of `custom_code_file` if
+    /// one is configured.
+    pub custom_code: Option<String>,
+    /// Whether to generate a single header instead of one per module.
+    pub single_header: Option<bool>,
+    /// Prefix of the include-guard macros.
+    pub guard_prefix: Option<String>,
+    /// Headers included by every generated header, replacing the default ones.
+    pub includes: Option<Vec<String>>,
+}
+
+/// Settings for `LangCpp`.
+#[derive(Clone, Debug, Default)]
+pub struct CppConfig {
+    pub lib_name: Option<String>,
+    /// Namespace of the declarations.
+    pub namespace: Option<String>,
+    /// Suffix of the functions freeing handles.
+    pub free_suffix: Option<String>,
+}
+
+/// Settings for `LangPython`.
+#[derive(Clone, Debug, Default)]
+pub struct PythonConfig {
+    pub lib_name: Option<String>,
+}
+
+/// Settings for `LangSwift`.
+#[derive(Clone, Debug, Default)]
+pub struct SwiftConfig {
+    pub lib_name: Option<String>,
+    /// Name of the clang module of the C header.
+    pub c_module: Option<String>,
+}
+
+/// Settings for `LangJava`.
+#[derive(Clone, Debug, Default)]
+pub struct JavaConfig {
+    pub lib_name: Option<String>,
+    /// Namespace of the `NativeBindings` class.
+    pub namespace: Option<String>,
+    /// Namespace of the structures.
+    pub model_namespace: Option<String>,
+    /// Native function freeing the pointers returned by functions.
+    pub free_function: Option<String>,
+    /// Whether returned pointers are owned by the caller, and freed with `free_function`.
+    pub owned_returns: Option<bool>,
+    /// Name of the class holding the constants.
+    pub consts_class: Option<String>,
+    /// Namespace of the constants class.
+    pub consts_namespace: Option<String>,
+    /// Java types to use for Rust types.
+    pub type_map: BTreeMap<String, JavaType>,
+    pub filter: Option<Filter>,
+}
+
+/// Settings for `LangCSharp`.
+#[derive(Clone, Debug, Default)]
+pub struct CSharpConfig {
+    pub lib_name: Option<String>,
+    /// Types represented by opaque pointers.
+    pub opaque_types: Vec<String>,
+    pub filter: Option<Filter>,
+    /// Functions to not generate wrapper functions for.
+    pub wrapper_function_blacklist: Vec<String>,
+    pub consts_enabled: Option<bool>,
+    pub types_enabled: Option<bool>,
+    pub utils_enabled: Option<bool>,
+    pub interface_section: Option<SectionConfig>,
+    pub functions_section: Option<SectionConfig>,
+    pub consts_section: Option<SectionConfig>,
+    pub types_section: Option<SectionConfig>,
+    pub utils_section: Option<SectionConfig>,
+    /// Additional constants.
+    pub consts: Vec<ConstConfig>,
+}
+
+/// Identifiers to generate bindings for (`whitelist`) or to ignore (`blacklist`).
+#[derive(Clone, Debug)]
+pub struct Filter {
+    pub mode: FilterMode,
+    pub idents: Vec<String>,
+}
+
+/// Location of a C# output section. Missing fields keep their defaults.
+#[derive(Clone, Debug, Default)]
+pub struct SectionConfig {
+    pub path: Option<String>,
+    pub namespace: Option<String>,
+    pub class: Option<String>,
+}
+
+/// Additional C# constant.
+#[derive(Clone, Debug)]
+pub struct ConstConfig {
+    pub ty: String,
+    pub name: String,
+    /// The value as C# code.
+    pub value: String,
+}
+
+impl Config {
+    /// Loads the configuration for the crate in `dir`.
+    ///
+    /// `bindgen.toml` takes precedence over the `[package.metadata.bindgen]` table of the cargo
+    /// manifest `manifest`. If neither exists, the default configuration is returned.
+    pub fn load(dir: &Path, manifest: Option<&toml::Value>) -> Result<Self, Error> {
+        let path = dir.join(CONFIG_FILE);
+        if path.exists() {
+            return Self::from_file(&path);
+        }
+
+        let metadata = manifest
+            .and_then(|manifest| manifest.get("package"))
+            .and_then(|package| package.get("metadata"))
+            .and_then(|metadata| metadata.get("bindgen"));
+        match metadata {
+            Some(value) => {
+                Self::from_toml(value, &dir.join("Cargo.toml"), "package.metadata.bindgen")
+            }
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Reads the configuration from a `bindgen.toml` file.
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let content = fs::read_to_string(path).map_err(|err| Error::unreadable_file(path, &err))?;
+        let value = content.parse::<toml::Value>().map_err(|err| Error {
+            level: Level::Fatal,
+            span: None,
+            file: Some(path.to_path_buf()),
+            item: None,
+            message: format!("could not parse `{}`: {}", path.display(), err),
+        })?;
+        Self::from_toml(&value, path, "")
+    }
+
+    /// Reads the configuration from the table `value`, found at `key` in the file `path`.
+    fn from_toml(value: &toml::Value, path: &Path, key: &str) -> Result<Self, Error> {
+        let table = Table::new(value, path, key)?;
+        if table.table.contains_key("kotlin") {
+            return Err(Error {
+                level: Level::Error,
+                span: None,
+                file: Some(path.to_path_buf()),
+                item: None,
+                message: format!(
+                    "unknown key `{}` (Kotlin is configured by the `{}` table)",
+                    table.path("kotlin"),
+                    table.path("java")
+                ),
+            });
+        }
+        table.deny_unknown_keys(&[
+            "lib_name", "roots", "c", "cpp", "java", "csharp", "python", "swift",
+        ])?;
+        let mut files = vec![path.to_path_buf()];
+
+        let c = match table.table("c")? {
+            Some(c) => {
+                c.deny_unknown_keys(&[
+                    "lib_name",
+                    "opaque_types",
+                    "custom_code",
+                    "custom_code_file",
+                    "single_header",
+                    "guard_prefix",
+                    "includes",
+                ])?;
+                let mut custom_code = c.string("custom_code")?;
+                // Paths are relative to the configuration file.
+                if let Some(file) = c.string("custom_code_file")? {
+                    let file = path.parent().unwrap_or_else(|| Path::new("")).join(file);
+                    let code = fs::read_to_string(&file)
+                        .map_err(|err| Error::unreadable_file(&file, &err))?;
+                    custom_code = Some(custom_code.unwrap_or_default() + &code);
+                    files.push(file);
+                }
+                CConfig {
+                    lib_name: c.string("lib_name")?,
+                    opaque_types: c.strings("opaque_types")?,
+                    custom_code,
+                    single_header: c.boolean("single_header")?,
+                    guard_prefix: c.string("guard_prefix")?,
+                    includes: if c.table.contains_key("includes") {
+                        Some(c.strings("includes")?)
+                    } else {
+                        None
+                    },
+                }
+            }
+            None => CConfig::default(),
+        };
+
+        let cpp = match table.table("cpp")? {
+            Some(cpp) => {
+                cpp.deny_unknown_keys(&["lib_name", "namespace", "free_suffix"])?;
+                CppConfig {
+                    lib_name: cpp.string("lib_name")?,
+                    namespace: cpp.string("namespace")?,
+                    free_suffix: cpp.string("free_suffix")?,
+                }
+            }
+            None => CppConfig::default(),
+        };
+
+        let java = match table.table("java")? {
+            Some(java) => {
+                java.deny_unknown_keys(&[
+                    "lib_name",
+                    "namespace",
+                    "model_namespace",
+                    "free_function",
+                    "owned_returns",
+                    "consts_class",
+                    "consts_namespace",
+                    "type_map",
+                    "whitelist",
+                    "blacklist",
+                ])?;
+                JavaConfig {
+                    lib_name: java.string("lib_name")?,
+                    namespace: java.string("namespace")?,
+                    model_namespace: java.string("model_namespace")?,
+                    free_function: java.string("free_function")?,
+                    owned_returns: java.boolean("owned_returns")?,
+                    consts_class: java.string("consts_class")?,
+                    consts_namespace: java.string("consts_namespace")?,
+                    type_map: match java.table("type_map")? {
+                        Some(type_map) => type_map
+                            .table
+                            .iter()
+                            .map(|(name, value)| match value {
+                                toml::Value::String(ty) => {
+                                    Ok((name.clone(), java_type_from_str(ty)))
+                                }
+                                _ => Err(invalid_value(path, &type_map.path(name), "a string")),
+                            })
+                            .collect::<Result<_, Error>>()?,
+                        None => BTreeMap::new(),
+                    },
+                    filter: java.filter()?,
+                }
+            }
+            None => JavaConfig::default(),
+        };
+
+        let csharp = match table.table("csharp")? {
+            Some(csharp) => {
+                csharp.deny_unknown_keys(&[
+                    "lib_name",
+                    "opaque_types",
+                    "whitelist",
+                    "blacklist",
+                    "wrapper_function_blacklist",
+                    "consts_enabled",
+                    "types_enabled",
+                    "utils_enabled",
+                    "interface_section",
+                    "functions_section",
+                    "consts_section",
+                    "types_section",
+                    "utils_section",
+                    "consts",
+                ])?;
+                CSharpConfig {
+                    lib_name: csharp.string("lib_name")?,
+                    opaque_types: csharp.strings("opaque_types")?,
+                    filter: csharp.filter()?,
+                    wrapper_function_blacklist: csharp.strings("wrapper_function_blacklist")?,
+                    consts_enabled: csharp.boolean("consts_enabled")?,
+                    types_enabled: csharp.boolean("types_enabled")?,
+                    utils_enabled: csharp.boolean("utils_enabled")?,
+                    interface_section: csharp.section("interface_section")?,
+                    functions_section: csharp.section("functions_section")?,
+                    consts_section: csharp.section("consts_section")?,
+                    types_section: csharp.section("types_section")?,
+                    utils_section: csharp.section("utils_section")?,
+                    consts: csharp.consts("consts")?,
+                }
+            }
+            None => CSharpConfig::default(),
+        };
+
+        let python = match table.table("python")? {
+            Some(python) => {
+                python.deny_unknown_keys(&["lib_name"])?;
+                PythonConfig {
+                    lib_name: python.string("lib_name")?,
+                }
+            }
+            None => PythonConfig::default(),
+        };
+
+        let swift = match table.table("swift")? {
+            Some(swift) => {
+                swift.deny_unknown_keys(&["lib_name", "c_module"])?;
+                SwiftConfig {
+                    lib_name: swift.string("lib_name")?,
+                    c_module: swift.string("c_module")?,
+                }
+            }
+            None => SwiftConfig::default(),
+        };
+
+        Ok(Config {
+            lib_name: table.string("lib_name")?,
+            roots: table.strings("roots")?,
+            c,
+            cpp,
+            java,
+            csharp,
+            python,
+            swift,
+            files,
+        })
+    }
+
+    /// Name of the native library, given the one set for a particular language.
+    pub(crate) fn lib_name_or<'a>(&'a self, lang_lib_name: &'a Option<String>) -> Option<&'a str> {
+        lang_lib_name.as_deref().or(self.lib_name.as_deref())
+    }
+}
+
+/// A TOML table together with its location, for error reporting.
+struct Table<'a> {
+    table: &'a toml::value::Table,
+    file: &'a Path,
+    key: String,
+}
+
+impl<'a> Table<'a> {
+    fn new(value: &'a toml::Value, file: &'a Path, key: &str) -> Result<Self, Error> {
+        match value.as_table() {
+            Some(table) => Ok(Table {
+                table,
+                file,
+                key: key.to_string(),
+            }),
+            None => Err(invalid_value(file, key, "a table")),
+        }
+    }
+
+    /// Full name of the key `key` in this table.
+    fn path(&self, key: &str) -> String {
+        if self.key.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", self.key, key)
+        }
+    }
+
+    fn table(&self, key: &str) -> Result<Option<Table<'a>>, Error> {
+        match self.table.get(key) {
+            Some(value) => Table::new(value, self.file, &self.path(key)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Rejects the first key of the table which isn't one of `known`.
+    fn deny_unknown_keys(&self, known: &[&str]) -> Result<(), Error> {
+        match self.table.keys().find(|key| !known.contains(&key.as_str())) {
+            Some(key) => Err(Error {
+                level: Level::Error,
+                span: None,
+                file: Some(self.file.to_path_buf()),
+                item: None,
+                message: format!("unknown key `{}`", self.path(key)),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn string(&self, key: &str) -> Result<Option<String>, Error> {
+        match self.table.get(key) {
+            Some(toml::Value::String(value)) => Ok(Some(value.clone())),
+            Some(_) => Err(invalid_value(self.file, &self.path(key), "a string")),
+            None => Ok(None),
+        }
+    }
+
+    fn boolean(&self, key: &str) -> Result<Option<bool>, Error> {
+        match self.table.get(key) {
+            Some(toml::Value::Boolean(value)) => Ok(Some(*value)),
+            Some(_) => Err(invalid_value(self.file, &self.path(key), "a boolean")),
+            None => Ok(None),
+        }
+    }
+
+    fn strings(&self, key: &str) -> Result<Vec<String>, Error> {
+        let values = match self.table.get(key) {
+            Some(toml::Value::Array(values)) => values,
+            Some(_) => {
+                return Err(invalid_value(
+                    self.file,
+                    &self.path(key),
+                    "an array of strings",
+                ))
+            }
+            None => return Ok(Vec::new()),
+        };
+        values
+            .iter()
+            .map(|value| match value {
+                toml::Value::String(value) => Ok(value.clone()),
+                _ => Err(invalid_value(
+                    self.file,
+                    &self.path(key),
+                    "an array of strings",
+                )),
+            })
+            .collect()
+    }
+
+    /// Reads a filter given as either `whitelist` or `blacklist`.
+    fn filter(&self) -> Result<Option<Filter>, Error> {
+        match (self.table.get("whitelist"), self.table.get("blacklist")) {
+            (Some(_), Some(_)) => Err(Error {
+                level: Level::Error,
+                span: None,
+                file: Some(self.file.to_path_buf()),
+                item: None,
+                message: format!(
+                    "`{}` and `{}` cannot be used together",
+                    self.path("whitelist"),
+                    self.path("blacklist")
+                ),
+            }),
+            (Some(_), None) => Ok(Some(Filter {
+                mode: FilterMode::Whitelist,
+                idents: self.strings("whitelist")?,
+            })),
+            (None, Some(_)) => Ok(Some(Filter {
+                mode: FilterMode::Blacklist,
+                idents: self.strings("blacklist")?,
+            })),
+            (None, None) => Ok(None),
+        }
+    }
+
+    fn section(&self, key: &str) -> Result<Option<SectionConfig>, Error> {
+        match self.table(key)? {
+            Some(section) => {
+                section.deny_unknown_keys(&["path", "namespace", "class"])?;
+                Ok(Some(SectionConfig {
+                    path: section.string("path")?,
+                    namespace: section.string("namespace")?,
+                    class: section.string("class")?,
+                }))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn consts(&self, key: &str) -> Result<Vec<ConstConfig>, Error> {
+        let values = match self.table.get(key) {
+            Some(toml::Value::Array(values)) => values,
+            Some(_) => {
+                return Err(invalid_value(
+                    self.file,
+                    &self.path(key),
+                    "an array of tables",
+                ))
+            }
+            None => return Ok(Vec::new()),
+        };
+        values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let table =
+                    Table::new(value, self.file, &format!("{}[{}]", self.path(key), index))?;
+                table.deny_unknown_keys(&["type", "name", "value"])?;
+                let required = |field: &str| {
+                    table
+                        .string(field)?
+                        .ok_or_else(|| invalid_value(self.file, &table.path(field), "a string"))
+                };
+                let value = match table.table.get("value") {
+                    Some(toml::Value::String(value)) => value.clone(),
+                    Some(toml::Value::Integer(value)) => value.to_string(),
+                    Some(toml::Value::Float(value)) => value.to_string(),
+                    Some(toml::Value::Boolean(value)) => value.to_string(),
+                    _ => {
+                        return Err(invalid_value(
+                            self.file,
+                            &table.path("value"),
+                            "a string, number or boolean",
+                        ))
+                    }
+                };
+                Ok(ConstConfig {
+                    ty: required("type")?,
+                    name: required("name")?,
+                    value,
+                })
+            })
+            .collect()
+    }
+}
+
+fn invalid_value(file: &Path, key: &str, expected: &str) -> Error {
+    Error {
+        level: Level::Error,
+        span: None,
+        file: Some(PathBuf::from(file)),
+        item: None,
+        message: format!("invalid value for `{}` (expected {})", key, expected),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{Lang, Outputs};
+    use crate::LangC;
+    use jni::signature::Primitive;
+    use std::env;
+    use unwrap::unwrap;
+
+    fn parse(source: &str) -> Result<Config, Error> {
+        let value = unwrap!(source.parse::<toml::Value>());
+        Config::from_toml(&value, Path::new("bindgen.toml"), "")
+    }
+
+    #[test]
+    fn full() {
+        let config = unwrap!(parse(
+            r#"
+            lib_name = "safe_app"
+            roots = ["api::ffi"]
+
+            [c]
+            opaque_types = ["Authenticator"]
+            custom_code = "typedef struct App App;"
+            single_header = true
+            includes = ["stdint.h", '"types.h"']
+
+            [cpp]
+            namespace = "safe::app"
+
+            [java]
+            lib_name = "safe_app_jni"
+            namespace = "net.maidsafe.safe_app"
+            free_function = "app_free"
+            owned_returns = true
+            consts_class = "AppConstants"
+            whitelist = ["app_registered"]
+
+            [java.type_map]
+            XorNameArray = "byte[]"
+            App = "long"
+
+            [csharp]
+            opaque_types = ["App"]
+            blacklist = ["app_free"]
+            utils_enabled = false
+
+            [csharp.types_section]
+            path = "AppTypes.cs"
+
+            [[csharp.consts]]
+            type = "ulong"
+            name = "asym_key_len"
+            value = 32
+
+            [python]
+            lib_name = "safe_app_py"
+
+            [swift]
+            c_module = "CSafeApp"
+            "#
+        ));
+
+        assert_eq!(config.lib_name_or(&config.c.lib_name), Some("safe_app"));
+        assert_eq!(
+            config.lib_name_or(&config.java.lib_name),
+            Some("safe_app_jni")
+        );
+        assert_eq!(config.roots, vec!["api::ffi"]);
+        assert_eq!(
+            config.c.custom_code.as_deref(),
+            Some("typedef struct App App;")
+        );
+        assert_eq!(config.c.opaque_types, vec!["Authenticator"]);
+        assert_eq!(config.c.single_header, Some(true));
+        assert_eq!(config.c.guard_prefix, None);
+        assert_eq!(
+            config.c.includes,
+            Some(vec!["stdint.h".to_string(), "\"types.h\"".to_string()])
+        );
+
+        assert_eq!(config.cpp.namespace.as_deref(), Some("safe::app"));
+        assert_eq!(config.cpp.free_suffix, None);
+
+        assert_eq!(
+            config.lib_name_or(&config.python.lib_name),
+            Some("safe_app_py")
+        );
+        assert_eq!(config.swift.c_module.as_deref(), Some("CSafeApp"));
+
+        assert_eq!(
+            config.java.namespace.as_deref(),
+            Some("net.maidsafe.safe_app")
+        );
+        assert_eq!(config.java.model_namespace, None);
+        assert_eq!(config.java.free_function.as_deref(), Some("app_free"));
+        assert_eq!(config.java.owned_returns, Some(true));
+        assert_eq!(config.java.consts_class.as_deref(), Some("AppConstants"));
+        assert_eq!(config.java.consts_namespace, None);
+        assert_eq!(
+            config.java.type_map["XorNameArray"],
+            JavaType::Array(Box::new(JavaType::Primitive(Primitive::Byte)))
+        );
+        assert_eq!(
+            config.java.type_map["App"],
+            JavaType::Primitive(Primitive::Long)
+        );
+        let filter = unwrap!(config.java.filter);
+        assert_eq!(filter.mode, FilterMode::Whitelist);
+        assert_eq!(filter.idents, vec!["app_registered"]);
+
+        assert_eq!(config.csharp.opaque_types, vec!["App"]);
+        assert_eq!(unwrap!(config.csharp.filter).mode, FilterMode::Blacklist);
+        assert_eq!(config.csharp.utils_enabled, Some(false));
+        assert_eq!(config.csharp.consts_enabled, None);
+        let section = unwrap!(config.csharp.types_section);
+        assert_eq!(section.path.as_deref(), Some("AppTypes.cs"));
+        assert_eq!(section.namespace, None);
+        assert_eq!(config.csharp.consts[0].ty, "ulong");
+        assert_eq!(config.csharp.consts[0].name, "asym_key_len");
+        assert_eq!(config.csharp.consts[0].value, "32");
+    }
+
+    #[test]
+    fn invalid() {
+        let messages: Vec<_> = [
+            "lib_name = 1",
+            "[java.type_map]\nApp = 1",
+            "[csharp]\nwhitelist = []\nblacklist = []",
+            "[[csharp.consts]]\ntype = \"int\"\nvalue = 1",
+            "lib_nme = \"safe_app\"",
+            "[java]\nnamespce = \"net.maidsafe\"",
+            "[csharp.types_section]\nnamespace = \"App\"\nclas = \"Types\"",
+            "[[csharp.consts]]\ntype = \"int\"\nname = \"x\"\nvalue = 1\nvalu = 2",
+            "[kotlin]\nnamespace = \"net.maidsafe\"",
+        ]
+        .iter()
+        .map(|source| match parse(source) {
+            Ok(_) => panic!("`{}` should be rejected", source),
+            Err(error) => error.message,
+        })
+        .collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "invalid value for `lib_name` (expected a string)",
+                "invalid value for `java.type_map.App` (expected a string)",
+                "`csharp.whitelist` and `csharp.blacklist` cannot be used together",
+                "invalid value for `csharp.consts[0].name` (expected a string)",
+                "unknown key `lib_nme`",
+                "unknown key `java.namespce`",
+                "unknown key `csharp.types_section.clas`",
+                "unknown key `csharp.consts[0].valu`",
+                "unknown key `kotlin` (Kotlin is configured by the `java` table)",
+            ]
+        );
+    }
+
+    #[test]
+    fn load() {
+        let dir = env::temp_dir().join("sn_bindgen_config_load");
+        let _ = fs::remove_dir_all(&dir);
+        unwrap!(fs::create_dir_all(&dir));
+
+        let manifest = unwrap!(r#"
+            [package]
+            name = "safe_app"
+
+            [package.metadata.bindgen]
+            lib_name = "from_manifest"
+            "#
+        .parse::<toml::Value>());
+
+        assert_eq!(unwrap!(Config::load(&dir, None)).lib_name, None);
+        assert_eq!(
+            unwrap!(Config::load(&dir, Some(&manifest)))
+                .lib_name
+                .as_deref(),
+            Some("from_manifest")
+        );
+
+        unwrap!(fs::write(
+            dir.join(CONFIG_FILE),
+            "lib_name = \"from_file\"\n[c]\ncustom_code = \"typedef struct App App;\"\n\
+             custom_code_file = \"include/custom.h\""
+        ));
+        unwrap!(fs::create_dir_all(dir.join("include")));
+        unwrap!(fs::write(
+            dir.join("include/custom.h"),
+            "\ntypedef int Handle;"
+        ));
+        let config = unwrap!(Config::load(&dir, Some(&manifest)));
+        assert_eq!(config.lib_name.as_deref(), Some("from_file"));
+        assert_eq!(
+            config.files,
+            vec![dir.join(CONFIG_FILE), dir.join("include/custom.h")]
+        );
+
+        let mut lang = LangC::new();
+        lang.apply_config(&config);
+        let mut outputs = Outputs::default();
+        unwrap!(lang.finalise_output(&mut outputs));
+        let header = unwrap!(outputs.get("from_file.h"));
+        assert!(header.contains("typedef struct App App;\ntypedef int Handle;"));
+
+        let _ = fs::remove_dir_all(&dir);
+        unwrap!(fs::create_dir_all(&dir));
+        let manifest = unwrap!(r#"
+            [package.metadata.bindgen]
+            lib_nam = "from_manifest"
+            "#
+        .parse::<toml::Value>());
+        match Config::load(&dir, Some(&manifest)) {
+            Ok(_) => panic!("unknown keys of the manifest table should be rejected"),
+            Err(error) => assert_eq!(
+                error.message,
+                "unknown key `package.metadata.bindgen.lib_nam`"
+            ),
+        }
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
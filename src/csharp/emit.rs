@@ -226,7 +226,12 @@ pub fn emit_const(writer: &mut IndentedWriter, context: &Context, name: &str, it
 }
 
 pub fn emit_enum(writer: &mut IndentedWriter, context: &Context, name: &str, item: &Enum) {
-    emitln!(writer, "public enum {}\n{{", name);
+    emit!(writer, "public enum {}", name);
+    if let Some(ref ty) = item.repr {
+        emit!(writer, " : ");
+        emit_enum_base_type(writer, ty);
+    }
+    emitln!(writer, "\n{{");
     writer.indent();
 
     for variant in &item.variants {
@@ -243,6 +248,100 @@ pub fn emit_enum(writer: &mut IndentedWriter, context: &Context, name: &str, ite
     emitln!(writer, "}}\n");
 }
 
+/// Emits an enum with data-carrying variants as a tag enum, a struct for the fields of every
+/// variant and a struct overlapping those, following the layout of RFC 2195.
+pub fn emit_tagged_union(writer: &mut IndentedWriter, context: &Context, name: &str, item: &Enum) {
+    emit!(writer, "public enum {}Tag", name);
+    if let Some(ref ty) = item.repr {
+        emit!(writer, " : ");
+        emit_enum_base_type(writer, ty);
+    }
+    emitln!(writer, "\n{{");
+    writer.indent();
+
+    for variant in &item.variants {
+        emit_docs(writer, context, &variant.docs);
+
+        if let Some(value) = variant.value {
+            emitln!(writer, "{} = {},", variant.name, value);
+        } else {
+            emitln!(writer, "{},", variant.name);
+        }
+    }
+
+    writer.unindent();
+    emitln!(writer, "}}\n");
+
+    let variants: Vec<_> = item
+        .variants
+        .iter()
+        .filter(|variant| !variant.fields.is_empty())
+        .collect();
+
+    for variant in &variants {
+        emitln!(writer, "public struct {}{}\n{{", name, variant.name);
+        writer.indent();
+
+        // Without `#[repr(C)]` every variant starts with the tag.
+        if !item.repr_c {
+            emitln!(writer, "public {}Tag Tag;", name);
+        }
+        for field in &variant.fields {
+            emit_docs(writer, context, &field.docs);
+            emit_struct_field(writer, context, field, StructMode::Normal);
+        }
+
+        writer.unindent();
+        emitln!(writer, "}}\n");
+    }
+
+    let union_name = if item.repr_c {
+        format!("{}Payload", name)
+    } else {
+        name.to_string()
+    };
+
+    emitln!(writer, "[StructLayout(LayoutKind.Explicit)]");
+    emitln!(writer, "public struct {}\n{{", union_name);
+    writer.indent();
+
+    if !item.repr_c {
+        emitln!(writer, "[FieldOffset(0)]");
+        emitln!(writer, "public {}Tag Tag;", name);
+    }
+    for variant in &variants {
+        emitln!(writer, "[FieldOffset(0)]");
+        emitln!(writer, "public {0}{1} {1};", name, variant.name);
+    }
+
+    writer.unindent();
+    emitln!(writer, "}}\n");
+
+    if item.repr_c {
+        emitln!(writer, "public struct {}\n{{", name);
+        writer.indent();
+        emitln!(writer, "public {}Tag Tag;", name);
+        emitln!(writer, "public {} Payload;", union_name);
+        writer.unindent();
+        emitln!(writer, "}}\n");
+    }
+}
+
+// Emits the underlying type of an enum, which has to be an integral type.
+fn emit_enum_base_type(writer: &mut IndentedWriter, ty: &Type) {
+    match *ty {
+        Type::I8 => emit!(writer, "sbyte"),
+        Type::I16 => emit!(writer, "short"),
+        Type::I32 => emit!(writer, "int"),
+        Type::I64 | Type::ISize => emit!(writer, "long"),
+        Type::U8 => emit!(writer, "byte"),
+        Type::U16 => emit!(writer, "ushort"),
+        Type::U32 => emit!(writer, "uint"),
+        Type::U64 | Type::USize => emit!(writer, "ulong"),
+        _ => unreachable!("enums can only be represented by integers"),
+    }
+}
+
 pub fn emit_normal_struct(
     writer: &mut IndentedWriter,
     context: &Context,
@@ -98,13 +98,29 @@ pub struct StructField {
 }
 
 pub struct Enum {
+    /// Type of the discriminant, if given by a primitive representation (e.g. `#[repr(u8)]`).
+    pub repr: Option<Type>,
+    /// Whether the enum is `#[repr(C)]`, in which case the tag of a data-carrying enum is
+    /// followed by the variant fields instead of being part of every variant.
+    pub repr_c: bool,
     pub variants: Vec<EnumVariant>,
 }
 
+impl Enum {
+    /// Returns whether any of the variants carries data, making the enum a tagged union.
+    pub fn has_data(&self) -> bool {
+        self.variants
+            .iter()
+            .any(|variant| !variant.fields.is_empty())
+    }
+}
+
 pub struct EnumVariant {
     pub docs: String,
     pub name: String,
     pub value: Option<i64>,
+    /// Fields of a data-carrying variant. Fields of tuple variants are named `item0`, `item1`...
+    pub fields: Vec<StructField>,
 }
 
 pub fn transform_type(input: &syn::Type) -> Option<Type> {
@@ -258,24 +274,57 @@ pub fn transform_const(ty: &syn::Type, value: &syn::Expr) -> Option<Const> {
     Some(Const { ty, value })
 }
 
-pub fn transform_enum(variants: &[syn::Variant]) -> Option<Enum> {
+pub fn transform_enum(variants: &[syn::Variant], repr: &common::EnumRepr) -> Option<Enum> {
     let variants: Option<Vec<_>> = variants
         .iter()
         .map(|variant| {
-            match variant.fields {
-                syn::Fields::Unit => (),
-                _ => return None,
-            }
-
             let (_, docs) = common::parse_attr(&variant.attrs[..], |_| true, retrieve_docstring);
             let name = variant.ident.to_string();
             let value = common::extract_enum_variant_value(variant);
 
-            Some(EnumVariant { docs, name, value })
+            let fields: Option<Vec<_>> = variant
+                .fields
+                .iter()
+                .enumerate()
+                .map(|(index, field)| {
+                    let (_, docs) =
+                        common::parse_attr(&field.attrs[..], |_| true, retrieve_docstring);
+                    let name = match field.ident {
+                        Some(ref ident) => ident.to_string(),
+                        None => format!("item{}", index),
+                    };
+                    let ty = try_opt!(transform_type(&field.ty));
+
+                    Some(StructField {
+                        docs,
+                        name,
+                        ty,
+                        has_cap: false,
+                    })
+                })
+                .collect();
+            let fields = try_opt!(fields);
+
+            Some(EnumVariant {
+                docs,
+                name,
+                value,
+                fields,
+            })
         })
         .collect();
+    let repr_ty = match repr.int {
+        Some(ref int) => Some(try_opt!(transform_path(
+            &try_opt!(syn::parse_str(int).ok())
+        ))),
+        None => None,
+    };
 
-    variants.map(|variants| Enum { variants })
+    variants.map(|variants| Enum {
+        repr: repr_ty,
+        repr_c: repr.c,
+        variants,
+    })
 }
 
 pub fn transform_struct(fields: syn::Fields) -> Option<Struct> {
@@ -1,19 +1,21 @@
 #[macro_use]
 mod emit;
-mod intermediate;
+pub(crate) mod intermediate;
 #[cfg(test)]
 mod tests;
 
 use self::emit::*;
 use self::intermediate::*;
 use crate::common::{self, FilterMode, Lang, Outputs};
+use crate::config::SectionConfig;
 use crate::output::IndentedWriter;
-use crate::{Error, Level};
+use crate::{Config, Error, Level};
 use inflector::Inflector;
 use std::collections::btree_map::Entry;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::{Display, Write};
 use std::mem;
+use syn::spanned::Spanned;
 use unwrap::unwrap;
 
 const INDENT_WIDTH: usize = 4;
@@ -71,6 +73,18 @@ pub struct Section {
 }
 
 impl Section {
+    fn apply_config(&mut self, config: &SectionConfig) {
+        if let Some(ref path) = config.path {
+            self.path = path.clone();
+        }
+        if let Some(ref namespace) = config.namespace {
+            self.namespace = namespace.clone();
+        }
+        if let Some(ref class) = config.class {
+            self.class = class.clone();
+        }
+    }
+
     fn new<P, N, C>(path: P, namespace: N, class: C) -> Self
     where
         P: Into<String>,
@@ -236,6 +250,14 @@ impl LangCSharp {
             }
         }
 
+        for snippet in &mut self.enums {
+            for variant in &mut snippet.item.variants {
+                for field in &mut variant.fields {
+                    resolve_alias(&self.aliases, &mut field.ty);
+                }
+            }
+        }
+
         for snippet in &mut self.functions {
             resolve_alias(&self.aliases, &mut snippet.item.output);
 
@@ -289,6 +311,54 @@ impl Default for LangCSharp {
 }
 
 impl Lang for LangCSharp {
+    fn apply_config(&mut self, config: &Config) {
+        let csharp = &config.csharp;
+
+        if let Some(lib_name) = config.lib_name_or(&csharp.lib_name) {
+            self.set_lib_name(lib_name);
+        }
+        for name in &csharp.opaque_types {
+            self.add_opaque_type(name.as_str());
+        }
+        if let Some(ref filter) = csharp.filter {
+            self.reset_filter(filter.mode);
+            for ident in &filter.idents {
+                self.filter(ident.as_str());
+            }
+        }
+        for ident in &csharp.wrapper_function_blacklist {
+            self.blacklist_wrapper_function(ident.as_str());
+        }
+
+        if let Some(enabled) = csharp.consts_enabled {
+            self.set_consts_enabled(enabled);
+        }
+        if let Some(enabled) = csharp.types_enabled {
+            self.set_types_enabled(enabled);
+        }
+        if let Some(enabled) = csharp.utils_enabled {
+            self.set_utils_enabled(enabled);
+        }
+
+        let context = &mut self.context;
+        let sections = vec![
+            (&mut context.interface_section, &csharp.interface_section),
+            (&mut context.functions_section, &csharp.functions_section),
+            (&mut context.consts_section, &csharp.consts_section),
+            (&mut context.types_section, &csharp.types_section),
+            (&mut context.utils_section, &csharp.utils_section),
+        ];
+        for (section, section_config) in sections {
+            if let Some(ref section_config) = *section_config {
+                section.apply_config(section_config);
+            }
+        }
+
+        for constant in &csharp.consts {
+            self.add_const(&constant.ty, &constant.name, &constant.value);
+        }
+    }
+
     fn parse_ty(
         &mut self,
         item: &syn::ItemType,
@@ -300,17 +370,16 @@ impl Lang for LangCSharp {
             return Ok(());
         }
 
+        // Parameterized type aliases are not supported and reported as skipped.
         if !item.generics.params.is_empty() {
-            println!(
-                "parameterized type aliases not supported ({}). Skipping.",
-                name,
-            );
             return Ok(());
         }
 
         let ty = transform_type(&*item.ty).ok_or_else(|| Error {
             level: Level::Error,
-            span: None, //NONE FOR NOW
+            span: Some(item.ty.span()),
+            file: None,
+            item: None,
             message: format!("bindgen cannot handle the type `{}`", name),
         })?;
 
@@ -333,7 +402,9 @@ impl Lang for LangCSharp {
         let docs = common::parse_attr(&item.attrs, |_| true, retrieve_docstring).1;
         let item = transform_const(&*item.ty, &*item.expr).ok_or_else(|| Error {
             level: Level::Error,
-            span: None, //NONE FOR NOW
+            span: Some(item.ident.span()),
+            file: None,
+            item: None,
             message: format!("bindgen cannot handle constant {}", name),
         })?;
 
@@ -344,10 +415,13 @@ impl Lang for LangCSharp {
 
     /// Converts a Rust enum into a C# enum.
     ///
-    /// The Rust enum must be marked with `#[repr(C)]` and must be public otherwise the function
-    /// will abort.
+    /// The Rust enum must be marked with `#[repr(C)]` or a primitive representation (e.g.
+    /// `#[repr(u8)]`) and must be public otherwise the function will abort. The primitive
+    /// representation becomes the underlying type of the C# enum. Enums with data-carrying
+    /// variants become structs with an explicit layout, overlapping the fields of the variants.
     ///
-    /// Bindgen will error if the enum is generic or if it contains non-unit variants.
+    /// Bindgen will error if the enum is generic or if a variant has fields which can't be
+    /// overlapped (strings, arrays or functions).
     fn parse_enum(
         &mut self,
         item: &syn::ItemEnum,
@@ -359,11 +433,11 @@ impl Lang for LangCSharp {
             return Ok(());
         }
 
-        let (repr_c, docs) =
-            common::parse_attr(&item.attrs[..], common::check_repr_c, retrieve_docstring);
+        let (_, docs) = common::parse_attr(&item.attrs[..], |_| true, retrieve_docstring);
 
-        // If it's not #[repr(C)] ignore it.
-        if !repr_c {
+        // If it has neither `#[repr(C)]` nor a primitive representation ignore it.
+        let repr = common::enum_repr(&item.attrs);
+        if !repr.is_defined() {
             return Ok(());
         }
 
@@ -372,12 +446,34 @@ impl Lang for LangCSharp {
             return Err(Error::unsupported_generics_error("enums"));
         }
         let vars: Vec<_> = item.to_owned().variants.into_iter().collect();
-        let item = transform_enum(vars.as_slice()).ok_or_else(|| Error {
+        let enum_item = transform_enum(vars.as_slice(), &repr).ok_or_else(|| Error {
             level: Level::Error,
-            span: None, //NONE FOR NOW
+            span: Some(item.ident.span()),
+            file: None,
+            item: None,
             message: format!("bindgen cannot handle enum {}", item.ident.to_string()),
         })?;
 
+        // Managed types can't share their memory with other fields.
+        for variant in &enum_item.variants {
+            for field in &variant.fields {
+                if let Type::String | Type::Array(..) | Type::Function(..) = field.ty {
+                    return Err(Error {
+                        level: Level::Error,
+                        span: Some(item.ident.span()),
+                        file: None,
+                        item: None,
+                        message: format!(
+                            "bindgen cannot handle field `{}` of variant `{}::{}`: strings, \
+                             arrays and functions can't be part of a tagged union",
+                            field.name, name, variant.name
+                        ),
+                    });
+                }
+            }
+        }
+        let item = enum_item;
+
         self.enums.push(Snippet { docs, name, item });
 
         Ok(())
@@ -416,7 +512,9 @@ impl Lang for LangCSharp {
 
         let item = transform_struct(item.to_owned().fields).ok_or_else(|| Error {
             level: Level::Error,
-            span: None, //NONE FOR NOW
+            span: Some(item.ident.span()),
+            file: None,
+            item: None,
             message: format!("bindgen cannot handle struct {}", item.ident.to_string()),
         })?;
         let name = name;
@@ -455,7 +553,9 @@ impl Lang for LangCSharp {
             let string = item.to_owned().ident.to_string();
             Error {
                 level: Level::Error,
-                span: None, //NONE FOR NOW
+                span: Some(item.ident.span()),
+                file: None,
+                item: None,
                 message: format!("bindgen cannot handle function {}", string),
             }
         })?;
@@ -659,7 +759,12 @@ impl Lang for LangCSharp {
             // Enums
             for snippet in self.enums.drain(..) {
                 emit_docs(&mut writer, &self.context, &snippet.docs);
-                emit_enum(&mut writer, &self.context, &snippet.name, &snippet.item);
+
+                if snippet.item.has_data() {
+                    emit_tagged_union(&mut writer, &self.context, &snippet.name, &snippet.item);
+                } else {
+                    emit_enum(&mut writer, &self.context, &snippet.name, &snippet.item);
+                }
             }
 
             // Structs
@@ -696,7 +801,7 @@ impl Lang for LangCSharp {
     }
 }
 
-fn resolve_alias(aliases: &HashMap<String, Type>, new_ty: &mut Type) {
+pub(crate) fn resolve_alias(aliases: &HashMap<String, Type>, new_ty: &mut Type) {
     let mut orig_new_ty = mem::replace(new_ty, Type::Unit);
 
     match orig_new_ty {
@@ -429,6 +429,18 @@ fn enums() {
             Zero = 0,
             One = 1,
         }
+
+        #[repr(u8)]
+        pub enum Priority {
+            Low = 1,
+            High,
+        }
+
+        #[repr(C, i64)]
+        pub enum Offset {
+            Start,
+            End,
+        }
     });
 
     let actual = fetch(&outputs, "Types.cs");
@@ -452,6 +464,105 @@ fn enums() {
                  One = 1,
              }
 
+             public enum Priority : byte
+             {
+                 Low = 1,
+                 High,
+             }
+
+             public enum Offset : long
+             {
+                 Start,
+                 End,
+             }
+
+         }
+        "
+    );
+
+    assert_multiline_eq!(actual, expected);
+}
+
+#[test]
+fn tagged_unions() {
+    let outputs = compile!(LangCSharp::default(), {
+        #[repr(C)]
+        pub enum Shape {
+            Point,
+            Circle(f64),
+            Rect { width: u32, height: u32 },
+        }
+
+        #[repr(u8)]
+        pub enum Event {
+            Closed = 1,
+            Data { bytes: *const u8, len: usize },
+        }
+    });
+
+    let actual = fetch(&outputs, "Types.cs");
+    let expected = indoc!(
+        "using System;
+         using System.Collections.Generic;
+         using System.Runtime.InteropServices;
+
+         namespace Backend
+         {
+             public enum ShapeTag
+             {
+                 Point,
+                 Circle,
+                 Rect,
+             }
+
+             public struct ShapeCircle
+             {
+                 public double Item0;
+             }
+
+             public struct ShapeRect
+             {
+                 public uint Width;
+                 public uint Height;
+             }
+
+             [StructLayout(LayoutKind.Explicit)]
+             public struct ShapePayload
+             {
+                 [FieldOffset(0)]
+                 public ShapeCircle Circle;
+                 [FieldOffset(0)]
+                 public ShapeRect Rect;
+             }
+
+             public struct Shape
+             {
+                 public ShapeTag Tag;
+                 public ShapePayload Payload;
+             }
+
+             public enum EventTag : byte
+             {
+                 Closed = 1,
+                 Data,
+             }
+
+             public struct EventData
+             {
+                 public EventTag Tag;
+                 public IntPtr Bytes;
+                 public UIntPtr Len;
+             }
+
+             [StructLayout(LayoutKind.Explicit)]
+             public struct Event
+             {
+                 [FieldOffset(0)]
+                 public EventTag Tag;
+                 [FieldOffset(0)]
+                 public EventData Data;
+             }
+
          }
         "
     );
@@ -459,6 +570,20 @@ fn enums() {
     assert_multiline_eq!(actual, expected);
 }
 
+#[test]
+fn tagged_unions_with_managed_fields() {
+    let errors = match crate::test_utils::try_compile(
+        LangCSharp::default(),
+        "#[repr(C)] pub enum Message { Empty, Text(*const c_char) }".to_string(),
+    ) {
+        Ok(_) => panic!("compilation should fail"),
+        Err(errors) => errors,
+    };
+    assert!(errors[0]
+        .message
+        .contains("`item0` of variant `Message::Text`"));
+}
+
 #[test]
 fn functions_without_extern_and_no_mangle_are_ignored() {
     let outputs = compile!(LangCSharp::default(), {
@@ -1,5 +1,8 @@
+use proc_macro2::LineColumn;
+use serde_json::json;
 use std::fmt;
 use std::io::Error as IoError;
+use std::path::{Path, PathBuf};
 use syn::export::Span;
 
 /// Describes an error encountered by the compiler.
@@ -9,33 +12,274 @@ use syn::export::Span;
 pub struct Error {
     pub level: Level,
     pub(crate) span: Option<Span>,
+    pub(crate) file: Option<PathBuf>,
+    pub(crate) item: Option<String>,
     pub message: String,
 }
 
+/// How errors are reported by `Bindgen::print_error`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MessageFormat {
+    /// Human readable messages in the style of rustc, written to stderr.
+    #[default]
+    Human,
+    /// One JSON object per message in the style of `cargo --message-format=json`, written to
+    /// stdout.
+    Json,
+}
+
 impl Error {
     pub fn error(message: &str) -> Self {
         Error {
             level: Level::Error,
             span: None,
+            file: None,
+            item: None,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn warning(message: &str) -> Self {
+        Error {
+            level: Level::Warning,
+            span: None,
+            file: None,
+            item: None,
             message: message.to_string(),
         }
     }
 
-    pub(crate) fn print(&self) {
-        // TODO: improve error output, add spans where needed
-        println!("{:?}, {}: {}", self.span, self.level, self.message);
+    /// Path to the source file the error was found in, if known.
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// Name of the item that was being processed when the error occurred, if known.
+    pub fn item(&self) -> Option<&str> {
+        self.item.as_deref()
+    }
+
+    /// Line and column (both starting at 1) of the offending code, if known.
+    pub fn line_column(&self) -> Option<(usize, usize)> {
+        self.span.map(|span| {
+            let start = span.start();
+            (start.line, start.column + 1)
+        })
+    }
+
+    /// Attaches the location of the offending code, unless a more precise one is already known.
+    pub(crate) fn with_span(mut self, span: Span) -> Self {
+        if self.span.is_none() {
+            self.span = Some(span);
+        }
+        self
+    }
+
+    /// Attaches the name and location of the item being processed.
+    pub(crate) fn with_item(mut self, ident: &syn::Ident) -> Self {
+        if self.item.is_none() {
+            self.item = Some(ident.to_string());
+        }
+        self.with_span(ident.span())
+    }
+
+    /// Attaches the source file the error was found in, unless it is already known.
+    pub(crate) fn with_file<P: AsRef<Path>>(mut self, file: P) -> Self {
+        if self.file.is_none() {
+            self.file = Some(file.as_ref().to_path_buf());
+        }
+        self
+    }
+
+    /// Renders the error in the style of rustc, quoting the offending line of `source` (the
+    /// contents of the file the error was found in) when available.
+    pub(crate) fn render(&self, source: Option<&str>) -> String {
+        let mut output = format!("{}: {}\n", self.level, self.message);
+
+        let file = match self.file() {
+            Some(file) => file,
+            None => return output,
+        };
+        let span = match self.span {
+            Some(span) => span,
+            None => {
+                output.push_str(&format!(" --> {}\n", file.display()));
+                return output;
+            }
+        };
+
+        let start = Position::new(source, span.start());
+        let end = Position::new(source, span.end());
+        let gutter = " ".repeat(start.line.to_string().len());
+        output.push_str(&format!(
+            "{}--> {}:{}:{}\n",
+            gutter,
+            file.display(),
+            start.line,
+            start.column
+        ));
+
+        let line = match source.and_then(|source| source.lines().nth(start.line - 1)) {
+            Some(line) => line,
+            None => return output,
+        };
+        let width = if end.line == start.line && end.column > start.column {
+            end.column - start.column
+        } else {
+            1
+        };
+
+        output.push_str(&format!("{} |\n", gutter));
+        output.push_str(&format!("{} | {}\n", start.line, line));
+        output.push_str(&format!(
+            "{} | {}{}\n",
+            gutter,
+            " ".repeat(start.column - 1),
+            "^".repeat(width)
+        ));
+        output
+    }
+
+    /// Converts the error into a JSON object in the format used by `cargo --message-format=json`
+    /// for compiler messages, extended with the name of the item being processed.
+    pub(crate) fn to_json(&self, source: Option<&str>) -> serde_json::Value {
+        let level = match self.level {
+            Level::Bug => "error: internal compiler error".to_string(),
+            Level::Fatal => "error".to_string(),
+            level => level.to_string(),
+        };
+
+        let spans = match (self.file(), self.span) {
+            (Some(file), Some(span)) => {
+                let start = Position::new(source, span.start());
+                let end = Position::new(source, span.end());
+                let lines: Vec<&str> = source.map(|s| s.lines().collect()).unwrap_or_default();
+                let text: Vec<_> = lines
+                    .iter()
+                    .enumerate()
+                    .skip(start.line - 1)
+                    .take(end.line + 1 - start.line)
+                    .map(|(index, line)| {
+                        let number = index + 1;
+                        let highlight_start = if number == start.line {
+                            start.column
+                        } else {
+                            1
+                        };
+                        let highlight_end = if number == end.line {
+                            end.column
+                        } else {
+                            line.chars().count() + 1
+                        };
+                        json!({
+                            "text": line,
+                            "highlight_start": highlight_start,
+                            "highlight_end": highlight_end,
+                        })
+                    })
+                    .collect();
+
+                vec![json!({
+                    "file_name": file.display().to_string(),
+                    "byte_start": start.byte,
+                    "byte_end": end.byte,
+                    "line_start": start.line,
+                    "line_end": end.line,
+                    "column_start": start.column,
+                    "column_end": end.column,
+                    "is_primary": true,
+                    "text": text,
+                    "label": null,
+                    "suggested_replacement": null,
+                    "suggestion_applicability": null,
+                    "expansion": null,
+                })]
+            }
+            _ => vec![],
+        };
+
+        json!({
+            "reason": "compiler-message",
+            "item": self.item,
+            "message": {
+                "message": self.message,
+                "code": null,
+                "level": level,
+                "spans": spans,
+                "children": [],
+                "rendered": self.render(source),
+            },
+        })
+    }
+
+    /// Constructs an error for a source file which could not be read.
+    pub(crate) fn unreadable_file(path: &Path, err: &IoError) -> Self {
+        Self {
+            level: Level::Fatal,
+            span: None,
+            file: Some(path.to_path_buf()),
+            item: None,
+            message: format!("could not read `{}`: {}", path.display(), err),
+        }
+    }
+
+    /// Constructs an error for a source file which could not be parsed.
+    pub(crate) fn unparsable_file(path: &Path, err: &syn::Error) -> Self {
+        Self {
+            level: Level::Error,
+            span: Some(err.span()),
+            file: Some(path.to_path_buf()),
+            item: None,
+            message: format!("could not parse `{}`: {}", path.display(), err),
+        }
     }
 
     /// Constructs an error for unsupported generics.
     pub fn unsupported_generics_error(name: &str) -> Self {
         Self {
             level: Level::Error,
-            span: None, //NONE FOR NOW
+            span: None,
+            file: None,
+            item: None,
             message: format!("bindgen cannot handle parameterized {}", name),
         }
     }
 }
 
+/// Location within a source file.
+struct Position {
+    /// Line, starting at 1.
+    line: usize,
+    /// Column in characters, starting at 1.
+    column: usize,
+    /// Offset in bytes from the start of the file.
+    byte: usize,
+}
+
+impl Position {
+    /// Span locations count columns in bytes, so the source file is needed to convert them into
+    /// characters. Without it the columns are taken as they are.
+    fn new(source: Option<&str>, location: LineColumn) -> Self {
+        let mut position = Position {
+            line: location.line,
+            column: location.column + 1,
+            byte: 0,
+        };
+        if let Some(source) = source {
+            for (index, text) in source.split('\n').enumerate() {
+                if index + 1 == location.line {
+                    let column = location.column.min(text.len());
+                    position.column = text.get(..column).map_or(column, |s| s.chars().count()) + 1;
+                    position.byte += column;
+                    break;
+                }
+                position.byte += text.len() + 1;
+            }
+        }
+        position
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(formatter, "{}: {}", self.level, self.message)
@@ -59,6 +303,8 @@ impl From<IoError> for Error {
         Error {
             level: Level::Fatal,
             span: None,
+            file: None,
+            item: None,
             message: format!("I/O Error: {}", e),
         }
     }
@@ -92,3 +338,77 @@ impl fmt::Display for Level {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unwrap::unwrap;
+
+    #[test]
+    fn render() {
+        let source = "pub struct Foo;\n\npub fn bar(_: Never) {}\n";
+        let ast: syn::File = unwrap!(syn::parse_str(source));
+        let span = match ast.items[1] {
+            syn::Item::Fn(ref item) => item.ident.span(),
+            _ => panic!("expected a function"),
+        };
+
+        let error = Error::error("bindgen cannot handle function bar")
+            .with_span(span)
+            .with_file("src/ffi.rs");
+
+        assert_eq!(
+            error.render(Some(source)),
+            "error: bindgen cannot handle function bar\n\
+             \x20--> src/ffi.rs:3:8\n\
+             \x20 |\n\
+             3 | pub fn bar(_: Never) {}\n\
+             \x20 |        ^^^\n"
+        );
+        assert_eq!(
+            error.render(None),
+            "error: bindgen cannot handle function bar\n --> src/ffi.rs:3:8\n"
+        );
+        assert_eq!(
+            Error::error("no bindings generated").render(None),
+            "error: no bindings generated\n"
+        );
+    }
+
+    #[test]
+    fn json() {
+        let source = "pub struct Foo;\n\npub fn bär(_: Never) {}\n";
+        let ast: syn::File = unwrap!(syn::parse_str(source));
+        let ident = match ast.items[1] {
+            syn::Item::Fn(ref item) => item.ident.clone(),
+            _ => panic!("expected a function"),
+        };
+
+        let error = Error::error("bindgen cannot handle function bär")
+            .with_item(&ident)
+            .with_file("src/ffi.rs");
+        let json = error.to_json(Some(source));
+
+        assert_eq!(json["reason"], "compiler-message");
+        assert_eq!(json["item"], "bär");
+        assert_eq!(json["message"]["level"], "error");
+        assert_eq!(
+            json["message"]["message"],
+            "bindgen cannot handle function bär"
+        );
+        assert_eq!(json["message"]["rendered"], error.render(Some(source)));
+
+        let span = &json["message"]["spans"][0];
+        assert_eq!(span["file_name"], "src/ffi.rs");
+        assert_eq!(span["line_start"], 3);
+        assert_eq!(span["column_start"], 8);
+        assert_eq!(span["column_end"], 11);
+        assert_eq!(span["byte_start"], 24);
+        assert_eq!(span["byte_end"], 28);
+        assert_eq!(span["text"][0]["text"], "pub fn bär(_: Never) {}");
+
+        let json = Error::error("no bindings generated").to_json(None);
+        assert_eq!(json["item"], serde_json::Value::Null);
+        assert_eq!(json["message"]["spans"], serde_json::json!([]));
+    }
+}
@@ -0,0 +1,178 @@
+//! Functions generating the Java class which wraps asynchronous native functions into methods
+//! returning a `CompletableFuture`.
+
+use super::{callback_params, Context};
+use crate::common::{is_result_arg_barefn, is_user_data_arg_barefn};
+use crate::java::types::java_type_to_str;
+use crate::Error;
+use ::jni::signature::{JavaType, Primitive};
+use inflector::Inflector;
+
+/// Returns the name of the class holding the wrappers, which is named after the native library.
+///
+/// The `Async` suffix keeps it apart from the handle type the library is usually named after.
+pub fn class_name(context: &Context) -> String {
+    format!("{}Async", context.lib_name.to_pascal_case())
+}
+
+/// Returns the name of the file holding the wrappers.
+pub fn class_file(context: &Context) -> String {
+    format!("{}.java", class_name(context))
+}
+
+/// Returns the type used as a type argument for a Java type (primitives are boxed).
+fn boxed_type(ty: &JavaType) -> Result<String, Error> {
+    let boxed = match *ty {
+        JavaType::Primitive(Primitive::Boolean) => "Boolean",
+        JavaType::Primitive(Primitive::Byte) => "Byte",
+        JavaType::Primitive(Primitive::Char) => "Character",
+        JavaType::Primitive(Primitive::Short) => "Short",
+        JavaType::Primitive(Primitive::Int) => "Integer",
+        JavaType::Primitive(Primitive::Long) => "Long",
+        JavaType::Primitive(Primitive::Float) => "Float",
+        JavaType::Primitive(Primitive::Double) => "Double",
+        JavaType::Primitive(Primitive::Void) => "Void",
+        _ => return java_type_to_str(ty),
+    };
+    Ok(boxed.to_string())
+}
+
+/// Returns the parameters of a callback receiving an `FfiResult` followed by at most one value,
+/// which can complete a future. Other callbacks can't be wrapped and return `None`.
+pub(crate) fn result_callback_params(
+    callback: &syn::TypeBareFn,
+    context: &Context,
+) -> Result<Option<Vec<(JavaType, String)>>, Error> {
+    let has_result = callback
+        .inputs
+        .iter()
+        .find(|arg| !is_user_data_arg_barefn(arg))
+        .is_some_and(is_result_arg_barefn);
+    let params = callback_params(callback, context)?;
+    if !has_result || params.len() > 2 {
+        return Ok(None);
+    }
+
+    Ok(Some(params))
+}
+
+/// Generates a method calling the native function `java_name` and completing the returned
+/// future once the callback is called.
+///
+/// `args` are the parameters of the native function in `NativeBindings`, with the callback at
+/// `callback_index`. The callback has to receive an `FfiResult` followed by at most one value,
+/// otherwise no wrapper is generated. If the error code of the `FfiResult` is not zero, the
+/// future completes exceptionally with an `FfiException`.
+pub fn generate_wrapper(
+    java_name: &str,
+    docs: &str,
+    args: &[(String, String)],
+    callback_index: usize,
+    callback: &syn::TypeBareFn,
+    context: &Context,
+) -> Result<Option<String>, Error> {
+    let params = match result_callback_params(callback, context)? {
+        Some(params) => params,
+        None => return Ok(None),
+    };
+
+    let (value_ty, value) = match params.get(1) {
+        Some((ty, name)) => (boxed_type(ty)?, name.clone()),
+        None => ("Void".to_string(), "null".to_string()),
+    };
+    let lambda_params: Vec<_> = params.iter().map(|(_, name)| name.as_str()).collect();
+
+    let mut wrapper_args = Vec::new();
+    let mut call_args = Vec::new();
+    for (index, (ty, name)) in args.iter().enumerate() {
+        if index == callback_index {
+            call_args.push(format!("({}) -> {{", lambda_params.join(", ")));
+        } else {
+            wrapper_args.push(format!("{} {}", ty, name));
+            call_args.push(name.clone());
+        }
+    }
+
+    let mut buffer = String::new();
+    buffer.push_str("/**\n");
+    buffer.push_str(&docs.replace("///", " *"));
+    buffer.push_str(" */\n");
+    buffer.push_str(&format!(
+        "public static CompletableFuture<{ty}> {name}({args}) {{\n\
+         \tCompletableFuture<{ty}> future = new CompletableFuture<>();\n\
+         \tNativeBindings.{name}({call_args}\n\
+         \t\tif (result != null && result.getErrorCode() != 0) {{\n\
+         \t\t\tfuture.completeExceptionally(\
+         new FfiException(result.getErrorCode(), result.getDescription()));\n\
+         \t\t}} else {{\n\
+         \t\t\tfuture.complete({value});\n\
+         \t\t}}\n\
+         \t}}{rest});\n\
+         \treturn future;\n\
+         }}\n\n",
+        ty = value_ty,
+        name = java_name,
+        args = wrapper_args.join(", "),
+        call_args = call_args[..=callback_index].join(", "),
+        value = value,
+        rest = call_args[callback_index + 1..]
+            .iter()
+            .map(|arg| format!(", {}", arg))
+            .collect::<String>(),
+    ));
+
+    Ok(Some(buffer))
+}
+
+/// Adds the package, the imports and the class declaration to the generated wrappers.
+pub fn format_class(methods: &mut String, context: &Context) {
+    let lines = methods.lines().fold(String::new(), |mut output, line| {
+        if !line.is_empty() {
+            output.push('\t');
+        }
+        output.push_str(line);
+        output.push('\n');
+        output
+    });
+    *methods = format!(
+        "package {namespace};\n\
+         \n\
+         import java.util.concurrent.CompletableFuture;\n\
+         \n\
+         public final class {name} {{\n\
+         {lines}\
+         \tprivate {name}() {{\n\
+         \t}}\n\
+         }}\n\n",
+        namespace = context.namespace,
+        name = class_name(context),
+        lines = lines,
+    );
+}
+
+/// Returns the exception futures complete with when a native function fails.
+pub fn ffi_exception(context: &Context) -> String {
+    format!(
+        "package {namespace};\n\
+         \n\
+         public class FfiException extends Exception {{\n\
+         \tprivate final int errorCode;\n\
+         \tprivate final String description;\n\
+         \n\
+         \tpublic FfiException(int errorCode, String description) {{\n\
+         \t\tsuper(description);\n\
+         \t\tthis.errorCode = errorCode;\n\
+         \t\tthis.description = description;\n\
+         \t}}\n\
+         \n\
+         \tpublic int getErrorCode() {{\n\
+         \t\treturn errorCode;\n\
+         \t}}\n\
+         \n\
+         \tpublic String getDescription() {{\n\
+         \t\treturn description;\n\
+         \t}}\n\
+         }}\n\n",
+        namespace = context.namespace,
+    )
+}
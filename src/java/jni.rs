@@ -9,7 +9,7 @@ use crate::common::{
 };
 use crate::struct_field::StructField;
 use inflector::Inflector;
-use jni::signature::{self, JavaType, Primitive, TypeSignature};
+use jni::signature::{JavaType, Primitive, TypeSignature};
 use proc_macro2::Span;
 use quote::ToTokens;
 use quote::*;
@@ -22,6 +22,17 @@ fn to_jni_arg(arg: &syn::ArgCaptured, ty_name: &str) -> proc_macro2::TokenStream
     quote! { #pat: #ty_name }
 }
 
+// Returns the JNI type a Rust primitive type is passed as
+fn jni_primitive_type(ty: &str) -> Option<&'static str> {
+    match ty {
+        "c_char" | "u8" | "i8" => Some("jbyte"),
+        "c_short" | "u16" | "i16" => Some("jshort"),
+        "c_int" | "u32" | "i32" => Some("jint"),
+        "c_long" | "u64" | "i64" | "c_usize" | "usize" | "isize" => Some("jlong"),
+        _ => None,
+    }
+}
+
 fn transform_jni_arg(arg: &syn::ArgCaptured) -> proc_macro2::TokenStream {
     match arg.ty {
         // Callback
@@ -32,15 +43,7 @@ fn transform_jni_arg(arg: &syn::ArgCaptured) -> proc_macro2::TokenStream {
             let ty = unwrap!(path.path.segments.last()).into_value();
             let ty = ty.ident.to_string();
 
-            let jni_type = match ty.as_str() {
-                "c_char" | "u8" | "i8" => "jbyte",
-                "c_short" | "u16" | "i16" => "jshort",
-                "c_int" | "u32" | "i32" => "jint",
-                "c_long" | "u64" | "i64" | "c_usize" | "usize" | "isize" => "jlong",
-                _ => ty.as_str(),
-            };
-
-            to_jni_arg(arg, jni_type)
+            to_jni_arg(arg, jni_primitive_type(&ty).unwrap_or(&ty))
         }
 
         // Standard pointers.
@@ -80,6 +83,16 @@ fn lookup_object_type(ty: &str, context: &Context) -> JavaType {
     }
 }
 
+// Checks whether `ty` is an enum without data, which is represented by a Java enum.
+fn is_enum(ty: &syn::Type, context: &Context) -> bool {
+    match *ty {
+        syn::Type::Path(ref path) if path.path.segments.len() == 1 => context
+            .enums
+            .contains_key(&path.path.segments[0].ident.to_string()),
+        _ => false,
+    }
+}
+
 fn rust_ty_to_signature(ty: &syn::Type, context: &Context) -> Option<JavaType> {
     match ty {
         // Callback
@@ -108,16 +121,37 @@ fn rust_ty_to_signature(ty: &syn::Type, context: &Context) -> Option<JavaType> {
     }
 }
 
+// Unwraps the result of a JNI call. Functions without a return value use `jni_unwrap!`, while
+// the others return `default` on errors, leaving a thrown Java exception pending.
+fn jni_try(
+    expr: proc_macro2::TokenStream,
+    default: Option<&proc_macro2::TokenStream>,
+) -> proc_macro2::TokenStream {
+    match default {
+        Some(default) => quote! {
+            match #expr {
+                Ok(value) => value,
+                Err(_) => return #default,
+            }
+        },
+        None => quote! { jni_unwrap!(#expr) },
+    }
+}
+
 struct JniArgResult {
     stmt: proc_macro2::TokenStream,
     call_args: Vec<proc_macro2::TokenStream>,
 }
 
-fn transform_string_arg(arg_name: &str) -> JniArgResult {
+fn transform_string_arg(
+    arg_name: &str,
+    default: Option<&proc_macro2::TokenStream>,
+) -> JniArgResult {
     // statements
     let arg_name = syn::Ident::new(arg_name, Span::call_site());
+    let value = jni_try(quote! { CString::from_java(&env, #arg_name) }, default);
     let stmt = quote! {
-        let #arg_name = jni_unwrap!(CString::from_java(&env, #arg_name));
+        let #arg_name = #value;
     };
 
     // call arg value(s)
@@ -126,12 +160,17 @@ fn transform_string_arg(arg_name: &str) -> JniArgResult {
     JniArgResult { stmt, call_args }
 }
 
-fn transform_struct_arg(arg_name: &str, arg_ty: &syn::Type) -> JniArgResult {
+fn transform_struct_arg(
+    arg_name: &str,
+    arg_ty: &syn::Type,
+    default: Option<&proc_macro2::TokenStream>,
+) -> JniArgResult {
     // statements
     let arg_name = syn::Ident::new(arg_name, Span::call_site());
     let struct_ty = syn::Ident::new(format!("{}", quote!(#arg_ty)).as_str(), Span::call_site());
+    let value = jni_try(quote! { #struct_ty::from_java(&env, #arg_name) }, default);
     let stmt = quote! {
-        let #arg_name = jni_unwrap!(#struct_ty::from_java(&env, #arg_name));
+        let #arg_name = #value;
     };
 
     // call arg value(s)
@@ -140,11 +179,30 @@ fn transform_struct_arg(arg_name: &str, arg_ty: &syn::Type) -> JniArgResult {
     JniArgResult { stmt, call_args }
 }
 
-fn transform_array_arg(arg_name: &str) -> JniArgResult {
+fn transform_enum_arg(
+    arg_name: &str,
+    arg_ty: &syn::Type,
+    default: Option<&proc_macro2::TokenStream>,
+) -> JniArgResult {
+    // statements
+    let arg_name = syn::Ident::new(arg_name, Span::call_site());
+    let value = jni_try(quote! { #arg_ty::from_java(&env, #arg_name) }, default);
+    let stmt = quote! {
+        let #arg_name = #value;
+    };
+
+    // call arg value(s)
+    let call_args = vec![quote! { #arg_name }];
+
+    JniArgResult { stmt, call_args }
+}
+
+fn transform_array_arg(arg_name: &str, default: Option<&proc_macro2::TokenStream>) -> JniArgResult {
     // statements
     let arg_name = syn::Ident::new(arg_name.to_string().as_str(), Span::call_site());
+    let value = jni_try(quote! { Vec::from_java(&env, #arg_name) }, default);
     let stmt = quote! {
-        let #arg_name = jni_unwrap!(Vec::from_java(&env, #arg_name));
+        let #arg_name = #value;
     };
 
     // call arg value(s)
@@ -203,9 +261,106 @@ fn transform_opaque_ptr(arg_name: &str, ty: &str) -> JniArgResult {
     JniArgResult { stmt, call_args }
 }
 
+struct JniReturn {
+    // JNI type returned to Java
+    ty: proc_macro2::TokenStream,
+    // Value returned if the conversion of an argument or of the result fails
+    default: proc_macro2::TokenStream,
+    // Statements converting the native `result` into the returned `output`
+    stmts: proc_macro2::TokenStream,
+}
+
+// Returns the JNI type a Java primitive type is returned as
+fn jni_return_primitive(ty: Primitive) -> proc_macro2::TokenStream {
+    match ty {
+        Primitive::Boolean => quote! { jboolean },
+        Primitive::Byte => quote! { jbyte },
+        Primitive::Char => quote! { jchar },
+        Primitive::Short => quote! { jshort },
+        Primitive::Int => quote! { jint },
+        Primitive::Long => quote! { jlong },
+        Primitive::Float => quote! { jfloat },
+        Primitive::Double => quote! { jdouble },
+        Primitive::Void => quote! { () },
+    }
+}
+
+// Converts the value returned by a native function. Primitives (and handles, which are pointers
+// to types mapped to a Java primitive) are cast, while the other values are converted with
+// `ToJava`. Returned strings and structures are borrowed, unless returns are owned, in which case
+// they are freed with the free function once they are converted. Handles are never freed.
+fn transform_return(ty: &syn::Type, context: &Context) -> JniReturn {
+    let null = quote! { ::std::ptr::null_mut() };
+
+    let primitive = match *ty {
+        syn::Type::Ptr(ref ptr) => {
+            let name = unwrap!(take_out_ident_from_type(&ptr.elem));
+            match context.type_map.get(&name) {
+                Some(JavaType::Primitive(primitive)) => Some(*primitive),
+                _ => None,
+            }
+        }
+        _ => match rust_ty_to_signature(ty, context) {
+            Some(JavaType::Primitive(primitive)) => Some(primitive),
+            _ => None,
+        },
+    };
+    if let Some(primitive) = primitive {
+        let ty = jni_return_primitive(primitive);
+        return JniReturn {
+            stmts: quote! {
+                let output = result as #ty;
+            },
+            default: quote! { 0 as #ty },
+            ty,
+        };
+    }
+
+    let stmts = match *ty {
+        syn::Type::Ptr(ref ptr) => {
+            let free = match context.free_fn {
+                Some(ref free_fn) if context.owned_returns => {
+                    let free_fn = syn::Ident::new(free_fn, Span::call_site());
+                    quote! { #free_fn(result as *mut c_void); }
+                }
+                _ => quote! {},
+            };
+            let conversion = match unwrap!(take_out_ident_from_type(&ptr.elem)).as_str() {
+                "c_char" => quote! { result.to_java(&env).map(JObject::from) },
+                _ => quote! { (*result).to_java(&env) },
+            };
+            let output = jni_try(quote! { output }, Some(&null));
+
+            quote! {
+                let output = if result.is_null() {
+                    Ok(JObject::null())
+                } else {
+                    let output = #conversion;
+                    #free
+                    output
+                };
+                let output = #output.into_inner();
+            }
+        }
+        _ => {
+            let output = jni_try(quote! { result.to_java(&env) }, Some(&null));
+            quote! {
+                let output = #output.into_inner();
+            }
+        }
+    };
+
+    JniReturn {
+        ty: quote! { jobject },
+        default: null,
+        stmts,
+    }
+}
+
 /// Generates JNI function binding based on a native function
 pub fn generate_jni_function(
     args: &[syn::FnArg],
+    output: &syn::ReturnType,
     attrs: &[syn::Attribute],
     native_name: &str,
     func_name: &str,
@@ -224,6 +379,12 @@ pub fn generate_jni_function(
     let native_name_str = native_name;
     let native_name = syn::Ident::new(native_name, Span::call_site());
 
+    let ret = match *output {
+        syn::ReturnType::Type(_, ref ty) => Some(transform_return(ty, context)),
+        syn::ReturnType::Default => None,
+    };
+    let default = ret.as_ref().map(|ret| &ret.default);
+
     // Generate inputs
     let mut call_args = Vec::new();
     let mut stmts = Vec::new();
@@ -244,7 +405,7 @@ pub fn generate_jni_function(
             transform_fnarg_to_argcap_option(args_iter.peek().cloned()),
         ) {
             args_iter.next();
-            Some(transform_array_arg(&arg_name))
+            Some(transform_array_arg(&arg_name, default))
         } else {
             match unwrap!(transform_fnarg_to_argcap(arg)).ty {
                 // Callback
@@ -265,11 +426,16 @@ pub fn generate_jni_function(
                             Some(transform_opaque_ptr(&arg_name, opaque))
                         }
                         // Detect strings, which are *const c_char or *mut c_char
-                        "c_char" => Some(transform_string_arg(&arg_name)),
-                        _ => Some(transform_struct_arg(&arg_name, &*ptr.elem)),
+                        "c_char" => Some(transform_string_arg(&arg_name, default)),
+                        _ => Some(transform_struct_arg(&arg_name, &*ptr.elem, default)),
                     }
                 }
 
+                // Enums are passed as Java enums
+                ref enum_ty if is_enum(enum_ty, context) => {
+                    Some(transform_enum_arg(&arg_name, enum_ty, default))
+                }
+
                 // Native types and others
                 ref native_ty => {
                     let id = syn::Ident::new(arg_name.as_str(), Span::call_site());
@@ -286,7 +452,12 @@ pub fn generate_jni_function(
             stmts.push(jni_arg_res.stmt);
         }
 
-        jni_fn_inputs.push(transform_jni_arg(unwrap!(transform_fnarg_to_argcap(&arg))));
+        let argcap = unwrap!(transform_fnarg_to_argcap(&arg));
+        jni_fn_inputs.push(if is_enum(&argcap.ty, context) {
+            to_jni_arg(argcap, "JObject")
+        } else {
+            transform_jni_arg(argcap)
+        });
     }
 
     if !callbacks.is_empty() {
@@ -322,16 +493,35 @@ pub fn generate_jni_function(
         }
     }
 
-    let tokens = quote! {
-        #[no_mangle]
-        pub unsafe extern "system" fn #func_name(
-            env: JNIEnv,
-            _class: JClass,
-            #(#jni_fn_inputs),*
-        ) {
-            #(#stmts)*
-            #native_name(#(#call_args),*);
-        }
+    let tokens = match ret {
+        Some(JniReturn {
+            ty,
+            stmts: ret_stmts,
+            ..
+        }) => quote! {
+            #[no_mangle]
+            pub unsafe extern "system" fn #func_name(
+                env: JNIEnv,
+                _class: JClass,
+                #(#jni_fn_inputs),*
+            ) -> #ty {
+                #(#stmts)*
+                let result = #native_name(#(#call_args),*);
+                #ret_stmts
+                output
+            }
+        },
+        None => quote! {
+            #[no_mangle]
+            pub unsafe extern "system" fn #func_name(
+                env: JNIEnv,
+                _class: JClass,
+                #(#jni_fn_inputs),*
+            ) {
+                #(#stmts)*
+                #native_name(#(#call_args),*);
+            }
+        },
     };
 
     let mut output = String::new();
@@ -432,11 +622,9 @@ fn generate_callback(cb: &syn::TypeBareFn, context: &Context) -> JniCallback {
                         }
                     }
                 }
-                _ => {
-                    quote! {
-                        let #arg_name = jni_unwrap!(#arg_name.to_java(&env));
-                    }
-                }
+                _ => quote! {
+                    let #arg_name = jni_unwrap!(#arg_name.to_java(&env));
+                },
             };
 
             arg_java_ty.push(unwrap!(rust_ty_to_signature(&arg.ty, context)));
@@ -448,7 +636,7 @@ fn generate_callback(cb: &syn::TypeBareFn, context: &Context) -> JniCallback {
         "{}",
         TypeSignature {
             args: arg_java_ty,
-            ret: JavaType::Primitive(signature::Primitive::Void),
+            ret: JavaType::Primitive(Primitive::Void),
         }
     );
 
@@ -535,17 +723,29 @@ pub fn generate_jni_callback(cb: &syn::TypeBareFn, cb_name: &str, context: &mut
     tokens.to_string()
 }
 
+/// Generates the statements converting the fields of a struct into a new Java object.
+///
+/// With `bound_fields` the fields are read from the references bound by a `match` arm (as for
+/// enum variants) instead of from `self`.
 fn generate_struct_to_java(
-    struct_ident: &syn::Ident,
     java_class_name: &str,
     fields: &[StructField],
     context: &Context,
+    bound_fields: bool,
 ) -> proc_macro2::TokenStream {
+    let access = |ident: &syn::Ident| {
+        if bound_fields {
+            quote! { (*#ident) }
+        } else {
+            quote! { self.#ident }
+        }
+    };
     let mut stmts = Vec::new();
 
     for f in fields {
         let field_name_str = f.name();
         let field_name = syn::Ident::new(field_name_str.as_str(), Span::call_site());
+        let field = access(&field_name);
         let java_field_name = field_name_str.to_camel_case();
 
         let stmt = match *f {
@@ -557,6 +757,7 @@ fn generate_struct_to_java(
                 if let syn::Type::Ptr(ref ptr) = field.ty {
                     let len_field_ident =
                         syn::Ident::new(len_field.clone().as_str(), Span::call_site());
+                    let len_field_value = access(&len_field_ident);
                     let len_field = len_field.to_camel_case();
                     let ty = &*ptr.elem;
                     let ty_str = format!("{}", quote! {#ty});
@@ -564,12 +765,12 @@ fn generate_struct_to_java(
                         // Byte array
                         quote! {
                             let arr = env.new_byte_array(
-                                self.#len_field_ident as jni::sys::jsize
+                                #len_field_value as jni::sys::jsize
                             )?;
                             let slice = unsafe {
                                 slice::from_raw_parts(
-                                    self.#field_name as *const i8,
-                                    self.#len_field_ident
+                                    #field as *const i8,
+                                    #len_field_value
                                 )
                             };
                             env.set_byte_array_region(arr, 0, slice)?;
@@ -585,7 +786,7 @@ fn generate_struct_to_java(
                                 output,
                                 #len_field,
                                 "J",
-                                self.#len_field_ident.to_java(env)?.into()
+                                #len_field_value.to_java(env)?.into()
                             )?;
                         }
                     } else {
@@ -605,12 +806,12 @@ fn generate_struct_to_java(
                         quote! {
                             let cls = unsafe { find_class(env, #full_ty_str)? };
                             let arr = env.new_object_array(
-                                self.#len_field_ident as jni::sys::jsize,
+                                #len_field_value as jni::sys::jsize,
                                 &cls,
                                 JObject::null()
                             )?;
                             let items = unsafe {
-                                slice::from_raw_parts(self.#field_name, self.#len_field_ident)
+                                slice::from_raw_parts(#field, #len_field_value)
                             };
                             for (idx, item) in items.iter().enumerate() {
                                 let jobj = item.to_java(env)?;
@@ -633,7 +834,7 @@ fn generate_struct_to_java(
                                 output,
                                 #len_field,
                                 "J",
-                                self.#len_field_ident.to_java(env)?.into()
+                                #len_field_value.to_java(env)?.into()
                             )?;
                         }
                     }
@@ -643,8 +844,8 @@ fn generate_struct_to_java(
             }
             StructField::String(ref _f) => {
                 quote! {
-                    if !self.#field_name.is_null() {
-                        let #field_name: JObject = self.#field_name.to_java(env)?.into();
+                    if !#field.is_null() {
+                        let #field_name: JObject = #field.to_java(env)?.into();
                         env.set_field(
                             output,
                             #java_field_name,
@@ -659,7 +860,7 @@ fn generate_struct_to_java(
                 let signature = format!("{}", unwrap!(rust_ty_to_signature(&*ty.elem, context)));
 
                 quote! {
-                    let jobj = self.#field_name.to_java(env)?;
+                    let jobj = #field.to_java(env)?;
                     env.set_field(
                         output,
                         #field_name_str,
@@ -688,7 +889,7 @@ fn generate_struct_to_java(
                         quote! {}
                     };
                     quote! {
-                        let jobj = self.#field_name.to_java(env)?;
+                        let jobj = #field.to_java(env)?;
                         env.set_field(
                             output,
                             #java_field_name,
@@ -708,22 +909,40 @@ fn generate_struct_to_java(
     let fully_qualified_name = fully_qualified(java_class_name, context);
 
     quote! {
-        impl<'a> ToJava<'a, JObject<'a>> for #struct_ident {
-            fn to_java(&self, env: &'a JNIEnv) -> Result<JObject<'a>, JniError> {
-                let cls = unsafe { find_class(env, #fully_qualified_name)? };
-                let output = env.new_object(&cls, "()V", &[])?;
-                #(#stmts)*
-                Ok(output)
-            }
-        }
+        let cls = unsafe { find_class(env, #fully_qualified_name)? };
+        let output = env.new_object(&cls, "()V", &[])?;
+        #(#stmts)*
+        Ok(output)
+    }
+}
+
+// Returns the signature of a Java field holding a Rust primitive type, together with the method
+// unwrapping its value.
+fn primitive_field_conversion(ty: &str) -> Option<(&'static str, proc_macro2::TokenStream)> {
+    match ty {
+        "c_byte" | "i8" | "u8" => Some(("B", quote! { b() })),
+        "c_short" | "u16" | "i16" => Some(("S", quote! { s() })),
+        "c_int" | "u32" | "i32" => Some(("I", quote! { i() })),
+        "c_long" | "u64" | "i64" | "c_usize" | "usize" | "isize" => Some(("J", quote! { j() })),
+        "c_bool" | "bool" => Some(("Z", quote! { z() })),
+        "f32" => Some(("F", quote! { f() })),
+        "f64" => Some(("D", quote! { d() })),
+        _ => None,
     }
 }
 
-fn generate_struct_from_java(
-    struct_ident: &syn::Ident,
+/// Generates the statements reading the fields of a struct from a Java object.
+///
+/// `construct` produces the expression building the struct (or enum variant) from the
+/// variables holding the converted fields.
+fn generate_struct_from_java<F>(
     fields: &[StructField],
     context: &Context,
-) -> proc_macro2::TokenStream {
+    construct: F,
+) -> proc_macro2::TokenStream
+where
+    F: FnOnce(&[proc_macro2::TokenStream]) -> proc_macro2::TokenStream,
+{
     let mut fields_values = Vec::new();
     let mut conversions = Vec::new();
 
@@ -862,18 +1081,7 @@ fn generate_struct_from_java(
 
                         let rust_ty = syn::Ident::new(ty.as_str(), Span::call_site());
 
-                        let conv = match ty.as_str() {
-                            "c_byte" | "i8" | "u8" => Some(("B", quote! { b() })),
-                            "c_short" | "u16" | "i16" => Some(("S", quote! { s() })),
-                            "c_int" | "u32" | "i32" => Some(("I", quote! { i() })),
-                            "c_long" | "u64" | "i64" | "c_usize" | "usize" | "isize" => {
-                                Some(("J", quote! { j() }))
-                            }
-                            "c_bool" | "bool" => Some(("Z", quote! { z() })),
-                            _ => None,
-                        };
-
-                        if let Some((signature, unwrap_method)) = conv {
+                        if let Some((signature, unwrap_method)) = primitive_field_conversion(&ty) {
                             quote! {
                                 let #field_name = env.get_field(
                                     input,
@@ -898,16 +1106,12 @@ fn generate_struct_from_java(
         conversions.push(conv);
     }
 
+    let value = construct(&fields_values);
+
     quote! {
-        impl<'a> FromJava<JObject<'a>> for #struct_ident {
-            fn from_java(env: &JNIEnv, input: JObject) -> Result<Self, JniError> {
-                #(#conversions)*
+        #(#conversions)*
 
-                Ok(#struct_ident {
-                    #(#fields_values),*
-                })
-            }
-        }
+        Ok(#value)
     }
 }
 
@@ -920,13 +1124,164 @@ pub fn generate_struct(
 ) -> String {
     let struct_ident = syn::Ident::new(native_name, Span::call_site());
 
-    let from_java = generate_struct_from_java(&struct_ident, fields, context);
-    let to_java = generate_struct_to_java(&struct_ident, java_class_name, fields, context);
+    let from_java = generate_struct_from_java(fields, context, |fields_values| {
+        quote! {
+            #struct_ident {
+                #(#fields_values),*
+            }
+        }
+    });
+    let to_java = generate_struct_to_java(java_class_name, fields, context, false);
+
+    let tokens = quote! {
+        impl<'a> FromJava<JObject<'a>> for #struct_ident {
+            fn from_java(env: &JNIEnv, input: JObject) -> Result<Self, JniError> {
+                #from_java
+            }
+        }
+
+        impl<'a> ToJava<'a, JObject<'a>> for #struct_ident {
+            fn to_java(&self, env: &'a JNIEnv) -> Result<JObject<'a>, JniError> {
+                #to_java
+            }
+        }
+    };
+
+    tokens.to_string()
+}
+
+/// Variant of a native enum carrying data, converted into a nested Java class.
+pub struct EnumVariant {
+    /// Name of the Rust variant
+    pub name: String,
+    /// Name of the nested Java class (e.g. `Shape$Circle`)
+    pub java_class_name: String,
+    /// Whether the variant is a tuple variant, whose fields are named `item0`, `item1`...
+    pub tuple: bool,
+    pub fields: Vec<StructField>,
+}
+
+/// Generates JNI bindings for a native enum carrying data, which is represented by a Java class
+/// for every variant.
+pub fn generate_enum(native_name: &str, variants: &[EnumVariant], context: &Context) -> String {
+    let enum_ident = syn::Ident::new(native_name, Span::call_site());
+
+    let mut patterns = Vec::new();
+    let mut to_java = Vec::new();
+    let mut from_java = Vec::new();
+
+    for variant in variants {
+        let variant_ident = syn::Ident::new(&variant.name, Span::call_site());
+        let field_names: Vec<_> = variant
+            .fields
+            .iter()
+            .map(|f| syn::Ident::new(&f.name(), Span::call_site()))
+            .collect();
+
+        patterns.push(if variant.fields.is_empty() {
+            quote! { #enum_ident::#variant_ident }
+        } else if variant.tuple {
+            quote! { #enum_ident::#variant_ident(#(ref #field_names),*) }
+        } else {
+            quote! { #enum_ident::#variant_ident { #(ref #field_names),* } }
+        });
+        to_java.push(generate_struct_to_java(
+            &variant.java_class_name,
+            &variant.fields,
+            context,
+            true,
+        ));
+
+        let fully_qualified_name = fully_qualified(&variant.java_class_name, context);
+        let conversion = generate_struct_from_java(&variant.fields, context, |fields_values| {
+            if variant.fields.is_empty() {
+                quote! { #enum_ident::#variant_ident }
+            } else if variant.tuple {
+                quote! { #enum_ident::#variant_ident(#(#fields_values),*) }
+            } else {
+                quote! { #enum_ident::#variant_ident { #(#fields_values),* } }
+            }
+        });
+        from_java.push((fully_qualified_name, conversion));
+    }
+
+    // The Java classes can't be extended outside of the enum class, so an object which is not an
+    // instance of any other variant has to be an instance of the last one.
+    let (_, last_conversion) = unwrap!(from_java.pop());
+    let checks = from_java.iter().map(|(class_name, conversion)| {
+        quote! {
+            let cls = unsafe { find_class(env, #class_name)? };
+            if env.is_instance_of(input, &cls)? {
+                return { #conversion };
+            }
+        }
+    });
 
     let tokens = quote! {
-        #from_java
+        impl<'a> FromJava<JObject<'a>> for #enum_ident {
+            fn from_java(env: &JNIEnv, input: JObject) -> Result<Self, JniError> {
+                #(#checks)*
+                #last_conversion
+            }
+        }
 
-        #to_java
+        impl<'a> ToJava<'a, JObject<'a>> for #enum_ident {
+            fn to_java(&self, env: &'a JNIEnv) -> Result<JObject<'a>, JniError> {
+                match *self {
+                    #(#patterns => { #to_java })*
+                }
+            }
+        }
+    };
+
+    tokens.to_string()
+}
+
+/// Generates JNI bindings for a native enum without data, which is represented by a Java enum.
+///
+/// The conversion goes through the discriminants of the variants (`variants` holds the name and
+/// the value of every variant).
+pub fn generate_value_enum(
+    native_name: &str,
+    java_class_name: &str,
+    variants: &[(String, i32)],
+    context: &Context,
+) -> String {
+    let enum_ident = syn::Ident::new(native_name, Span::call_site());
+    let fully_qualified_name = fully_qualified(java_class_name, context);
+    let from_value_signature = format!("(I)L{};", fully_qualified_name);
+
+    let patterns: Vec<_> = variants
+        .iter()
+        .map(|(name, _)| {
+            let variant_ident = syn::Ident::new(name, Span::call_site());
+            quote! { #enum_ident::#variant_ident }
+        })
+        .collect();
+    let patterns = &patterns;
+    let values: Vec<_> = variants.iter().map(|&(_, value)| value).collect();
+    let values = &values;
+
+    let tokens = quote! {
+        impl<'a> FromJava<JObject<'a>> for #enum_ident {
+            fn from_java(env: &JNIEnv, input: JObject) -> Result<Self, JniError> {
+                match env.call_method(input, "getValue", "()I", &[])?.i()? {
+                    #(#values => Ok(#patterns),)*
+                    value => Err(format!("invalid {} value: {}", #native_name, value).into()),
+                }
+            }
+        }
+
+        impl<'a> ToJava<'a, JObject<'a>> for #enum_ident {
+            fn to_java(&self, env: &'a JNIEnv) -> Result<JObject<'a>, JniError> {
+                let value = match *self {
+                    #(#patterns => #values,)*
+                };
+                let cls = unsafe { find_class(env, #fully_qualified_name)? };
+                env.call_static_method(&cls, "fromValue", #from_value_signature, &[value.into()])?
+                    .l()
+            }
+        }
     };
 
     tokens.to_string()
@@ -1031,6 +1386,7 @@ mod tests {
                 let inputs: Vec<_> = func.decl.inputs.iter().cloned().collect();
                 let generated_jni_string = generate_jni_function(
                     inputs.as_slice(),
+                    &func.decl.output,
                     &func.attrs[..],
                     native_name,
                     func_name,
@@ -1103,6 +1459,7 @@ mod tests {
                 let inputs: Vec<_> = func.decl.inputs.iter().cloned().collect();
                 let generated_jni_string = generate_jni_function(
                     inputs.as_slice(),
+                    &func.decl.output,
                     &func.attrs[..],
                     native_name,
                     func_name,
@@ -1,40 +1,62 @@
 //! Functions for generating Java glue code.
 
-mod jni;
-mod types;
+pub(crate) mod futures;
+pub(crate) mod jni;
+pub(crate) mod types;
+
+pub use self::types::java_type_from_str;
 
 use crate::common::{
     self, append_output, check_no_mangle, is_array_arg, is_array_arg_barefn, is_user_data_arg,
     is_user_data_arg_barefn, parse_attr, retrieve_docstring, take_out_pat,
     transform_fnarg_to_argcap, FilterMode, Outputs,
 };
+use crate::csharp::intermediate::{self, Const, ConstValue, Snippet, Type};
+use crate::csharp::resolve_alias;
 use crate::java::types::{callback_name, java_type_to_str, rust_to_java, struct_to_java_classname};
 use crate::struct_field::{transform_struct_fields, StructField};
-use crate::{Error, Level};
+use crate::{Config, Error, Level};
 use ::jni::signature::JavaType;
 use ::rustfmt::{self, format_input};
 use inflector::Inflector;
 use quote::*;
 use std::collections::{BTreeSet, HashMap, HashSet};
+use std::convert::TryFrom;
+use syn::spanned::Spanned;
 use unwrap::unwrap;
 
 pub struct LangJava {
     context: Context,
     filter: HashSet<String>,
     filter_mode: FilterMode,
+    /// Name of the class holding the constants
+    consts_class: String,
+    /// Namespace of the constants class (the namespace of `NativeBindings` if not set)
+    consts_namespace: Option<String>,
+    consts: Vec<Snippet<Const>>,
+    /// Type aliases, resolved when the constants are generated
+    aliases: HashMap<String, Type>,
+    /// Whether the free function is one of the parsed functions, which are declared already
+    free_fn_declared: bool,
 }
 
 pub struct Context {
     /// Native library name
-    lib_name: String,
+    pub(crate) lib_name: String,
     /// Namespace
-    namespace: String,
+    pub(crate) namespace: String,
     /// Model namespace (structures go into this one)
-    namespace_model: String,
+    pub(crate) namespace_model: String,
     /// Maps types from Rust to Java
-    type_map: HashMap<&'static str, JavaType>,
+    type_map: HashMap<String, JavaType>,
     /// Keeps track of which JNI callback functions has been generated already
     generated_jni_cbs: BTreeSet<String>,
+    /// Maps enums without data, which become Java enums, to the constant their fields default to
+    pub(crate) enums: HashMap<String, String>,
+    /// Native function freeing the pointers returned by functions
+    free_fn: Option<String>,
+    /// Whether returned pointers are owned by the caller, rather than borrowed
+    owned_returns: bool,
 }
 
 impl Default for Context {
@@ -45,6 +67,9 @@ impl Default for Context {
             namespace_model: "net.maidsafe.dummy".to_string(),
             type_map: Default::default(),
             generated_jni_cbs: Default::default(),
+            enums: Default::default(),
+            free_fn: None,
+            owned_returns: false,
         }
     }
 }
@@ -54,13 +79,24 @@ impl LangJava {
         LangJava {
             filter: Default::default(),
             filter_mode: FilterMode::Blacklist,
+            consts_class: "Constants".to_owned(),
+            consts_namespace: None,
+            consts: Vec::new(),
+            aliases: HashMap::new(),
             context: Context {
-                type_map,
+                type_map: type_map
+                    .into_iter()
+                    .map(|(name, ty)| (name.to_string(), ty))
+                    .collect(),
                 lib_name: "backend".to_owned(),
                 namespace: "net.maidsafe.bindings".to_owned(),
                 namespace_model: "net.maidsafe.model".to_owned(),
                 generated_jni_cbs: BTreeSet::new(),
+                enums: HashMap::new(),
+                free_fn: None,
+                owned_returns: false,
             },
+            free_fn_declared: false,
         }
     }
 
@@ -69,6 +105,11 @@ impl LangJava {
         self.context.lib_name = name.into();
     }
 
+    /// Use the Java type `ty` for the Rust type `name`.
+    pub fn add_type_mapping<T: Into<String>>(&mut self, name: T, ty: JavaType) {
+        let _ = self.context.type_map.insert(name.into(), ty);
+    }
+
     /// Set the namespace to put the NativeBindings class in.
     pub fn set_namespace<T: Into<String>>(&mut self, namespace: T) {
         self.context.namespace = namespace.into();
@@ -79,6 +120,77 @@ impl LangJava {
         self.context.namespace_model = namespace.into();
     }
 
+    /// Set the native function freeing the pointers returned by functions, once they are
+    /// converted to Java. It has to take a `*mut c_void`, and is only called if returns are owned.
+    pub fn set_free_function<T: Into<String>>(&mut self, name: T) {
+        self.context.free_fn = Some(name.into());
+    }
+
+    /// Set whether the strings and structures returned by functions are owned by the caller, and
+    /// so freed with the free function, or borrowed from the native library (the default).
+    ///
+    /// Handles, which are pointers to types mapped to a Java primitive, are always borrowed.
+    pub fn set_owned_returns(&mut self, owned: bool) {
+        self.context.owned_returns = owned;
+    }
+
+    /// Set the name of the class holding the constants (`Constants` by default).
+    pub fn set_consts_class<T: Into<String>>(&mut self, name: T) {
+        self.consts_class = name.into();
+    }
+
+    /// Set the namespace to put the constants class in.
+    pub fn set_consts_namespace<T: Into<String>>(&mut self, namespace: T) {
+        self.consts_namespace = Some(namespace.into());
+    }
+
+    /// Generates the constants class, with the type aliases resolved.
+    fn generate_consts(&mut self) -> Result<String, Error> {
+        let mut fields = String::new();
+        for snippet in &mut self.consts {
+            resolve_alias(&self.aliases, &mut snippet.item.ty);
+
+            let ty = const_type_to_java(&snippet.item.ty);
+            let value = ty
+                .as_ref()
+                .and_then(|_| const_value_to_java(&snippet.item.ty, &snippet.item.value));
+            let (ty, value) = match (ty, value) {
+                (Some(ty), Some(value)) => (ty, value),
+                _ => {
+                    return Err(Error {
+                        level: Level::Error,
+                        span: None,
+                        file: None,
+                        item: Some(snippet.name.clone()),
+                        message: format!("bindgen cannot handle constant {}", snippet.name),
+                    });
+                }
+            };
+
+            fields.push_str(&snippet.docs);
+            fields.push_str(&format!(
+                "\tpublic static final {} {} = {};\n",
+                ty, snippet.name, value
+            ));
+        }
+
+        Ok(format!(
+            "package {namespace};\n\n\
+             public final class {name} {{\n\
+             {fields}\
+             \n\
+             \tprivate {name}() {{\n\
+             \t}}\n\
+             }}\n\n",
+            namespace = self
+                .consts_namespace
+                .as_ref()
+                .unwrap_or(&self.context.namespace),
+            name = self.consts_class,
+            fields = fields,
+        ))
+    }
+
     /// Applies rustfmt to JNI code to improve debuggability
     fn format_jni_output(&self, input: &mut String) {
         let mut output: Vec<u8> = Vec::with_capacity(input.len() * 2);
@@ -122,7 +234,12 @@ impl LangJava {
         self.filter_mode = filter_mode;
     }
 
-    fn is_ignored(&self, ident: &str) -> bool {
+    /// Returns the context shared with the JNI generation.
+    pub(crate) fn context(&self) -> &Context {
+        &self.context
+    }
+
+    pub(crate) fn is_ignored(&self, ident: &str) -> bool {
         match self.filter_mode {
             FilterMode::Blacklist => self.filter.contains(ident),
             FilterMode::Whitelist => !self.filter.contains(ident),
@@ -131,30 +248,211 @@ impl LangJava {
 }
 
 impl common::Lang for LangJava {
+    fn apply_config(&mut self, config: &Config) {
+        if let Some(lib_name) = config.lib_name_or(&config.java.lib_name) {
+            self.set_lib_name(lib_name);
+        }
+        if let Some(ref namespace) = config.java.namespace {
+            self.set_namespace(namespace.as_str());
+        }
+        if let Some(ref namespace) = config.java.model_namespace {
+            self.set_model_namespace(namespace.as_str());
+        }
+        if let Some(ref name) = config.java.free_function {
+            self.set_free_function(name.as_str());
+        }
+        if let Some(owned) = config.java.owned_returns {
+            self.set_owned_returns(owned);
+        }
+        if let Some(ref name) = config.java.consts_class {
+            self.set_consts_class(name.as_str());
+        }
+        if let Some(ref namespace) = config.java.consts_namespace {
+            self.set_consts_namespace(namespace.as_str());
+        }
+        for (name, ty) in &config.java.type_map {
+            self.add_type_mapping(name.as_str(), ty.clone());
+        }
+        if let Some(ref filter) = config.java.filter {
+            self.reset_filter(filter.mode);
+            for ident in &filter.idents {
+                self.filter(ident.as_str());
+            }
+        }
+    }
+
+    fn declare_enum(&mut self, item: &syn::ItemEnum) {
+        let name = item.ident.to_string();
+        if !self.is_ignored(&name)
+            && common::enum_repr(&item.attrs).is_defined()
+            && !common::has_data_variants(item)
+        {
+            declare_value_enum(item, &mut self.context);
+        }
+    }
+
+    /// Add a Rust constant to the constants class.
+    ///
+    /// Integer, float, bool and string constants are supported, as well as arrays of those.
     fn parse_const(
         &mut self,
-        _item: &syn::ItemConst,
+        item: &syn::ItemConst,
         _module: &[String],
         _outputs: &mut Outputs,
     ) -> Result<(), Error> {
+        let name = item.ident.to_string();
+        if self.is_ignored(&name) {
+            return Ok(());
+        }
+        let (_, docs) = parse_attr(&item.attrs, |_| true, |attr| retrieve_docstring(attr, "\t"));
+        let item = intermediate::transform_const(&item.ty, &item.expr).ok_or_else(|| Error {
+            level: Level::Error,
+            span: Some(item.ident.span()),
+            file: None,
+            item: None,
+            message: format!("bindgen cannot handle constant {}", name),
+        })?;
+
+        self.consts.push(Snippet { docs, name, item });
+
         Ok(())
     }
 
+    /// Record a type alias, so constants using it get the right Java type.
     fn parse_ty(
         &mut self,
-        _item: &syn::ItemType,
+        item: &syn::ItemType,
         _module: &[String],
         _outputs: &mut Outputs,
     ) -> Result<(), Error> {
+        // Parameterized type aliases are not supported.
+        if !item.generics.params.is_empty() {
+            return Ok(());
+        }
+        if let Some(ty) = intermediate::transform_type(&item.ty) {
+            let _ = self.aliases.insert(item.ident.to_string(), ty);
+        }
+
         Ok(())
     }
 
+    /// Convert a Rust enum carrying data into a Java class hierarchy.
+    ///
+    /// The enum becomes an abstract class with a nested class for every variant. As its only
+    /// constructor is private, no other classes can extend it.
+    ///
+    /// Enums without data are converted into Java enums instead.
     fn parse_enum(
         &mut self,
-        _item: &syn::ItemEnum,
+        item: &syn::ItemEnum,
         _module: &[String],
-        _outputs: &mut Outputs,
+        outputs: &mut Outputs,
     ) -> Result<(), Error> {
+        let name = item.ident.to_string();
+        if self.is_ignored(&name) {
+            return Ok(());
+        }
+        // Only enums with a defined layout can be called from C.
+        if !common::enum_repr(&item.attrs).is_defined() {
+            return Ok(());
+        }
+        if !common::has_data_variants(item) {
+            return transform_enum(item, outputs, &mut self.context);
+        }
+        if !item.generics.params.is_empty() {
+            return Err(Error {
+                level: Level::Error,
+                span: Some(item.generics.span()),
+                file: None,
+                item: None,
+                message: "cheddar cannot handle parameterized enums".into(),
+            });
+        }
+        let (_, docs) = parse_attr(&item.attrs, |_| true, |attr| retrieve_docstring(attr, ""));
+
+        let class_name = struct_to_java_classname(&name);
+
+        let mut buffer = String::new();
+        buffer.push_str(&format!("package {};\n\n", self.context.namespace_model));
+        buffer.push_str(&docs);
+        buffer.push_str(&format!(
+            "public abstract class {name} {{\n\tprivate {name}() {{\n\t}}\n",
+            name = class_name
+        ));
+
+        let mut variants = Vec::new();
+        for variant in &item.variants {
+            let (_, docs) = parse_attr(
+                &variant.attrs,
+                |_| true,
+                |attr| retrieve_docstring(attr, "\t"),
+            );
+            let variant_class = struct_to_java_classname(variant.ident.to_string());
+
+            // Fields of tuple variants are named after their position.
+            let tuple = matches!(variant.fields, syn::Fields::Unnamed(..));
+            let fields: Vec<_> = variant
+                .fields
+                .iter()
+                .enumerate()
+                .map(|(index, field)| {
+                    let mut field = field.clone();
+                    if field.ident.is_none() {
+                        field.ident =
+                            Some(syn::Ident::new(&format!("item{}", index), field.span()));
+                    }
+                    field
+                })
+                .collect();
+            let struct_fields = transform_struct_fields(&fields);
+            let class_fields = transform_struct_into_class_fields(&struct_fields, &self.context)?;
+
+            let mut class = String::new();
+            if !class_fields.is_empty() {
+                class.push_str(&generate_class_fields(&class_fields)?);
+                class.push('\n');
+            }
+            class.push_str(&generate_default_constructor(
+                &variant_class,
+                &class_fields,
+            )?);
+            if !class_fields.is_empty() {
+                class.push_str(&generate_parametrised_constructor(
+                    &variant_class,
+                    &class_fields,
+                )?);
+            }
+            class.push_str(&generate_getters_setters(&class_fields)?);
+
+            buffer.push('\n');
+            buffer.push_str(&docs);
+            buffer.push_str(&format!(
+                "\tpublic static final class {} extends {} {{\n",
+                variant_class, class_name
+            ));
+            for line in class.lines() {
+                if !line.is_empty() {
+                    buffer.push('\t');
+                }
+                buffer.push_str(line);
+                buffer.push('\n');
+            }
+            buffer.push_str("\t}\n");
+
+            variants.push(jni::EnumVariant {
+                name: variant.ident.to_string(),
+                java_class_name: format!("{}${}", class_name, variant_class),
+                tuple,
+                fields: struct_fields,
+            });
+        }
+        buffer.push_str("}\n\n");
+
+        let jni = jni::generate_enum(&name, &variants, &self.context);
+        append_output(jni, "jni.rs", outputs);
+
+        outputs.insert(format!("{}.java", class_name), buffer);
+
         Ok(())
     }
 
@@ -178,7 +476,7 @@ impl common::Lang for LangJava {
         if !no_mangle {
             return Ok(());
         }
-        if !common::is_extern(unwrap!(item.to_owned().abi)) {
+        if item.abi.is_none() || !common::is_extern(unwrap!(item.to_owned().abi)) {
             // If it doesn't have a C ABI it can't be called from C.
             return Ok(());
         }
@@ -186,7 +484,9 @@ impl common::Lang for LangJava {
         if !item.decl.generics.params.is_empty() {
             return Err(Error {
                 level: Level::Error,
-                span: None, //NONE FOR NOW
+                span: Some(item.decl.generics.span()),
+                file: None,
+                item: None,
                 message: "cheddar cannot handle parameterized extern functions".into(),
             });
         }
@@ -199,6 +499,9 @@ impl common::Lang for LangJava {
             outputs,
             &mut self.context,
         )?;
+        if self.context.free_fn.as_ref() == Some(&name) {
+            self.free_fn_declared = true;
+        }
 
         Ok(())
     }
@@ -223,7 +526,7 @@ impl common::Lang for LangJava {
         }
 
         let mut buffer = String::new();
-        buffer.push_str(&format!("package {};\n\n", self.context.namespace));
+        buffer.push_str(&format!("package {};\n\n", self.context.namespace_model));
         buffer.push_str(&docs);
 
         let orig_name = item.ident.to_owned().to_string();
@@ -233,7 +536,9 @@ impl common::Lang for LangJava {
         if !item.generics.params.is_empty() {
             return Err(Error {
                 level: Level::Error,
-                span: None, // NONE FOR NOW
+                span: Some(item.generics.span()),
+                file: None,
+                item: None,
                 message: "cheddar cannot handle parameterized `#[repr(C)]` structs".into(),
             });
         }
@@ -272,6 +577,34 @@ impl common::Lang for LangJava {
     }
 
     fn finalise_output(&mut self, outputs: &mut Outputs) -> Result<(), Error> {
+        if !self.consts.is_empty() {
+            let consts = self.generate_consts()?;
+            outputs.insert(format!("{}.java", self.consts_class), consts);
+        }
+
+        if self.context.owned_returns && self.context.free_fn.is_none() {
+            return Err(Error {
+                level: Level::Error,
+                span: None,
+                file: None,
+                item: None,
+                message: "returns are owned, but there is no free function to free them".to_owned(),
+            });
+        }
+        if let Some(ref free_fn) = self.context.free_fn {
+            if self.context.owned_returns
+                && !self.free_fn_declared
+                && outputs.contains_key("jni.rs")
+            {
+                let jni = format!(
+                    "\n#[link(name = \"{libname}\")]\nextern {{ fn {free_fn}(ptr: *mut c_void); }}\n",
+                    libname = self.context.lib_name,
+                    free_fn = free_fn,
+                );
+                append_output(jni, "jni.rs", outputs);
+            }
+        }
+
         match outputs.get_mut("jni.rs") {
             Some(input) => {
                 self.format_jni_output(input);
@@ -279,36 +612,110 @@ impl common::Lang for LangJava {
             None => {
                 return Err(Error {
                     level: Level::Error,
-                    span: None, //NONE FOR NOW
+                    span: None,
+                    file: None,
+                    item: None,
                     message: "no jni bindings generated?".to_owned(),
                 });
             }
         }
 
+        let class_file = futures::class_file(&self.context);
+        if let Some(input) = outputs.get_mut(&class_file) {
+            futures::format_class(input, &self.context);
+            outputs.insert(
+                "FfiException.java".to_owned(),
+                futures::ffi_exception(&self.context),
+            );
+        }
+
         match outputs.get_mut("NativeBindings.java") {
             Some(input) => {
                 self.format_native_functions(input);
-                Ok(())
             }
-            None => Err(Error {
-                level: Level::Error,
-                span: None, //NONE FOR NOW
-                message: "no native bindings generated?".to_owned(),
-            }),
+            None => {
+                return Err(Error {
+                    level: Level::Error,
+                    span: None,
+                    file: None,
+                    item: None,
+                    message: "no native bindings generated?".to_owned(),
+                });
+            }
         }
+
+        import_packages(outputs);
+        Ok(())
+    }
+}
+
+/// Returns the package a generated Java file declares.
+fn package_of(code: &str) -> Option<&str> {
+    let line = code.lines().next()?;
+    if line.starts_with("package ") && line.ends_with(';') {
+        Some(&line["package ".len()..line.len() - 1])
+    } else {
+        None
+    }
+}
+
+/// Makes every generated class see the classes in the other packages.
+///
+/// Structures, enums and callbacks refer to each other across the bindings, model and
+/// constants namespaces, which only compiles when the packages import one another.
+fn import_packages(outputs: &mut Outputs) {
+    let packages: BTreeSet<String> = outputs
+        .iter()
+        .filter(|(name, _)| name.ends_with(".java"))
+        .filter_map(|(_, code)| package_of(code))
+        .map(str::to_string)
+        .collect();
+    if packages.len() < 2 {
+        return;
+    }
+
+    for (name, code) in outputs.iter_mut() {
+        if !name.ends_with(".java") {
+            continue;
+        }
+        let package = match package_of(code) {
+            Some(package) => package.to_string(),
+            None => continue,
+        };
+        let imports = packages.iter().filter(|&other| *other != package).fold(
+            String::new(),
+            |mut imports, other| {
+                imports.push_str(&format!("import {}.*;\n", other));
+                imports
+            },
+        );
+
+        let header = format!("package {};\n\n", package);
+        if !code.starts_with(&header) {
+            continue;
+        }
+        let body = code[header.len()..].to_string();
+        let separator = if body.starts_with("import ") {
+            ""
+        } else {
+            "\n"
+        };
+        *code = format!("{}{}{}{}", header, imports, separator, body);
     }
 }
 
 /// Contains all information necessary to construct a Java class
 /// field, transformed from `StructField`.
-struct JavaClassField {
-    name: String,
-    ty: JavaType,
-    ty_str: String,
+pub(crate) struct JavaClassField {
+    pub name: String,
+    pub ty: JavaType,
+    pub ty_str: String,
+    /// Enum constant the field is initialised with, for fields holding a Java enum
+    pub enum_default: Option<String>,
 }
 
 /// Transforms a list of struct fields into Java class fields
-fn transform_struct_into_class_fields(
+pub(crate) fn transform_struct_into_class_fields(
     fields: &[StructField],
     context: &Context,
 ) -> Result<Vec<JavaClassField>, Error> {
@@ -323,8 +730,20 @@ fn transform_struct_into_class_fields(
             ty = JavaType::Array(Box::new(ty));
         }
         let ty_str = java_type_to_str(&ty)?;
+        let enum_default = match struct_field.ty {
+            syn::Type::Path(ref path) if path.path.segments.len() == 1 => context
+                .enums
+                .get(&path.path.segments[0].ident.to_string())
+                .cloned(),
+            _ => None,
+        };
 
-        class_fields.push(JavaClassField { name, ty, ty_str });
+        class_fields.push(JavaClassField {
+            name,
+            ty,
+            ty_str,
+            enum_default,
+        });
     }
 
     Ok(class_fields)
@@ -374,6 +793,14 @@ fn generate_default_constructor(
 
     for field in fields {
         // Initialise object and array fields with default values to prevent them from being null
+        if let Some(ref constant) = field.enum_default {
+            default_obj_fields.push(format!(
+                "\t\tthis.{name} = {constant};",
+                name = field.name,
+                constant = constant
+            ));
+            continue;
+        }
         match field.ty {
             JavaType::Array(..) => {
                 default_obj_fields.push(format!(
@@ -421,46 +848,169 @@ fn generate_parametrised_constructor(
     ))
 }
 
-/// Transform a Rust FFI function into a Java native function
-pub fn transform_native_fn(
-    fn_decl: syn::FnDecl,
-    attrs: &[syn::Attribute],
-    docs: &str,
-    name: &str,
+/// Convert a Rust enum without data into a Java enum.
+///
+/// Every constant holds the discriminant of its variant, which is how the JNI bindings convert
+/// between both sides (`getValue()` and `fromValue(int)`).
+fn transform_enum(
+    item: &syn::ItemEnum,
     outputs: &mut Outputs,
     context: &mut Context,
 ) -> Result<(), Error> {
-    let mut args_str = Vec::new();
+    let name = item.ident.to_string();
+    let class_name = struct_to_java_classname(&name);
+    let (_, docs) = parse_attr(&item.attrs, |_| true, |attr| retrieve_docstring(attr, ""));
+
+    let mut constants = Vec::new();
+    let mut variants = Vec::new();
+    let values = common::enum_variant_values(item)?;
+    for (variant, value) in item.variants.iter().zip(values) {
+        // The discriminants are held by a Java `int`.
+        let value = i32::try_from(value).map_err(|_| Error {
+            level: Level::Error,
+            span: Some(variant.span()),
+            file: None,
+            item: None,
+            message: format!(
+                "bindgen cannot handle the discriminant {} of `{}::{}`, as it doesn't fit in \
+                 a Java int",
+                value, name, variant.ident
+            ),
+        })?;
+        let (_, docs) = parse_attr(
+            &variant.attrs,
+            |_| true,
+            |attr| retrieve_docstring(attr, "\t"),
+        );
+        constants.push(format!(
+            "{}\t{}({})",
+            docs,
+            variant.ident.to_string().to_screaming_snake_case(),
+            value
+        ));
+        variants.push((variant.ident.to_string(), value));
+    }
+
+    let mut buffer = String::new();
+    buffer.push_str(&format!("package {};\n\n", context.namespace_model));
+    buffer.push_str(&docs);
+    buffer.push_str(&format!(
+        "public enum {name} {{\n\
+         {constants};\n\
+         \n\
+         \tprivate final int value;\n\
+         \n\
+         \t{name}(int value) {{\n\
+         \t\tthis.value = value;\n\
+         \t}}\n\
+         \n\
+         \tpublic int getValue() {{\n\
+         \t\treturn value;\n\
+         \t}}\n\
+         \n\
+         \tpublic static {name} fromValue(int value) {{\n\
+         \t\tfor ({name} item : values()) {{\n\
+         \t\t\tif (item.value == value) {{\n\
+         \t\t\t\treturn item;\n\
+         \t\t\t}}\n\
+         \t\t}}\n\
+         \t\tthrow new IllegalArgumentException(\"Invalid {name} value: \" + value);\n\
+         \t}}\n\
+         }}\n\n",
+        name = class_name,
+        constants = constants.join(",\n"),
+    ));
+
+    declare_value_enum(item, context);
+
+    let jni = jni::generate_value_enum(&name, &class_name, &variants, context);
+    append_output(jni, "jni.rs", outputs);
+
+    outputs.insert(format!("{}.java", class_name), buffer);
+
+    Ok(())
+}
+
+/// Records an enum without data, so that the items using it treat it as a Java enum whose fields
+/// default to its first constant.
+fn declare_value_enum(item: &syn::ItemEnum, context: &mut Context) {
+    if let Some(variant) = item.variants.first() {
+        let class_name = struct_to_java_classname(item.ident.to_string());
+        let constant = variant.value().ident.to_string().to_screaming_snake_case();
+        let _ = context.enums.insert(
+            item.ident.to_string(),
+            format!("{}.{}", class_name, constant),
+        );
+    }
+}
+
+/// Parameter of a native function declared in `NativeBindings`.
+pub(crate) struct NativeParam<'a> {
+    /// Name of the parameter (in camel case)
+    pub name: String,
+    pub ty: JavaType,
+    /// Type of the Rust parameter (of the pointer for arrays)
+    pub rust_ty: &'a syn::Type,
+}
+
+/// Returns the parameters of a native function as declared in Java. The `user_data` parameter
+/// is left out and arrays are passed without their length.
+pub(crate) fn native_fn_params<'a>(
+    fn_decl: &'a syn::FnDecl,
+    context: &Context,
+) -> Result<Vec<NativeParam<'a>>, Error> {
+    let mut params = Vec::new();
 
     let mut fn_args = fn_decl
         .inputs
         .iter()
-        .filter(|arg| !is_user_data_arg(&unwrap!(transform_fnarg_to_argcap(*arg))))
+        .filter(|arg| !is_user_data_arg(unwrap!(transform_fnarg_to_argcap(arg))))
         .peekable();
 
     while let Some(arg) = fn_args.next() {
-        let pat = take_out_pat(&unwrap!(transform_fnarg_to_argcap(arg)).pat);
-        let arg_name = unwrap!(pat).ident.to_string();
+        let argcap = unwrap!(transform_fnarg_to_argcap(arg));
+        let arg_name = unwrap!(take_out_pat(&argcap.pat)).ident.to_string();
 
-        // Generate function arguments
-        let mut java_type = rust_to_java(&unwrap!(transform_fnarg_to_argcap(arg)).ty, context)?;
-        let next_arg: Option<&syn::ArgCaptured> = if fn_args.peek().is_some() {
-            Some(unwrap!(transform_fnarg_to_argcap(unwrap!(fn_args.peek()))))
-        } else {
-            None
-        };
-        if is_array_arg(unwrap!(transform_fnarg_to_argcap(arg)), next_arg) {
+        let mut java_type = rust_to_java(&argcap.ty, context)?;
+        let next_arg = fn_args
+            .peek()
+            .map(|arg| unwrap!(transform_fnarg_to_argcap(arg)));
+        if is_array_arg(argcap, next_arg) {
             // Skip the length args - e.g. for a case of `ptr: *const u8, ptr_len: usize`
             // we're going to skip the `len` part.
             java_type = JavaType::Array(Box::new(java_type));
             fn_args.next();
         }
 
-        let java_type = java_type_to_str(&java_type)?;
-        args_str.push(format!("{} {}", java_type, arg_name.to_camel_case()));
-        let argcap = unwrap!(transform_fnarg_to_argcap(arg));
+        params.push(NativeParam {
+            name: arg_name.to_camel_case(),
+            ty: java_type,
+            rust_ty: &argcap.ty,
+        });
+    }
+
+    Ok(params)
+}
+
+/// Transform a Rust FFI function into a Java native function
+pub fn transform_native_fn(
+    fn_decl: syn::FnDecl,
+    attrs: &[syn::Attribute],
+    docs: &str,
+    name: &str,
+    outputs: &mut Outputs,
+    context: &mut Context,
+) -> Result<(), Error> {
+    let mut args = Vec::new();
+    let mut callbacks = Vec::new();
+
+    for param in native_fn_params(&fn_decl, context)? {
+        args.push((java_type_to_str(&param.ty)?, param.name));
+
         // Generate a callback class - if it wasn't generated already
-        if let syn::Type::BareFn(ref bare_fn) = argcap.ty {
+        if let syn::Type::BareFn(ref bare_fn) = *param.rust_ty {
+            callbacks.push((args.len() - 1, bare_fn));
+
             let mut vec = vec![];
             for input in bare_fn.inputs.to_owned() {
                 vec.push(input);
@@ -471,7 +1021,7 @@ pub fn transform_native_fn(
             if outputs.get(&cb_file).is_none() {
                 eprintln!("Generating CB {}", cb_class);
 
-                let cb_output = transform_callback(&argcap.ty, &cb_class, context)?;
+                let cb_output = transform_callback(param.rust_ty, &cb_class, context)?;
                 let _ = outputs.insert(cb_file, cb_output);
 
                 // Generate JNI callback fn
@@ -492,14 +1042,17 @@ pub fn transform_native_fn(
         syn::ReturnType::Type(_, ref ty) if check_type_never(&*ty) => {
             return Err(Error {
                 level: Level::Error,
-                span: None, //NONE FOR NOW
+                span: Some(ty.span()),
+                file: None,
+                item: None,
                 message: "panics across a C boundary are naughty!".into(),
             });
         }
         syn::ReturnType::Default => String::from("public static native void"),
-        syn::ReturnType::Type(_, ref ty) => {
-            java_type_to_str(&unwrap!(rust_to_java(&*ty, context)))?
-        }
+        syn::ReturnType::Type(_, ref ty) => format!(
+            "public static native {}",
+            java_type_to_str(&rust_to_java(ty, context)?)?
+        ),
     };
 
     let java_name = name.to_camel_case();
@@ -507,7 +1060,10 @@ pub fn transform_native_fn(
         "{} {}({})",
         return_type,
         &java_name,
-        args_str.as_slice().join(", ")
+        args.iter()
+            .map(|(ty, name)| format!("{} {}", ty, name))
+            .collect::<Vec<_>>()
+            .join(", ")
     );
 
     let mut buffer = String::new();
@@ -519,6 +1075,20 @@ pub fn transform_native_fn(
 
     append_output(buffer, "NativeBindings.java", outputs);
 
+    // Functions taking a single callback which receives an `FfiResult` also get a wrapper
+    // returning a `CompletableFuture`.
+    let has_user_data = fn_decl
+        .inputs
+        .iter()
+        .any(|arg| is_user_data_arg(unwrap!(transform_fnarg_to_argcap(arg))));
+    if let (true, [(index, callback)]) = (has_user_data, callbacks.as_slice()) {
+        if let Some(wrapper) =
+            futures::generate_wrapper(&java_name, docs, &args, *index, callback, context)?
+        {
+            append_output(wrapper, &futures::class_file(context), outputs);
+        }
+    }
+
     // Append the function declaration to import it as an "extern fn"
     let mut fn_attrs = String::new();
     for attr in attrs {
@@ -526,7 +1096,7 @@ pub fn transform_native_fn(
             fn_attrs.push_str(&attr.into_token_stream().to_owned().to_string());
         }
     }
-    let args: Vec<_> = fn_decl
+    let native_args: Vec<_> = fn_decl
         .inputs
         .iter()
         .map(|arg| {
@@ -536,7 +1106,13 @@ pub fn transform_native_fn(
         })
         .collect();
 
-    let fn_declaration = format!("fn {}({})", name, args.join(", "));
+    let output = &fn_decl.output;
+    let fn_declaration = format!(
+        "fn {}({}) {}",
+        name,
+        native_args.join(", "),
+        quote!(#output)
+    );
 
     let mut jni = format!(
         "\n{attrs}#[link(name = \"{libname}\")]\nextern {{ {fndecl}; }}\n",
@@ -547,7 +1123,13 @@ pub fn transform_native_fn(
     let vec: Vec<_> = fn_decl.inputs.iter().cloned().collect();
     // Generate the JNI part of the interface
     jni.push_str(&jni::generate_jni_function(
-        &vec, attrs, name, &java_name, context, outputs,
+        &vec,
+        &fn_decl.output,
+        attrs,
+        name,
+        &java_name,
+        context,
+        outputs,
     ));
     jni.push('\n');
     append_output(jni, "jni.rs", outputs);
@@ -555,6 +1137,103 @@ pub fn transform_native_fn(
     Ok(())
 }
 
+/// Returns the Java type of a constant, which can only be a primitive, a string or an array.
+fn const_type_to_java(ty: &Type) -> Option<String> {
+    let java_ty = match *ty {
+        Type::Bool => "boolean",
+        Type::CChar | Type::I8 | Type::U8 => "byte",
+        Type::I16 | Type::U16 => "short",
+        Type::I32 | Type::U32 => "int",
+        Type::I64 | Type::U64 | Type::ISize | Type::USize => "long",
+        Type::F32 => "float",
+        Type::F64 => "double",
+        Type::String => "String",
+        Type::Array(ref ty, _) => return const_type_to_java(ty).map(|ty| format!("{}[]", ty)),
+        _ => return None,
+    };
+    Some(java_ty.to_owned())
+}
+
+/// Returns the Java literal of a constant value of the type `ty`.
+///
+/// Java has no unsigned types, so unsigned values out of the range of the signed type are cast.
+fn const_value_to_java(ty: &Type, value: &ConstValue) -> Option<String> {
+    let literal = match (ty, value) {
+        (Type::Bool, ConstValue::Bool(value)) => value.to_string(),
+        (Type::CChar, ConstValue::Int(value))
+        | (Type::I8, ConstValue::Int(value))
+        | (Type::U8, ConstValue::Int(value)) => {
+            cast_int_literal(*value, i64::from(i8::MAX), "byte")
+        }
+        (Type::I16, ConstValue::Int(value)) | (Type::U16, ConstValue::Int(value)) => {
+            cast_int_literal(*value, i64::from(i16::MAX), "short")
+        }
+        (Type::I32, ConstValue::Int(value)) | (Type::U32, ConstValue::Int(value)) => {
+            cast_int_literal(*value, i64::from(i32::MAX), "int")
+        }
+        (Type::I64, ConstValue::Int(value))
+        | (Type::U64, ConstValue::Int(value))
+        | (Type::ISize, ConstValue::Int(value))
+        | (Type::USize, ConstValue::Int(value)) => format!("{}L", value),
+        (Type::F32, ConstValue::Float(value)) => format!("{}f", float_literal(value)),
+        (Type::F64, ConstValue::Float(value)) => float_literal(value),
+        (Type::String, ConstValue::String(value)) => string_literal(value),
+        (Type::Array(ty, _), ConstValue::Array(elements)) => {
+            let elements: Option<Vec<_>> = elements
+                .iter()
+                .map(|element| const_value_to_java(ty, element))
+                .collect();
+            format!("{{ {} }}", elements?.join(", "))
+        }
+        _ => return None,
+    };
+    Some(literal)
+}
+
+fn cast_int_literal(value: i64, max: i64, ty: &str) -> String {
+    if value > i64::from(i32::MAX) {
+        // Literals out of the range of `int` have to be `long` literals.
+        format!("({}) {}L", ty, value)
+    } else if value > max {
+        format!("({}) {}", ty, value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Returns the Java literal of a string.
+///
+/// Java translates `\u` escapes before parsing string literals, so control characters are
+/// escaped as octal instead, and only the other non-ASCII characters as UTF-16 code units.
+fn string_literal(value: &str) -> String {
+    let mut literal = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            ' '..='~' => literal.push(c),
+            _ if (c as u32) < 0x80 => literal.push_str(&format!("\\{:03o}", c as u32)),
+            _ => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    literal.push_str(&format!("\\u{:04x}", unit));
+                }
+            }
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+fn float_literal(value: &str) -> String {
+    value
+        .trim_end_matches("f32")
+        .trim_end_matches("f64")
+        .replace('_', "")
+}
+
 fn check_type_never(ty: &syn::Type) -> bool {
     matches!(ty, syn::Type::Never(ref _never))
 }
@@ -577,7 +1256,9 @@ pub fn transform_callback<S: AsRef<str>>(
         // All other types just have a name associated with them.
         _ => Err(Error {
             level: Level::Error,
-            span: None, //NONE FOR NOW
+            span: Some(ty.span()),
+            file: None,
+            item: None,
             message: "Invalid callback type".into(),
         }),
     }
@@ -585,13 +1266,28 @@ pub fn transform_callback<S: AsRef<str>>(
 
 /// Transform a Rust FFI callback into Java function signature
 fn callback_to_java(fn_ty: &syn::TypeBareFn, context: &Context) -> Result<String, Error> {
+    let args = callback_params(fn_ty, context)?
+        .into_iter()
+        .map(|(java_type, name)| Ok(format!("{} {}", java_type_to_str(&java_type)?, name)))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(args.join(", "))
+}
+
+/// Returns the Java types and names of the parameters of a Rust FFI callback.
+pub(crate) fn callback_params(
+    fn_ty: &syn::TypeBareFn,
+    context: &Context,
+) -> Result<Vec<(JavaType, String)>, Error> {
     match unwrap!(unwrap!(fn_ty.to_owned().abi).name).value().as_str() {
         // If it doesn't have a C ABI it can't be called from C.
         "C" | "Cdecl" | "Stdcall" | "Fastcall" | "System" => {}
         _ => {
             return Err(Error {
                 level: Level::Error,
-                span: None, //NONE FOR NOW
+                span: Some(fn_ty.abi.span()),
+                file: None,
+                item: None,
                 message: "callbacks that don't have C ABI are not supported".into(),
             });
         }
@@ -600,7 +1296,9 @@ fn callback_to_java(fn_ty: &syn::TypeBareFn, context: &Context) -> Result<String
     if fn_ty.to_owned().lifetimes.is_some() {
         return Err(Error {
             level: Level::Error,
-            span: None, //NONE FOR NOW
+            span: Some(fn_ty.lifetimes.span()),
+            file: None,
+            item: None,
             message: "cannot handle lifetimes".into(),
         });
     }
@@ -626,16 +1324,16 @@ fn callback_to_java(fn_ty: &syn::TypeBareFn, context: &Context) -> Result<String
             args_iter.next();
         }
 
-        let java_type = java_type_to_str(&java_type)?;
-        args.push(format!("{} {}", java_type, arg_name.to_camel_case()));
+        args.push((java_type, arg_name.to_camel_case()));
     }
 
-    Ok(args.join(", "))
+    Ok(args)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use indoc::indoc;
 
     #[test]
     fn cb_names() {
@@ -650,6 +1348,9 @@ mod tests {
             namespace: "net.maidsafe.bindings".to_owned(),
             namespace_model: "net.maidsafe.model".to_owned(),
             generated_jni_cbs: BTreeSet::new(),
+            enums: HashMap::new(),
+            free_fn: None,
+            owned_returns: false,
         };
 
         let inputs = get_inputs("fn ()");
@@ -687,4 +1388,647 @@ mod tests {
             unwrap!(callback_name(&inputs, &context))
         );
     }
+    #[test]
+    fn tagged_unions() {
+        use crate::common::Lang;
+
+        let item: syn::ItemEnum = unwrap!(syn::parse_str(
+            "/// A shape.
+            #[repr(C)]
+            pub enum Shape {
+                Point,
+                Circle(f64),
+                Rect { width: u32, height: u32 },
+            }"
+        ));
+        let mut lang = LangJava::new(HashMap::new());
+        let mut outputs = Outputs::default();
+        unwrap!(lang.parse_enum(&item, &[], &mut outputs));
+
+        assert_multiline_eq!(
+            &outputs["Shape.java"],
+            "package net.maidsafe.model;\n\
+             \n\
+             /// A shape.\n\
+             public abstract class Shape {\n\
+             \tprivate Shape() {\n\
+             \t}\n\
+             \n\
+             \tpublic static final class Point extends Shape {\n\
+             \t\tpublic Point() {\n\
+             \n\
+             \t\t}\n\
+             \t}\n\
+             \n\
+             \tpublic static final class Circle extends Shape {\n\
+             \t\tprivate double item0;\n\
+             \n\
+             \t\tpublic Circle() {\n\
+             \n\
+             \t\t}\n\
+             \t\tpublic Circle(double item0) {\n\
+             \t\t\tthis.item0 = item0;\n\
+             \t\t}\n\
+             \t\tpublic double getItem0() {\n\
+             \t\t\treturn item0;\n\
+             \t\t}\n\
+             \n\
+             \t\tpublic void setItem0(final double val) {\n\
+             \t\t\tthis.item0 = val;\n\
+             \t\t}\n\
+             \n\
+             \t}\n\
+             \n\
+             \tpublic static final class Rect extends Shape {\n\
+             \t\tprivate int width;\n\
+             \t\tprivate int height;\n\
+             \n\
+             \t\tpublic Rect() {\n\
+             \n\
+             \t\t}\n\
+             \t\tpublic Rect(int width, int height) {\n\
+             \t\t\tthis.width = width;\n\
+             \t\t\tthis.height = height;\n\
+             \t\t}\n\
+             \t\tpublic int getWidth() {\n\
+             \t\t\treturn width;\n\
+             \t\t}\n\
+             \n\
+             \t\tpublic void setWidth(final int val) {\n\
+             \t\t\tthis.width = val;\n\
+             \t\t}\n\
+             \n\
+             \t\tpublic int getHeight() {\n\
+             \t\t\treturn height;\n\
+             \t\t}\n\
+             \n\
+             \t\tpublic void setHeight(final int val) {\n\
+             \t\t\tthis.height = val;\n\
+             \t\t}\n\
+             \n\
+             \t}\n\
+             }\n\
+             \n"
+        );
+
+        let jni = &outputs["jni.rs"];
+        assert!(jni.contains(
+            "if env . is_instance_of ( input , & cls ) ? { return { Ok ( Shape :: Point ) } ; }"
+        ));
+        assert!(jni.contains("Ok ( Shape :: Circle ( item0 ) )"));
+        assert!(jni.contains("Ok ( Shape :: Rect { width , height } )"));
+        assert!(jni.contains(
+            "Shape :: Rect { ref width , ref height } => { \
+             let cls = unsafe { find_class ( env , \"net/maidsafe/model/Shape$Rect\" ) ? } ;"
+        ));
+    }
+
+    #[test]
+    fn value_enums() {
+        use crate::common::Lang;
+
+        let mut lang = LangJava::new(HashMap::new());
+        let mut outputs = Outputs::default();
+
+        let item: syn::ItemEnum = unwrap!(syn::parse_str(
+            "/// Task priority.
+            #[repr(u8)]
+            pub enum Priority {
+                /// Can wait.
+                Low = 1,
+                High,
+            }"
+        ));
+        unwrap!(lang.parse_enum(&item, &[], &mut outputs));
+
+        assert_multiline_eq!(
+            &outputs["Priority.java"],
+            "package net.maidsafe.model;\n\
+             \n\
+             /// Task priority.\n\
+             public enum Priority {\n\
+             \t/// Can wait.\n\
+             \tLOW(1),\n\
+             \tHIGH(2);\n\
+             \n\
+             \tprivate final int value;\n\
+             \n\
+             \tPriority(int value) {\n\
+             \t\tthis.value = value;\n\
+             \t}\n\
+             \n\
+             \tpublic int getValue() {\n\
+             \t\treturn value;\n\
+             \t}\n\
+             \n\
+             \tpublic static Priority fromValue(int value) {\n\
+             \t\tfor (Priority item : values()) {\n\
+             \t\t\tif (item.value == value) {\n\
+             \t\t\t\treturn item;\n\
+             \t\t\t}\n\
+             \t\t}\n\
+             \t\tthrow new IllegalArgumentException(\"Invalid Priority value: \" + value);\n\
+             \t}\n\
+             }\n\
+             \n"
+        );
+
+        let item: syn::ItemStruct = unwrap!(syn::parse_str(
+            "#[repr(C)]
+            pub struct Task {
+                pub priority: Priority,
+            }"
+        ));
+        unwrap!(lang.parse_struct(&item, &[], &mut outputs));
+
+        let task = &outputs["Task.java"];
+        assert!(task.contains("private Priority priority;"));
+        assert!(task.contains("this.priority = Priority.LOW;"));
+
+        let item: syn::ItemFn = unwrap!(syn::parse_str(
+            "#[no_mangle]
+            pub extern \"C\" fn set_priority(
+                priority: Priority,
+                user_data: *mut c_void,
+                o_cb: extern \"C\" fn(user_data: *mut c_void, result: *const FfiResult, \
+                                      priority: Priority),
+            ) {}"
+        ));
+        unwrap!(lang.parse_fn(&item, &[], &mut outputs));
+
+        assert!(outputs["NativeBindings.java"]
+            .contains("public static native void setPriority(Priority priority, "));
+
+        let jni = &outputs["jni.rs"];
+        assert!(jni.contains(
+            "match env . call_method ( input , \"getValue\" , \"()I\" , & [ ] ) ? . i ( ) ? { \
+             1i32 => Ok ( Priority :: Low ) , \
+             2i32 => Ok ( Priority :: High ) ,"
+        ));
+        // Values coming from Java are matched rather than transmuted, so unknown ones are errors.
+        assert!(jni.contains(
+            "value => Err ( format ! ( \"invalid {} value: {}\" , \"Priority\" , value ) \
+             . into ( ) ) ,"
+        ));
+        assert!(jni.contains(
+            "let value = match * self { Priority :: Low => 1i32 , Priority :: High => 2i32 , } ;"
+        ));
+        assert!(jni.contains(
+            "env . call_static_method ( & cls , \"fromValue\" , \
+             \"(I)Lnet/maidsafe/model/Priority;\" , & [ value . into ( ) ] ) ? . l ( )"
+        ));
+        assert!(jni.contains(
+            "let jobj = self . priority . to_java ( env ) ? ; \
+             env . set_field ( output , \"priority\" , \"Lnet/maidsafe/model/Priority;\" , \
+             jobj . into ( ) ) ? ;"
+        ));
+        assert!(jni.contains(
+            "let priority = env . get_field ( input , \"priority\" , \
+             \"Lnet/maidsafe/model/Priority;\" ) ? . l ( ) ? ; \
+             let priority = Priority :: from_java ( env , priority ) ? ;"
+        ));
+        assert!(jni.contains("priority : JObject"));
+        assert!(jni.contains(
+            "let priority = jni_unwrap ! ( Priority :: from_java ( & env , priority ) ) ;"
+        ));
+    }
+
+    #[test]
+    fn enum_discriminants() {
+        use crate::common::Lang;
+
+        let mut lang = LangJava::new(HashMap::new());
+        let mut outputs = Outputs::default();
+
+        let item: syn::ItemEnum = unwrap!(syn::parse_str(
+            "#[repr(i8)]
+            pub enum Sign {
+                Negative = -1,
+                Zero,
+                Positive,
+            }"
+        ));
+        unwrap!(lang.parse_enum(&item, &[], &mut outputs));
+        assert!(outputs["Sign.java"].contains("\tNEGATIVE(-1),\n\tZERO(0),\n\tPOSITIVE(1);\n"));
+
+        let item: syn::ItemEnum = unwrap!(syn::parse_str(
+            "#[repr(C)]
+            pub enum Flags {
+                None = 0,
+                All = ALL_FLAGS,
+            }"
+        ));
+        let error = unwrap!(lang.parse_enum(&item, &[], &mut outputs).err());
+        assert_eq!(
+            error.message,
+            "bindgen cannot handle the discriminant of `Flags::All` (use an integer literal)"
+        );
+
+        let item: syn::ItemEnum = unwrap!(syn::parse_str(
+            "#[repr(i64)]
+            pub enum Offset {
+                Start,
+                End = 4294967296,
+            }"
+        ));
+        let error = unwrap!(lang.parse_enum(&item, &[], &mut outputs).err());
+        assert_eq!(
+            error.message,
+            "bindgen cannot handle the discriminant 4294967296 of `Offset::End`, as it doesn't \
+             fit in a Java int"
+        );
+        assert!(!outputs.contains_key("Flags.java"));
+        assert!(!outputs.contains_key("Offset.java"));
+    }
+
+    #[test]
+    fn enums_used_before_their_declaration() {
+        let outputs = compile!(LangJava::new(HashMap::new()), {
+            #[repr(C)]
+            pub struct Task {
+                pub priority: Priority,
+            }
+
+            #[no_mangle]
+            pub extern "C" fn set_priority(priority: Priority) {}
+
+            #[repr(C)]
+            pub enum Priority {
+                Low,
+                High,
+            }
+        });
+
+        // The enum class, and the structure and function using it.
+        let priority = &outputs["Priority.java"];
+        assert!(priority.contains("public enum Priority {\n\tLOW(0),\n\tHIGH(1);\n"));
+        assert!(priority.contains("\tpublic static Priority fromValue(int value) {\n"));
+        assert!(outputs["Task.java"].contains("\tprivate Priority priority;\n"));
+        assert!(outputs["Task.java"].contains("\t\tthis.priority = Priority.LOW;\n"));
+        assert!(outputs["NativeBindings.java"]
+            .contains("public static native void setPriority(Priority priority);"));
+
+        // The conversions between the Rust and the Java enum.
+        let jni = &outputs["jni.rs"];
+        assert!(jni.contains(indoc!(
+            "
+            impl<'a> FromJava<JObject<'a>> for Priority {
+                fn from_java(env: &JNIEnv, input: JObject) -> Result<Self, JniError> {
+                    match env.call_method(input, \"getValue\", \"()I\", &[])?.i()? {
+                        0i32 => Ok(Priority::Low),
+                        1i32 => Ok(Priority::High),
+                        value => Err(format!(\"invalid {} value: {}\", \"Priority\", value).into()),
+                    }
+                }
+            }"
+        )));
+        assert!(jni.contains(
+            "let value = match *self {\n            \
+             Priority::Low => 0i32,\n            \
+             Priority::High => 1i32,\n        \
+             };"
+        ));
+        assert!(jni.contains("let priority = Priority::from_java(env, priority)?;"));
+        assert!(jni.contains("let jobj = self.priority.to_java(env)?;"));
+        assert!(jni.contains("let priority = jni_unwrap!(Priority::from_java(&env, priority));"));
+    }
+
+    #[test]
+    fn constants() {
+        use crate::common::Lang;
+
+        let mut lang = LangJava::new(HashMap::new());
+        lang.set_consts_class("AppConstants");
+        lang.set_consts_namespace("net.maidsafe.app");
+        let mut outputs = Outputs::default();
+
+        let item: syn::ItemType = unwrap!(syn::parse_str("pub type Version = u64;"));
+        unwrap!(lang.parse_ty(&item, &[], &mut outputs));
+        let item: syn::ItemType = unwrap!(syn::parse_str("pub type Key = [u8; 4];"));
+        unwrap!(lang.parse_ty(&item, &[], &mut outputs));
+
+        for source in &[
+            "/// Maximum length of a name.\npub const MAX_LEN: usize = 32;",
+            "pub const VERSION: Version = 2;",
+            "pub const OFFSET: i32 = -4;",
+            "pub const MASK: u8 = 255;",
+            "pub const LIMIT: u32 = 4294967295;",
+            "pub const RATIO: f32 = 0.5;",
+            "pub const PRECISE: f64 = 1.25e3;",
+            "pub const ENABLED: bool = true;",
+            "pub const NAME: &'static str = \"app\";",
+            "pub const GREETING: &'static str = \"say \\\"hi\\\"\\n\\u{e9}\\u{1f600}\\0\";",
+            "pub const KEY: Key = [1, 2, 3, 200];",
+        ] {
+            let item: syn::ItemConst = unwrap!(syn::parse_str(source));
+            unwrap!(lang.parse_const(&item, &[], &mut outputs));
+        }
+
+        assert_multiline_eq!(
+            unwrap!(lang.generate_consts()),
+            "package net.maidsafe.app;\n\
+             \n\
+             public final class AppConstants {\n\
+             \t/// Maximum length of a name.\n\
+             \tpublic static final long MAX_LEN = 32L;\n\
+             \tpublic static final long VERSION = 2L;\n\
+             \tpublic static final int OFFSET = -4;\n\
+             \tpublic static final byte MASK = (byte) 255;\n\
+             \tpublic static final int LIMIT = (int) 4294967295L;\n\
+             \tpublic static final float RATIO = 0.5f;\n\
+             \tpublic static final double PRECISE = 1.25e3;\n\
+             \tpublic static final boolean ENABLED = true;\n\
+             \tpublic static final String NAME = \"app\";\n\
+             \tpublic static final String GREETING = \"say \\\"hi\\\"\\n\
+             \\u00e9\\ud83d\\ude00\\000\";\n\
+             \tpublic static final byte[] KEY = { 1, 2, 3, (byte) 200 };\n\
+             \n\
+             \tprivate AppConstants() {\n\
+             \t}\n\
+             }\n\
+             \n"
+        );
+    }
+
+    #[test]
+    fn return_values() {
+        use crate::common::Lang;
+
+        let mut lang = LangJava::new(HashMap::new());
+        lang.set_free_function("free_ptr");
+        lang.set_owned_returns(true);
+        let mut outputs = Outputs::default();
+
+        for source in &[
+            "#[no_mangle]\npub extern \"C\" fn is_valid(id: u64) -> bool {}",
+            "#[no_mangle]\npub extern \"C\" fn app_name(app: *const App) -> *const c_char {}",
+            "#[no_mangle]\npub extern \"C\" fn app_info(name: *const c_char) -> *mut AppInfo {}",
+            "#[no_mangle]\npub extern \"C\" fn app_version() -> Version {}",
+        ] {
+            let item: syn::ItemFn = unwrap!(syn::parse_str(source));
+            unwrap!(lang.parse_fn(&item, &[], &mut outputs));
+        }
+        unwrap!(lang.finalise_output(&mut outputs));
+
+        let bindings = &outputs["NativeBindings.java"];
+        assert!(bindings.contains("public static native boolean isValid(long id);"));
+        assert!(bindings.contains("public static native String appName(App app);"));
+        assert!(bindings.contains("public static native AppInfo appInfo(String name);"));
+
+        let jni = &outputs["jni.rs"];
+        assert!(jni.contains("fn is_valid(id: u64) -> bool;"));
+        assert!(jni.contains(indoc!(
+            "
+            ) -> jboolean {
+                let result = is_valid(id as u64);
+                let output = result as jboolean;
+                output
+            }"
+        )));
+        assert!(jni.contains(indoc!(
+            "
+            ) -> jobject {
+                let name = match CString::from_java(&env, name) {
+                    Ok(value) => value,
+                    Err(_) => return ::std::ptr::null_mut(),
+                };
+                let result = app_info(name.as_ptr());
+                let output = if result.is_null() {
+                    Ok(JObject::null())
+                } else {
+                    let output = (*result).to_java(&env);
+                    free_ptr(result as *mut c_void);
+                    output
+                };"
+        )));
+        assert!(jni.contains("let output = result.to_java(&env).map(JObject::from);"));
+        assert!(jni.contains("let output = match result.to_java(&env) {"));
+        assert!(jni.contains("fn free_ptr(ptr: *mut c_void);"));
+    }
+
+    #[test]
+    fn borrowed_returns() {
+        use crate::common::Lang;
+
+        let mut lang = LangJava::new(HashMap::new());
+        lang.add_type_mapping("App", java_type_from_str("long"));
+        lang.set_free_function("free_ptr");
+        let mut outputs = Outputs::default();
+
+        for source in &[
+            "#[no_mangle]\npub extern \"C\" fn app_new() -> *mut App {}",
+            "#[no_mangle]\npub extern \"C\" fn app_name(app: *const App) -> *const c_char {}",
+            "#[no_mangle]\npub extern \"C\" fn app_info(name: *const c_char) -> *mut AppInfo {}",
+        ] {
+            let item: syn::ItemFn = unwrap!(syn::parse_str(source));
+            unwrap!(lang.parse_fn(&item, &[], &mut outputs));
+        }
+        unwrap!(lang.finalise_output(&mut outputs));
+
+        let bindings = &outputs["NativeBindings.java"];
+        assert!(bindings.contains("public static native long appNew();"));
+
+        let jni = &outputs["jni.rs"];
+        assert!(jni.contains(indoc!(
+            "
+            ) -> jlong {
+                let result = app_new();
+                let output = result as jlong;
+                output
+            }"
+        )));
+        assert!(jni.contains(indoc!(
+            "
+            ) -> jobject {
+                let name = match CString::from_java(&env, name) {
+                    Ok(value) => value,
+                    Err(_) => return ::std::ptr::null_mut(),
+                };
+                let result = app_info(name.as_ptr());
+                let output = if result.is_null() {
+                    Ok(JObject::null())
+                } else {
+                    let output = (*result).to_java(&env);
+                    output
+                };"
+        )));
+        assert!(jni.contains("let output = result.to_java(&env).map(JObject::from);"));
+        assert!(!jni.contains("free_ptr"));
+
+        // Owned returns can't be freed without a free function.
+        let mut lang = LangJava::new(HashMap::new());
+        lang.set_owned_returns(true);
+        let mut outputs = Outputs::default();
+        let item: syn::ItemFn = unwrap!(syn::parse_str(
+            "#[no_mangle]\npub extern \"C\" fn app_name() -> *const c_char {}"
+        ));
+        unwrap!(lang.parse_fn(&item, &[], &mut outputs));
+        assert!(lang.finalise_output(&mut outputs).is_err());
+    }
+
+    #[test]
+    fn futures() {
+        use crate::common::Lang;
+
+        let mut lang = LangJava::new(HashMap::new());
+        lang.set_lib_name("safe_app");
+        let mut outputs = Outputs::default();
+
+        for source in &[
+            "/// Returns the version of the app.
+            #[no_mangle]
+            pub extern \"C\" fn app_version(
+                app: *const App,
+                user_data: *mut c_void,
+                o_cb: extern \"C\" fn(user_data: *mut c_void, result: *const FfiResult, \
+                                      version: u64),
+            ) {}",
+            "#[no_mangle]
+            pub extern \"C\" fn app_reconnect(
+                user_data: *mut c_void,
+                o_cb: extern \"C\" fn(user_data: *mut c_void, result: *const FfiResult),
+                force: bool,
+            ) {}",
+            "#[no_mangle]
+            pub extern \"C\" fn app_keys(
+                user_data: *mut c_void,
+                o_cb: extern \"C\" fn(user_data: *mut c_void, result: *const FfiResult, \
+                                      keys: *const u8, keys_len: usize),
+            ) {}",
+            "#[no_mangle]
+            pub extern \"C\" fn app_pair(
+                user_data: *mut c_void,
+                o_cb: extern \"C\" fn(user_data: *mut c_void, result: *const FfiResult, \
+                                      a: u32, b: u32),
+            ) {}",
+        ] {
+            let item: syn::ItemFn = unwrap!(syn::parse_str(source));
+            unwrap!(lang.parse_fn(&item, &[], &mut outputs));
+        }
+        unwrap!(lang.finalise_output(&mut outputs));
+
+        assert_multiline_eq!(
+            &outputs["SafeAppAsync.java"],
+            "package net.maidsafe.bindings;\n\
+             \n\
+             import net.maidsafe.model.*;\n\
+             import java.util.concurrent.CompletableFuture;\n\
+             \n\
+             public final class SafeAppAsync {\n\
+             \t/**\n\
+             \t * Returns the version of the app.\n\
+             \t */\n\
+             \tpublic static CompletableFuture<Long> appVersion(App app) {\n\
+             \t\tCompletableFuture<Long> future = new CompletableFuture<>();\n\
+             \t\tNativeBindings.appVersion(app, (result, version) -> {\n\
+             \t\t\tif (result != null && result.getErrorCode() != 0) {\n\
+             \t\t\t\tfuture.completeExceptionally(\
+             new FfiException(result.getErrorCode(), result.getDescription()));\n\
+             \t\t\t} else {\n\
+             \t\t\t\tfuture.complete(version);\n\
+             \t\t\t}\n\
+             \t\t});\n\
+             \t\treturn future;\n\
+             \t}\n\
+             \n\
+             \t/**\n\
+             \t */\n\
+             \tpublic static CompletableFuture<Void> appReconnect(boolean force) {\n\
+             \t\tCompletableFuture<Void> future = new CompletableFuture<>();\n\
+             \t\tNativeBindings.appReconnect((result) -> {\n\
+             \t\t\tif (result != null && result.getErrorCode() != 0) {\n\
+             \t\t\t\tfuture.completeExceptionally(\
+             new FfiException(result.getErrorCode(), result.getDescription()));\n\
+             \t\t\t} else {\n\
+             \t\t\t\tfuture.complete(null);\n\
+             \t\t\t}\n\
+             \t\t}, force);\n\
+             \t\treturn future;\n\
+             \t}\n\
+             \n\
+             \t/**\n\
+             \t */\n\
+             \tpublic static CompletableFuture<byte[]> appKeys() {\n\
+             \t\tCompletableFuture<byte[]> future = new CompletableFuture<>();\n\
+             \t\tNativeBindings.appKeys((result, keys) -> {\n\
+             \t\t\tif (result != null && result.getErrorCode() != 0) {\n\
+             \t\t\t\tfuture.completeExceptionally(\
+             new FfiException(result.getErrorCode(), result.getDescription()));\n\
+             \t\t\t} else {\n\
+             \t\t\t\tfuture.complete(keys);\n\
+             \t\t\t}\n\
+             \t\t});\n\
+             \t\treturn future;\n\
+             \t}\n\
+             \n\
+             \tprivate SafeAppAsync() {\n\
+             \t}\n\
+             }\n\
+             \n"
+        );
+        assert!(
+            outputs["FfiException.java"].contains("public class FfiException extends Exception {")
+        );
+    }
+
+    #[test]
+    fn futures_class_name() {
+        use crate::common::Lang;
+
+        let mut lang = LangJava::new(HashMap::new());
+        lang.set_lib_name("app");
+        let mut outputs = Outputs::default();
+
+        let item: syn::ItemStruct =
+            unwrap!(syn::parse_str("#[repr(C)] pub struct App { pub id: u64 }"));
+        unwrap!(lang.parse_struct(&item, &[], &mut outputs));
+        let item: syn::ItemFn = unwrap!(syn::parse_str(
+            "#[no_mangle]
+            pub extern \"C\" fn app_free(
+                app: *mut App,
+                user_data: *mut c_void,
+                o_cb: extern \"C\" fn(user_data: *mut c_void, result: *const FfiResult),
+            ) {}"
+        ));
+        unwrap!(lang.parse_fn(&item, &[], &mut outputs));
+        unwrap!(lang.finalise_output(&mut outputs));
+
+        // The wrappers don't end up in the class of the `App` structure.
+        assert!(outputs["App.java"].contains("public class App {"));
+        assert!(!outputs["App.java"].contains("CompletableFuture"));
+        assert!(outputs["AppAsync.java"].contains("public final class AppAsync {"));
+        assert!(outputs["AppAsync.java"]
+            .contains("public static CompletableFuture<Void> appFree(App app) {"));
+        assert!(outputs["NativeBindings.java"]
+            .contains("public static native void appFree(App app, CallbackResult oCb);"));
+    }
+
+    #[test]
+    fn packages() {
+        use crate::common::Lang;
+
+        let mut lang = LangJava::new(HashMap::new());
+        let mut outputs = Outputs::default();
+
+        let item: syn::ItemEnum = unwrap!(syn::parse_str("#[repr(u8)] pub enum Kind { A, B }"));
+        unwrap!(lang.parse_enum(&item, &[], &mut outputs));
+        let item: syn::ItemStruct = unwrap!(syn::parse_str(
+            "#[repr(C)] pub struct Info { pub kind: Kind }"
+        ));
+        unwrap!(lang.parse_struct(&item, &[], &mut outputs));
+        let item: syn::ItemFn = unwrap!(syn::parse_str(
+            "#[no_mangle]
+            pub extern \"C\" fn info_get(
+                user_data: *mut c_void,
+                o_cb: extern \"C\" fn(user_data: *mut c_void, info: *const Info),
+            ) {}"
+        ));
+        unwrap!(lang.parse_fn(&item, &[], &mut outputs));
+        unwrap!(lang.finalise_output(&mut outputs));
+
+        assert!(outputs["Info.java"].starts_with("package net.maidsafe.model;\n\n"));
+        assert!(outputs["Info.java"].contains("import net.maidsafe.bindings.*;\n"));
+        assert!(outputs["NativeBindings.java"]
+            .starts_with("package net.maidsafe.bindings;\n\nimport net.maidsafe.model.*;\n\n"));
+    }
 }
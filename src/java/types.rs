@@ -5,6 +5,7 @@ use crate::java::Context;
 use crate::{Error, Level};
 use jni::signature::{JavaType, Primitive};
 use syn::export::ToTokens;
+use syn::spanned::Spanned;
 use unwrap::unwrap;
 
 fn primitive_type_to_str(ty: Primitive) -> &'static str {
@@ -33,11 +34,34 @@ pub fn java_type_to_str(ty: &JavaType) -> Result<String, Error> {
         JavaType::Method(..) => Err(Error {
             level: Level::Error,
             span: None,
+            file: None,
+            item: None,
             message: "Java methods are not supported".into(),
         }),
     }
 }
 
+/// Converts Java code for a type (e.g. `long`, `byte[]` or `String`) into `JavaType`
+pub fn java_type_from_str(ty: &str) -> JavaType {
+    let ty = ty.trim();
+    if let Some(elem) = ty.strip_suffix("[]") {
+        return JavaType::Array(Box::new(java_type_from_str(elem)));
+    }
+    let primitive = match ty {
+        "boolean" => Primitive::Boolean,
+        "byte" => Primitive::Byte,
+        "char" => Primitive::Char,
+        "double" => Primitive::Double,
+        "float" => Primitive::Float,
+        "int" => Primitive::Int,
+        "long" => Primitive::Long,
+        "short" => Primitive::Short,
+        "void" => Primitive::Void,
+        _ => return JavaType::Object(ty.to_string()),
+    };
+    JavaType::Primitive(primitive)
+}
+
 /// Transform a struct name into a Java class name
 pub fn struct_to_java_classname<S: AsRef<str>>(s: S) -> String {
     // s.as_ref().to_class_case()
@@ -95,7 +119,9 @@ fn callback_arg_to_java(
         _ => {
             return Err(Error {
                 level: Level::Error,
-                span: None, //NONE FOR NOW
+                span: Some(fn_ty.abi.span()),
+                file: None,
+                item: None,
                 message: "callbacks that don't have C ABI are not supported".into(),
             });
         }
@@ -104,7 +130,9 @@ fn callback_arg_to_java(
     if fn_ty.lifetimes.is_some() {
         return Err(Error {
             level: Level::Error,
-            span: None, //NONE FOR NOW
+            span: Some(fn_ty.lifetimes.span()),
+            file: None,
+            item: None,
             message: "cannot handle lifetimes".into(),
         });
     }
@@ -153,7 +181,9 @@ fn anon_rust_to_java(
         // Function pointers should not be in this function.
         syn::Type::BareFn(..) => Err(Error {
             level: Level::Error,
-            span: None, //NONE FOR NOW
+            span: Some(ty.span()),
+            file: None,
+            item: None,
             message: "C function pointers must have a name or function declaration \
                       associated with them"
                 .into(),
@@ -180,7 +210,9 @@ fn anon_rust_to_java(
             } else {
                 Err(Error {
                     level: Level::Error,
-                    span: None, //NONE FOR NOW
+                    span: Some(ty.span()),
+                    file: None,
+                    item: None,
                     message: format!("unknown type `{}`", new_type),
                 })
             }
@@ -200,7 +232,9 @@ fn path_to_java(
     if path.segments.is_empty() {
         return Err(Error {
             level: Level::Bug,
-            span: None, //NONE FOR NOW
+            span: Some(path.span()),
+            file: None,
+            item: None,
             message: "invalid type".into(),
         });
     }
@@ -223,7 +257,9 @@ fn path_to_java(
             }
             ty => Err(Error {
                 level: Level::Error,
-                span: None,
+                span: Some(path.span()),
+                file: None,
+                item: None,
                 message: format!("can't convert type {:?}", ty),
             }),
         }
@@ -334,4 +370,15 @@ mod tests {
             "byte[]"
         );
     }
+
+    #[test]
+    fn java_types_from_string() {
+        for ty in &["long", "byte[]", "String", "net.maidsafe.Test", "int[][]"] {
+            assert_eq!(unwrap!(java_type_to_str(&java_type_from_str(ty))), *ty);
+        }
+        assert_eq!(
+            java_type_from_str("byte[]"),
+            JavaType::Array(Box::new(JavaType::Primitive(Primitive::Byte)))
+        );
+    }
 }
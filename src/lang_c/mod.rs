@@ -6,25 +6,50 @@ mod types;
 
 use self::types::{CPtrType, CType, CTypeNamed};
 use crate::common::{self, Lang, Outputs};
-use crate::{Error, Level};
-use petgraph::{algo, Graph};
+use crate::resolve::split_module_path;
+use crate::{Config, Error, Level};
+use inflector::Inflector;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use petgraph::{algo, Direction, Graph};
 use std::collections::btree_map::Entry;
-use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet};
 use std::ops::Deref;
 use std::path;
 use syn::export::ToTokens;
+use syn::spanned::Spanned;
 use unwrap::unwrap;
 
 pub struct LangC {
     lib_name: String,
+    /// Modules containing the FFI, whose headers are named after the library.
+    roots: Vec<Vec<String>>,
     decls: BTreeMap<String, String>,
     deps: BTreeMap<String, Vec<String>>,
+    /// Types each header only refers to through pointers.
+    pointer_deps: BTreeMap<String, BTreeSet<String>>,
+    /// Types referenced by each header.
+    type_refs: BTreeMap<String, BTreeSet<String>>,
+    /// Constants used in array lengths by each header.
+    length_refs: BTreeMap<String, BTreeSet<String>>,
+    /// Keywords of the declared structs and unions, which can be forward declared.
+    compound_decls: BTreeMap<String, &'static str>,
+    opaque_types: BTreeSet<String>,
+    /// Constants of the crate root which are macros, and may be used by any module.
+    root_defines: String,
+    /// Names of the constants in `root_defines`.
+    root_define_names: BTreeSet<String>,
+    /// Constants of the crate root which are variables, and may use types of any module.
+    root_statics: String,
     custom_code: String,
+    single_header: bool,
+    guard_prefix: String,
+    includes: Vec<String>,
 }
 
 /// Compile the header declarations then add the needed `#include`s.
 ///
-/// Currently includes:
+/// By default includes:
 ///
 /// - `stdint.h`
 /// - `stdbool.h`
@@ -32,9 +57,21 @@ impl LangC {
     pub fn new() -> Self {
         Self {
             lib_name: "backend".to_owned(),
+            roots: vec![vec!["ffi".to_owned()]],
             decls: BTreeMap::new(),
             deps: BTreeMap::new(),
+            pointer_deps: BTreeMap::new(),
+            type_refs: BTreeMap::new(),
+            length_refs: BTreeMap::new(),
+            compound_decls: BTreeMap::new(),
+            opaque_types: BTreeSet::new(),
+            root_defines: String::new(),
+            root_define_names: BTreeSet::new(),
+            root_statics: String::new(),
             custom_code: Default::default(),
+            single_header: false,
+            guard_prefix: "bindgen_".to_owned(),
+            includes: vec!["stdint.h".to_owned(), "stdbool.h".to_owned()],
         }
     }
 
@@ -43,17 +80,85 @@ impl LangC {
         self.lib_name = name.into();
     }
 
-    /// Adds manual C code into the top-level header - can be useful for typedefs,
-    /// like e.g. opaque pointers.
+    /// Set the modules containing the FFI (`ffi` by default). The header of a root is named
+    /// after the library, and the headers of its submodules are put into a directory named
+    /// after the library.
+    pub fn set_roots(&mut self, roots: Vec<Vec<String>>) {
+        self.roots = roots;
+    }
+
+    /// Adds manual C code into the top-level header - can be useful for typedefs
+    /// and macros.
     pub fn add_custom_code(&mut self, code: &str) {
         self.custom_code.push_str(code);
     }
 
+    /// Generate a single self-contained header `{lib_name}.h` instead of a header per module
+    /// included by the top-level header.
+    pub fn set_single_header(&mut self, enabled: bool) {
+        self.single_header = enabled;
+    }
+
+    /// Set the prefix of the include-guard macros.
+    pub fn set_guard_prefix<T: Into<String>>(&mut self, prefix: T) {
+        self.guard_prefix = prefix.into();
+    }
+
+    /// Clear the list of headers included by every generated header.
+    pub fn reset_includes(&mut self) {
+        self.includes.clear();
+    }
+
+    /// Include a header in every generated header. System headers can be given by their name
+    /// (`stdint.h`), other headers should be quoted (`"types.h"`).
+    pub fn add_include<T: Into<String>>(&mut self, include: T) {
+        self.includes.push(include.into());
+    }
+
+    /// Add definition of opaque type (type represented by an opaque pointer).
+    ///
+    /// Types which are referenced but never defined are declared as opaque automatically, so
+    /// this is only needed for types which don't appear in the bindings.
+    pub fn add_opaque_type<T: Into<String>>(&mut self, name: T) {
+        let _ = self.opaque_types.insert(name.into());
+    }
+
     fn add_dependencies(&mut self, module: &[String], cty: &CType) -> Result<(), Error> {
-        let deps = cty.dependencies();
+        let header = header_name(module, &self.roots, &self.lib_name)?;
+        let lengths = cty.length_dependencies();
+        if !lengths.is_empty() {
+            self.length_refs
+                .entry(header.clone())
+                .or_default()
+                .extend(lengths);
+        }
+
+        let types = cty.type_dependencies();
+        if !types.is_empty() {
+            self.type_refs
+                .entry(header.clone())
+                .or_default()
+                .extend(types);
+        }
 
+        let values = cty.value_dependencies();
+        let pointers: BTreeSet<_> = cty
+            .dependencies()
+            .into_iter()
+            .filter(|dep| !values.contains(dep))
+            .collect();
+        if !pointers.is_empty() {
+            self.pointer_deps
+                .entry(header)
+                .or_default()
+                .extend(pointers);
+        }
+        self.add_dependency_names(module, values)
+    }
+
+    fn add_dependency_names(&mut self, module: &[String], deps: Vec<String>) -> Result<(), Error> {
         if !deps.is_empty() {
-            let header = header_name(module, &self.lib_name)?;
+            let header = header_name(module, &self.roots, &self.lib_name)?;
 
             match self.deps.entry(header) {
                 Entry::Occupied(o) => o.into_mut().extend(deps.into_iter()),
@@ -72,11 +177,188 @@ impl LangC {
         module: &[String],
         outputs: &mut Outputs,
     ) -> Result<(), Error> {
-        let header = header_name(module, &self.lib_name)?;
+        let header = header_name(module, &self.roots, &self.lib_name)?;
         common::append_output(buffer, &header, outputs);
         Ok(())
     }
 
+    /// Order the headers by their dependencies, and collect the types which have to be declared
+    /// before them.
+    fn order_headers(&self, outputs: &Outputs) -> Result<HeaderOrder, Error> {
+        // Constants used in array lengths have to be generated as well, e.g. constants of other
+        // crates are not.
+        for (header_name, consts) in &self.length_refs {
+            let missing = consts.iter().find(|name| {
+                !self.decls.contains_key(*name)
+                    && !self.root_define_names.contains(*name)
+                    && !defines_macro(&self.custom_code, name)
+            });
+            if let Some(name) = missing {
+                return Err(Error {
+                    level: Level::Error,
+                    span: None,
+                    file: None,
+                    item: None,
+                    message: format!(
+                        "`{}` uses the constant `{}` in an array length, but it is not defined \
+                         within the FFI roots (define it there, or with custom code)",
+                        header_name, name
+                    ),
+                });
+            }
+        }
+
+        // Types which are referenced but never defined are forward declared in the first header
+        // referring to them, so the other headers using them are ordered after it.
+        let mut decls = self.decls.clone();
+        let mut compound_decls = self.compound_decls.clone();
+        let mut forward_decls: BTreeMap<String, String> = BTreeMap::new();
+        for (header_name, types) in &self.type_refs {
+            if !outputs.contains_key(header_name) {
+                continue;
+            }
+            for name in types {
+                if decls.contains_key(name) || declares_type(&self.custom_code, name) {
+                    continue;
+                }
+                let _ = decls.insert(name.clone(), header_name.clone());
+                let _ = compound_decls.insert(name.clone(), "struct");
+                forward_decls
+                    .entry(header_name.clone())
+                    .or_default()
+                    .push_str(&format!("typedef struct {0} {0};\n", name));
+            }
+        }
+
+        // Building a graph of dependencies, where each edge goes from the header declaring a
+        // type to a header using it.
+        let mut depgraph = Graph::<String, String>::new();
+        let nodes_map: BTreeMap<String, _> = outputs
+            .keys()
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|m| (m.clone(), depgraph.add_node(m)))
+            .collect();
+        let mut pointer_edges = Vec::new();
+        for (header_name, &node) in &nodes_map {
+            let value_deps = self.deps.get(header_name);
+            let pointer_deps = self
+                .pointer_deps
+                .get(header_name)
+                .into_iter()
+                .flatten()
+                .filter(|dep| !value_deps.is_some_and(|deps| deps.contains(dep)));
+
+            for dep in value_deps.into_iter().flatten() {
+                match decls.get(dep) {
+                    Some(pred) if pred != header_name => {
+                        let _ = depgraph.update_edge(nodes_map[pred], node, dep.clone());
+                    }
+                    _ => (),
+                }
+            }
+            for dep in pointer_deps {
+                match (decls.get(dep), compound_decls.get(dep)) {
+                    (Some(pred), Some(kind)) if pred != header_name => {
+                        pointer_edges.push((nodes_map[pred], node, dep, kind))
+                    }
+                    (Some(pred), None) if pred != header_name => {
+                        let _ = depgraph.update_edge(nodes_map[pred], node, dep.clone());
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        // Types used by value have to be defined first, so a cycle between them can't be broken.
+        if let Err(cycle) = algo::toposort(&depgraph, None) {
+            return Err(cycle_error(&depgraph, cycle.node_id()));
+        }
+
+        // Structs and unions only used through pointers are forward declared instead where
+        // ordering the headers would close a cycle.
+        let mut cycle_decls: BTreeMap<&String, (&'static str, _, Vec<_>)> = BTreeMap::new();
+        for (pred, succ, dep, &kind) in pointer_edges {
+            let edge = depgraph.add_edge(pred, succ, dep.clone());
+            if algo::is_cyclic_directed(&depgraph) {
+                let _ = depgraph.remove_edge(edge);
+                cycle_decls
+                    .entry(dep)
+                    .or_insert((kind, pred, Vec::new()))
+                    .2
+                    .push(succ);
+            }
+        }
+        let headers: Vec<String> = unwrap!(algo::toposort(&depgraph, None))
+            .into_iter()
+            .map(|node_id| depgraph[node_id].clone())
+            .collect();
+
+        // C99 doesn't allow repeating a typedef, so only the first header using such a type
+        // declares it, and its definition comes without the typedef.
+        let mut forward_declared: BTreeMap<String, Vec<_>> = BTreeMap::new();
+        for (dep, (kind, pred, succs)) in cycle_decls {
+            let first = headers
+                .iter()
+                .find(|header_name| succs.iter().any(|&succ| depgraph[succ] == **header_name));
+            if let Some(first) = first {
+                forward_decls
+                    .entry(first.clone())
+                    .or_default()
+                    .push_str(&format!("typedef {0} {1} {1};\n", kind, dep));
+                forward_declared
+                    .entry(depgraph[pred].clone())
+                    .or_default()
+                    .push((kind, dep.clone()));
+            }
+        }
+
+        // Opaque types which aren't referenced anywhere still need to be declared.
+        let mut opaque_types = String::new();
+        for name in &self.opaque_types {
+            if !decls.contains_key(name) && !declares_type(&self.custom_code, name) {
+                opaque_types.push_str(&format!("typedef struct {0} {0};\n", name));
+            }
+        }
+
+        Ok(HeaderOrder {
+            headers,
+            forward_decls,
+            forward_declared,
+            opaque_types,
+        })
+    }
+
+    /// Wrap a header with an include-guard using the configured prefix.
+    pub(crate) fn guard_header(&self, code: &str, header_name: &str) -> String {
+        wrap_guard(code, header_name, &self.guard_prefix)
+    }
+
+    /// Concatenate the declarations of all modules in the order of their dependencies, preceded
+    /// by the custom code.
+    pub(crate) fn amalgamate(&self, outputs: &Outputs) -> Result<String, Error> {
+        let order = self.order_headers(outputs)?;
+
+        let mut code = String::new();
+        if !self.custom_code.is_empty() {
+            code.push_str(&format!("{}\n", self.custom_code));
+        }
+        if !order.opaque_types.is_empty() {
+            code.push_str(&format!("{}\n", order.opaque_types));
+        }
+        code.push_str(&self.root_defines);
+        for header_name in &order.headers {
+            if let Some(forward_decls) = order.forward_decls.get(header_name) {
+                code.push_str(&format!("{}\n", forward_decls));
+            }
+            code.push_str(&order.definitions(header_name, &outputs[header_name]));
+        }
+        code.push_str(&self.root_statics);
+
+        Ok(code)
+    }
+
     /// Transform a Rust FFI function into a C function decl
     pub fn transform_native_fn(
         &mut self,
@@ -97,8 +379,8 @@ impl LangC {
             if let syn::FnArg::Captured(ref argcap) = arg {
                 if let syn::Pat::Ident(ref pat) = argcap.pat {
                     let arg_name = pat.ident.to_owned().to_string();
-                    let c_ty = unwrap!(rust_to_c(&argcap.ty, arg_name.as_str()));
-                    unwrap!(self.add_dependencies(module, &c_ty.1));
+                    let c_ty = param_to_c(&argcap.ty, arg_name.as_str())?;
+                    self.add_dependencies(module, &c_ty.1)?;
 
                     args.push(c_ty);
                 }
@@ -122,23 +404,23 @@ impl LangC {
         let output_type = &fn_decl.decl.output;
         let mut full_declaration = String::new();
         match output_type {
-            syn::ReturnType::Type(_, ref ty) => {
-                match ty.deref() {
-                    syn::Type::Never(..) => {
-                        return Err(Error {
-                            level: Level::Error,
-                            span: None, //NONE FOR NOW
-                            message: "panics across a C boundary are naughty!".into(),
-                        });
-                    }
-                    _ => {
-                        let c_ty = rust_to_c(&*ty, &buf)?;
-                        self.add_dependencies(module, &c_ty.1)?;
-                        let x = format!("{}", c_ty);
-                        full_declaration.push_str(&x[..])
-                    }
+            syn::ReturnType::Type(_, ref ty) => match ty.deref() {
+                syn::Type::Never(..) => {
+                    return Err(Error {
+                        level: Level::Error,
+                        span: Some(ty.span()),
+                        file: None,
+                        item: None,
+                        message: "panics across a C boundary are naughty!".into(),
+                    });
                 }
-            }
+                _ => {
+                    let c_ty = param_to_c(&*ty, &buf)?;
+                    self.add_dependencies(module, &c_ty.1)?;
+                    let x = format!("{}", c_ty);
+                    full_declaration.push_str(&x[..])
+                }
+            },
             syn::ReturnType::Default => full_declaration.push_str(&format!("void {}", buf)[..]),
         };
 
@@ -147,10 +429,137 @@ impl LangC {
         output.push_str(&full_declaration);
         output.push_str(";\n\n");
 
-        common::append_output(output, &header_name(module, &self.lib_name)?, outputs);
+        common::append_output(
+            output,
+            &header_name(module, &self.roots, &self.lib_name)?,
+            outputs,
+        );
 
         Ok(())
     }
+
+    /// Convert a Rust enum with data-carrying variants into a tagged union, following the layout
+    /// of RFC 2195.
+    ///
+    /// The variants are listed in a tag enum named `Enum_Tag` and the fields of each variant are
+    /// put into a struct named `Enum_Variant_Body`, held by a union member named after the
+    /// variant (see `member_name`). A `#[repr(C)]` enum becomes a struct holding the tag followed
+    /// by a union of the bodies, while an enum with only a primitive representation (e.g.
+    /// `#[repr(u8)]`) becomes a union of the bodies, each starting with the tag.
+    fn transform_tagged_union(
+        &mut self,
+        item: &syn::ItemEnum,
+        repr: &common::EnumRepr,
+        docs: &str,
+        module: &[String],
+        outputs: &mut Outputs,
+    ) -> Result<(), Error> {
+        let name = item.ident.to_string();
+        let tag_name = format!("{}_Tag", name);
+
+        let mut buffer = enum_to_c(&tag_name, &name, &item.variants, repr.int.as_ref());
+
+        let mut members = Vec::new();
+        for variant in item
+            .variants
+            .iter()
+            .filter(|v| v.fields != syn::Fields::Unit)
+        {
+            let body_name = format!("{}_{}_Body", name, variant.ident);
+            buffer.push_str(&format!("typedef struct {} {{\n", body_name));
+            if !repr.c {
+                buffer.push_str(&format!("    {} tag;\n", tag_name));
+            }
+            for (index, field) in variant.fields.iter().enumerate() {
+                let (_, docs) = common::parse_attr(
+                    &field.attrs[..],
+                    |_| true,
+                    |attr| common::retrieve_docstring(attr, "    "),
+                );
+                buffer.push_str(&docs);
+
+                // Fields of tuple variants are named after their position.
+                let field_name = match field.ident {
+                    Some(ref ident) => ident.to_string(),
+                    None => format!("_{}", index),
+                };
+                let ty = rust_to_c(&field.ty, &field_name)?;
+                self.add_dependencies(module, &ty.1)?;
+                buffer.push_str(&format!("    {};\n", ty));
+            }
+            buffer.push_str(&format!("}} {};\n\n", body_name));
+
+            members.push((body_name, member_name(&variant.ident.to_string())));
+        }
+
+        buffer.push_str(docs);
+        if repr.c {
+            buffer.push_str(&format!(
+                "typedef struct {} {{\n    {} tag;\n    union {{\n",
+                name, tag_name
+            ));
+            for (body_name, member) in members {
+                buffer.push_str(&format!("        {} {};\n", body_name, member));
+            }
+            buffer.push_str(&format!("    }} payload;\n}} {};\n\n", name));
+        } else {
+            buffer.push_str(&format!(
+                "typedef union {} {{\n    {} tag;\n",
+                name, tag_name
+            ));
+            for (body_name, member) in members {
+                buffer.push_str(&format!("    {} {};\n", body_name, member));
+            }
+            buffer.push_str(&format!("}} {};\n\n", name));
+        }
+        self.append_to_header(buffer, module, outputs)?;
+
+        let _ = self
+            .compound_decls
+            .insert(name.clone(), if repr.c { "struct" } else { "union" });
+        self.decls
+            .insert(name, header_name(module, &self.roots, &self.lib_name)?);
+
+        Ok(())
+    }
+}
+
+/// Headers of the modules, ordered by their dependencies.
+struct HeaderOrder {
+    headers: Vec<String>,
+    /// Declarations each header has to start with.
+    forward_decls: BTreeMap<String, String>,
+    /// Compound types (kind and name) defined by each header, which an earlier header already
+    /// forward declares with a typedef.
+    forward_declared: BTreeMap<String, Vec<(&'static str, String)>>,
+    /// Declarations of opaque types which aren't referenced by any header.
+    opaque_types: String,
+}
+
+impl HeaderOrder {
+    /// The code of a header, where the definitions of forward declared types are stripped of
+    /// their typedef (e.g. `typedef struct A {...} A;` becomes `struct A {...};`).
+    fn definitions(&self, header_name: &str, code: &str) -> String {
+        let types = match self.forward_declared.get(header_name) {
+            Some(types) => types,
+            None => return code.to_string(),
+        };
+
+        code.split_inclusive('\n')
+            .map(|line| {
+                let line_end = line.trim_end_matches('\n');
+                for (kind, name) in types {
+                    if line_end == format!("typedef {} {} {{", kind, name) {
+                        return format!("{} {} {{\n", kind, name);
+                    }
+                    if line_end == format!("}} {};", name) {
+                        return "};\n".to_string();
+                    }
+                }
+                line.to_string()
+            })
+            .collect()
+    }
 }
 
 impl Default for LangC {
@@ -160,12 +569,113 @@ impl Default for LangC {
 }
 
 impl Lang for LangC {
+    fn apply_config(&mut self, config: &Config) {
+        if let Some(lib_name) = config.lib_name_or(&config.c.lib_name) {
+            self.set_lib_name(lib_name);
+        }
+        if !config.roots.is_empty() {
+            self.set_roots(
+                config
+                    .roots
+                    .iter()
+                    .map(|root| split_module_path(root))
+                    .collect(),
+            );
+        }
+        if let Some(ref code) = config.c.custom_code {
+            self.add_custom_code(code);
+        }
+        for name in &config.c.opaque_types {
+            self.add_opaque_type(name.as_str());
+        }
+        if let Some(enabled) = config.c.single_header {
+            self.set_single_header(enabled);
+        }
+        if let Some(ref prefix) = config.c.guard_prefix {
+            self.set_guard_prefix(prefix.as_str());
+        }
+        if let Some(ref includes) = config.c.includes {
+            self.reset_includes();
+            for include in includes {
+                self.add_include(include.as_str());
+            }
+        }
+    }
+
+    /// Convert a Rust constant into a C constant.
+    ///
+    /// Literals and simple expressions become `#define NAME value`, arrays and struct literals
+    /// become `static const` variables with an initialiser. Constants from the crate root are
+    /// added to the top-level header of the library: macros before the module headers are
+    /// included, so that the modules can use them, and variables after.
     fn parse_const(
         &mut self,
-        _item: &syn::ItemConst,
-        _module: &[String],
-        _outputs: &mut Outputs,
+        item: &syn::ItemConst,
+        module: &[String],
+        outputs: &mut Outputs,
     ) -> Result<(), Error> {
+        // Constants of the crate root are passed regardless of their visibility.
+        if let syn::Visibility::Inherited | syn::Visibility::Crate(_) = item.vis {
+            return Ok(());
+        }
+
+        let (_, docs) = common::parse_attr(
+            &item.attrs[..],
+            |_| true,
+            |attr| common::retrieve_docstring(attr, ""),
+        );
+        let name = item.ident.to_string();
+        let mut deps = Vec::new();
+        let value = const_value_to_c(&item.expr, is_bool(&item.ty), &mut deps)?;
+        // Constants of the crate root don't belong to a module header.
+        let root = module.is_empty();
+
+        let mut buffer = String::new();
+        buffer.push_str(&docs);
+        match strip_const_expr(&item.expr) {
+            syn::Expr::Array(..) | syn::Expr::Repeat(..) | syn::Expr::Struct(..) => {
+                let ty = match *item.ty {
+                    syn::Type::Reference(ref reference) => &*reference.elem,
+                    ref ty => ty,
+                };
+                let declaration = match *ty {
+                    syn::Type::Slice(ref slice) => {
+                        let elem = anon_rust_to_c(&slice.elem)?;
+                        if !root {
+                            self.add_dependencies(module, &elem)?;
+                        }
+                        format!("{} {}[]", elem, name)
+                    }
+                    ref ty => {
+                        let ty = rust_to_c(ty, &name)?;
+                        if !root {
+                            self.add_dependencies(module, &ty.1)?;
+                        }
+                        ty.to_string()
+                    }
+                };
+                buffer.push_str(&format!("static const {} = {};\n\n", declaration, value));
+                if root {
+                    self.root_statics.push_str(&buffer);
+                    return Ok(());
+                }
+            }
+            _ => {
+                buffer.push_str(&format!("#define {} {}\n\n", name, value));
+                if root {
+                    self.root_defines.push_str(&buffer);
+                    let _ = self.root_define_names.insert(name);
+                    return Ok(());
+                }
+            }
+        }
+
+        self.add_dependency_names(module, deps)?;
+        self.append_to_header(buffer, module, outputs)?;
+
+        self.decls
+            .insert(name, header_name(module, &self.roots, &self.lib_name)?);
+
         Ok(())
     }
 
@@ -193,76 +703,62 @@ impl Lang for LangC {
         if !item.generics.params.is_empty() {
             return Ok(());
         }
-        let new_type = unwrap!(rust_to_c(item.ty.deref(), &name));
+        let new_type = rust_to_c(item.ty.deref(), &name)?;
+        self.add_dependencies(module, &new_type.1)?;
 
         buffer.push_str(&format!("typedef {};\n\n", new_type));
         self.append_to_header(buffer, module, outputs)?;
 
         self.decls
-            .insert(name, header_name(module, &self.lib_name)?);
+            .insert(name, header_name(module, &self.roots, &self.lib_name)?);
 
         Ok(())
     }
 
     /// Converts a Rust enum into a C enum.
     ///
-    /// The Rust enum must be marked with `#[repr(C)]` and must be public otherwise the function
-    /// will abort.
+    /// The Rust enum must be marked with `#[repr(C)]` or a primitive representation (e.g.
+    /// `#[repr(u8)]`) and must be public otherwise the function will abort. Enums with
+    /// data-carrying variants become tagged unions, see `transform_tagged_union`.
     ///
-    /// Bindgen will error if the enum is generic or if it contains non-unit variants.
+    /// Bindgen will error if the enum is generic.
     fn parse_enum(
         &mut self,
         item: &syn::ItemEnum,
         module: &[String],
         outputs: &mut Outputs,
     ) -> Result<(), Error> {
-        let (repr_c, docs) = common::parse_attr(&item.attrs[..], common::check_repr_c, |attr| {
-            common::retrieve_docstring(attr, "")
-        });
-        // If it's not #[repr(C)] then it can't be called from C.
-        if !repr_c {
+        let (_, docs) = common::parse_attr(
+            &item.attrs[..],
+            |_| true,
+            |attr| common::retrieve_docstring(attr, ""),
+        );
+
+        // If it has neither `#[repr(C)]` nor a primitive representation then it can't be
+        // called from C.
+        let repr = common::enum_repr(&item.attrs);
+        if !repr.is_defined() {
             return Ok(());
         }
 
-        let mut buffer = String::new();
-        buffer.push_str(&docs);
-
-        let name = item.ident.to_string();
-        buffer.push_str(&format!("typedef enum {} {{\n", name));
-
         // Error if generic parameters are encountered.
         if !item.generics.params.is_empty() {
             return Err(Error::unsupported_generics_error("enums"));
         }
-        for variant in item.variants.to_owned() {
-            if syn::Fields::Unit != variant.fields {
-                return Err(Error {
-                    level: Level::Error,
-                    span: None, //NONE FOR NOW
-                    message: "bindgen cannot handle `#[repr(C)]` enums with non-unit variants"
-                        .into(),
-                });
-            }
 
-            let (_, docs) = common::parse_attr(
-                &variant.attrs,
-                |_| true,
-                |attr| common::retrieve_docstring(attr, "    "),
-            );
-            buffer.push_str(&docs);
-
-            let name = format!("{}_{}", name, variant.ident.to_string());
-            let enum_line = if let Some(value) = common::extract_enum_variant_value(&variant) {
-                format!("    {} = {},\n", name, value)
-            } else {
-                format!("    {},\n", name)
-            };
-            buffer.push_str(&enum_line);
+        if common::has_data_variants(item) {
+            return self.transform_tagged_union(item, &repr, &docs, module, outputs);
         }
 
-        buffer.push_str(&format!("}} {};\n\n", name));
+        let name = item.ident.to_string();
+        let mut buffer = String::new();
+        buffer.push_str(&docs);
+        buffer.push_str(&enum_to_c(&name, &name, &item.variants, repr.int.as_ref()));
         self.append_to_header(buffer, module, outputs)?;
 
+        self.decls
+            .insert(name, header_name(module, &self.roots, &self.lib_name)?);
+
         Ok(())
     }
 
@@ -319,8 +815,9 @@ impl Lang for LangC {
         buffer.push_str(&format!(" {};\n\n", name));
         self.append_to_header(buffer, module, outputs)?;
 
+        let _ = self.compound_decls.insert(name.clone(), "struct");
         self.decls
-            .insert(name, header_name(module, &self.lib_name)?);
+            .insert(name, header_name(module, &self.roots, &self.lib_name)?);
 
         Ok(())
     }
@@ -348,11 +845,10 @@ impl Lang for LangC {
         }
 
         let name = item.ident.to_owned().to_string();
-        if item.abi.to_owned().is_some() {
-            match unwrap!(unwrap!(item.abi.to_owned()).name).value().as_str() {
-                // If it doesn't have a C ABI it can't be called from C.
-                "C" | "Cdecl" | "Stdcall" | "Fastcall" | "System" => {}
-                _ => return Ok(()),
+        // If it doesn't have a C ABI it can't be called from C.
+        if let Some(ref abi) = item.abi {
+            if !common::is_extern(abi.clone()) {
+                return Ok(());
             }
         }
 
@@ -366,59 +862,109 @@ impl Lang for LangC {
     }
 
     fn finalise_output(&mut self, outputs: &mut Outputs) -> Result<(), Error> {
-        let mut depgraph = Graph::<String, String>::new();
-        let nodes_map: HashMap<String, _> = outputs
-            .keys()
-            .map(|m| (m.clone(), depgraph.add_node(m.clone())))
-            .collect();
-        let node_ids_map: HashMap<_, String> =
-            nodes_map.iter().map(|(k, v)| (*v, k.clone())).collect();
-        let mut edges = BTreeSet::new();
+        let mut includes = String::new();
+        for include in &self.includes {
+            includes.push_str(&format!("#include {}\n", include_path(include)));
+        }
+
+        // Put the contents of all modules into one header, in the order of their dependencies
+        if self.single_header {
+            let code = format!("{}\n{}", includes, self.amalgamate(outputs)?);
+
+            let header_name = format!("{}.h", self.lib_name);
+            outputs.clear();
+            let _ = outputs.insert(
+                header_name.clone(),
+                wrap_guard(&wrap_extern(&code), &header_name, &self.guard_prefix),
+            );
+
+            return Ok(());
+        }
+
+        let order = self.order_headers(outputs)?;
 
         // Wrap modules with common includes
         for (header_name, value) in outputs.iter_mut() {
-            let code = format!("#include <stdint.h>\n#include <stdbool.h>\n\n{}", value);
-
-            *value = wrap_guard(&wrap_extern(&code), header_name);
-
-            // Building a graph of dependencies
-            if let Some(module_deps) = self.deps.get(header_name) {
-                for dep in module_deps {
-                    if let Some(mod_name) = self.decls.get(dep) {
-                        let pred = mod_name.to_string();
-                        let succ = header_name.to_string();
-                        if pred == succ {
-                            continue;
-                        }
-                        let _ = edges.insert((nodes_map[&pred], nodes_map[&succ]));
-                    }
-                }
+            let mut code = format!("{}\n", includes);
+            if let Some(forward_decls) = order.forward_decls.get(header_name) {
+                code.push_str(&format!("{}\n", forward_decls));
             }
-        }
+            code.push_str(&order.definitions(header_name, value));
 
-        // Build a full dependency graph and topologically sort dependencies
-        depgraph.extend_with_edges(&edges);
-        let sorted_deps = unwrap!(algo::toposort(&depgraph, None));
+            *value = wrap_guard(&wrap_extern(&code), header_name, &self.guard_prefix);
+        }
 
         // Generate a top-level header and add custom user code
         let mut top_level_header = String::new();
         if !self.custom_code.is_empty() {
             top_level_header.push_str(&format!("{}\n", self.custom_code));
         }
-        for node_id in sorted_deps {
-            let header_name = &node_ids_map[&node_id];
+        if !order.opaque_types.is_empty() {
+            top_level_header.push_str(&format!("{}\n", order.opaque_types));
+        }
+        top_level_header.push_str(&self.root_defines);
+        for header_name in &order.headers {
             top_level_header.push_str(&format!("#include \"{}\"\n", header_name));
         }
+        if !self.root_statics.is_empty() {
+            top_level_header.push_str(&format!("\n{}{}", includes, self.root_statics));
+        }
 
         outputs.insert(
             format!("{}.h", self.lib_name),
-            wrap_guard(&top_level_header, &format!("{}_root", self.lib_name)),
+            wrap_guard(
+                &top_level_header,
+                &format!("{}_root", self.lib_name),
+                &self.guard_prefix,
+            ),
         );
 
         Ok(())
     }
 }
 
+/// Turn the variants of a Rust enum into a C enum named `name`, whose enumerators are prefixed
+/// with `prefix`.
+///
+/// The size of a C enum is implementation-defined, so with a primitive representation the
+/// enumerators are declared in an anonymous enum and `name` becomes a fixed-size integer type
+/// instead. Naming that enum `name` as well would clash with the typedef in C++.
+fn enum_to_c<'a>(
+    name: &str,
+    prefix: &str,
+    variants: impl IntoIterator<Item = &'a syn::Variant>,
+    int: Option<&String>,
+) -> String {
+    let mut buffer = String::new();
+    if int.is_some() {
+        buffer.push_str("enum {\n");
+    } else {
+        buffer.push_str(&format!("typedef enum {} {{\n", name));
+    }
+
+    for variant in variants {
+        let (_, docs) = common::parse_attr(
+            &variant.attrs,
+            |_| true,
+            |attr| common::retrieve_docstring(attr, "    "),
+        );
+        buffer.push_str(&docs);
+
+        let variant_name = format!("{}_{}", prefix, variant.ident);
+        if let Some(value) = common::extract_enum_variant_value(variant) {
+            buffer.push_str(&format!("    {} = {},\n", variant_name, value));
+        } else {
+            buffer.push_str(&format!("    {},\n", variant_name));
+        }
+    }
+
+    match int {
+        Some(int) => buffer.push_str(&format!("}};\ntypedef {} {};\n\n", rust_ty_to_c(int), name)),
+        None => buffer.push_str(&format!("}} {};\n\n", name)),
+    }
+    buffer
+}
+
 /// Turn a Rust type with an associated name or type into a C type.
 pub fn rust_to_c(ty: &syn::Type, assoc: &str) -> Result<CTypeNamed, Error> {
     match ty {
@@ -431,22 +977,69 @@ pub fn rust_to_c(ty: &syn::Type, assoc: &str) -> Result<CTypeNamed, Error> {
     }
 }
 
+/// Turn the Rust type of a function parameter or return value into a C type.
+///
+/// C can neither pass nor return arrays by value, so they are turned into pointers.
+pub(crate) fn param_to_c(ty: &syn::Type, assoc: &str) -> Result<CTypeNamed, Error> {
+    match *ty {
+        syn::Type::BareFn(..) => rust_to_c(ty, assoc),
+        _ => Ok(CTypeNamed(assoc.to_string(), decayed_to_c(ty)?)),
+    }
+}
+
+/// Turn a Rust type into a C type, turning arrays into pointers to their innermost elements the
+/// way C passes them to functions.
+///
+/// The lengths of the arrays are not needed, so they are not evaluated.
+fn decayed_to_c(ty: &syn::Type) -> Result<CType, Error> {
+    match *ty {
+        syn::Type::Array(..) => Ok(CType::Ptr(
+            Box::new(anon_rust_to_c(innermost_elem(ty))?),
+            CPtrType::Const,
+        )),
+        _ => anon_rust_to_c(ty),
+    }
+}
+
+/// Returns the type of the innermost elements of (possibly nested) arrays, or the type itself.
+fn innermost_elem(ty: &syn::Type) -> &syn::Type {
+    match *ty {
+        syn::Type::Array(ref array) => innermost_elem(&array.elem),
+        ref ty => ty,
+    }
+}
+
 /// Turn a Rust type into a C type.
 fn anon_rust_to_c(ty: &syn::Type) -> Result<CType, Error> {
     match ty {
         // Function pointers should not be in this function.
         syn::Type::BareFn(..) => Err(Error {
             level: Level::Error,
-            span: None, //there is no span for types in syn, hence none
+            span: Some(ty.span()),
+            file: None,
+            item: None,
             message:
                 "C function ptrs must have a name or function declaration associated with them"
                     .into(),
         }),
-        // Fixed-length arrays, converted into pointers.
-        syn::Type::Array(syn::TypeArray { ref elem, .. }) => Ok(CType::Ptr(
-            Box::new(anon_rust_to_c(&*elem)?),
-            CPtrType::Const,
-        )),
+        // Fixed-length arrays.
+        syn::Type::Array(syn::TypeArray {
+            ref elem, ref len, ..
+        }) => {
+            let mut consts = Vec::new();
+            let len = const_value_to_c(len, false, &mut consts).map_err(|_| Error {
+                level: Level::Error,
+                span: Some(len.span()),
+                file: None,
+                item: None,
+                message: format!(
+                    "bindgen cannot handle the array length `{}` (use a literal or a constant \
+                     defined within the FFI roots)",
+                    len.into_token_stream()
+                ),
+            })?;
+            Ok(CType::Array(Box::new(anon_rust_to_c(elem)?), len, consts))
+        }
         // Standard pointers.
         syn::Type::Ptr(ref ptr) => ptr_to_c(ptr),
         // Plain old types.
@@ -459,14 +1052,18 @@ fn anon_rust_to_c(ty: &syn::Type) -> Result<CType, Error> {
             } else {
                 Err(Error {
                     level: Level::Error,
-                    span: None,
+                    span: Some(ty.span()),
+                    file: None,
+                    item: None,
                     message: format!("bindgen cannot handle the type `{:?}`", ty),
                 })
             }
         }
         ty => Err(Error {
             level: Level::Error,
-            span: None,
+            span: Some(ty.span()),
+            file: None,
+            item: None,
             message: format!("bindgen cannot handle the type `{:?}`", ty),
         }),
     }
@@ -474,7 +1071,8 @@ fn anon_rust_to_c(ty: &syn::Type) -> Result<CType, Error> {
 
 /// Turn a Rust pointer (*mut or *const) into the correct C form.
 fn ptr_to_c(typeptr: &syn::TypePtr) -> Result<CType, Error> {
-    let new_type = anon_rust_to_c(&*typeptr.elem)?;
+    // A pointer to an array points to its first element.
+    let new_type = anon_rust_to_c(innermost_elem(&typeptr.elem))?;
     let const_type: CPtrType;
     if typeptr.mutability.is_some() {
         const_type = CPtrType::Mutable;
@@ -504,7 +1102,9 @@ fn fn_ptr_to_c(fn_ty: &syn::TypeBareFn, inner: &str) -> Result<CType, Error> {
     if fn_ty.lifetimes.to_owned().is_some() {
         return Err(Error {
             level: Level::Error,
-            span: None, //NONE FOR NOW
+            span: Some(fn_ty.lifetimes.span()),
+            file: None,
+            item: None,
             message: "bindgen cannot handle lifetimes".into(),
         });
     }
@@ -519,25 +1119,25 @@ fn fn_ptr_to_c(fn_ty: &syn::TypeBareFn, inner: &str) -> Result<CType, Error> {
                 .0
                 .into_token_stream()
                 .to_string();
-            let arg_type = rust_to_c(&arg.ty, &arg_name1.as_str())?;
+            let arg_type = param_to_c(&arg.ty, &arg_name1.as_str())?;
             args.push(arg_type);
         }
         args
     };
 
     let return_type = match &fn_ty.output {
-        syn::ReturnType::Type(_, ref ty) => {
-            match ty.as_ref() {
-                syn::Type::Never(..) => {
-                    return Err(Error {
-                        level: Level::Error,
-                        span: None, //NONE FOR NOW
-                        message: "panics across a C boundary are naughty!".into(),
-                    });
-                }
-                _ => anon_rust_to_c(&*ty)?,
+        syn::ReturnType::Type(_, ref ty) => match ty.as_ref() {
+            syn::Type::Never(..) => {
+                return Err(Error {
+                    level: Level::Error,
+                    span: Some(ty.span()),
+                    file: None,
+                    item: None,
+                    message: "panics across a C boundary are naughty!".into(),
+                });
             }
-        }
+            _ => decayed_to_c(ty)?,
+        },
         syn::ReturnType::Default => CType::Void,
     };
 
@@ -556,7 +1156,9 @@ fn path_to_c(path: &syn::TypePath) -> Result<CType, Error> {
     if path.path.segments.is_empty() {
         return Err(Error {
             level: Level::Bug,
-            span: None, //NONE FOR NOW
+            span: Some(path.span()),
+            file: None,
+            item: None,
             message: "invalid type".into(),
         });
     }
@@ -578,7 +1180,9 @@ fn path_to_c(path: &syn::TypePath) -> Result<CType, Error> {
             "std::os::raw" => Ok(osraw_ty_to_c(ty)),
             _ => Err(Error {
                 level: Level::Error,
-                span: None,
+                span: Some(path.span()),
+                file: None,
+                item: None,
                 message: "cannot handle types in other modules (except `libc` and `std::os::raw`)"
                     .into(),
             }),
@@ -660,6 +1264,227 @@ fn rust_ty_to_c(ty: &str) -> CType {
     }
 }
 
+/// Removes references and parentheses around a constant expression.
+fn strip_const_expr(expr: &syn::Expr) -> &syn::Expr {
+    match expr {
+        syn::Expr::Reference(ref reference) => strip_const_expr(&reference.expr),
+        syn::Expr::Paren(ref paren) => strip_const_expr(&paren.expr),
+        expr => expr,
+    }
+}
+
+/// Checks whether a constant of the type holds booleans (directly or as array elements).
+fn is_bool(ty: &syn::Type) -> bool {
+    match *ty {
+        syn::Type::Path(ref path) => path.path.is_ident("bool"),
+        syn::Type::Reference(ref reference) => is_bool(&reference.elem),
+        syn::Type::Array(ref array) => is_bool(&array.elem),
+        syn::Type::Slice(ref slice) => is_bool(&slice.elem),
+        _ => false,
+    }
+}
+
+/// Convert the value of a Rust constant into a C expression.
+///
+/// `boolean` tells whether the value is a boolean, for which `!` is a logical rather than a
+/// bitwise negation. The names of other constants the value refers to are added to `deps`.
+fn const_value_to_c(
+    expr: &syn::Expr,
+    boolean: bool,
+    deps: &mut Vec<String>,
+) -> Result<String, Error> {
+    let unsupported = || Error {
+        level: Level::Error,
+        span: Some(expr.span()),
+        file: None,
+        item: None,
+        message: format!(
+            "bindgen cannot handle the constant expression `{}`",
+            expr.into_token_stream()
+        ),
+    };
+
+    match strip_const_expr(expr) {
+        syn::Expr::Lit(ref lit) => const_literal_to_c(&lit.lit).ok_or_else(unsupported),
+        syn::Expr::Unary(ref unary) => match unary.op {
+            syn::UnOp::Neg(..) => Ok(format!(
+                "-{}",
+                const_value_to_c(&unary.expr, boolean, deps)?
+            )),
+            syn::UnOp::Not(..) => Ok(format!(
+                "{}{}",
+                if boolean { "!" } else { "~" },
+                const_value_to_c(&unary.expr, boolean, deps)?
+            )),
+            syn::UnOp::Deref(..) => Err(unsupported()),
+        },
+        syn::Expr::Binary(ref binary) => {
+            // The operands of comparisons may be of any type, the ones of `&&` and `||` are
+            // booleans, and other operators keep the type.
+            let boolean = match binary.op {
+                syn::BinOp::And(..) | syn::BinOp::Or(..) => true,
+                syn::BinOp::Eq(..)
+                | syn::BinOp::Ne(..)
+                | syn::BinOp::Lt(..)
+                | syn::BinOp::Le(..)
+                | syn::BinOp::Gt(..)
+                | syn::BinOp::Ge(..) => false,
+                _ => boolean,
+            };
+            Ok(format!(
+                "({} {} {})",
+                const_value_to_c(&binary.left, boolean, deps)?,
+                binary.op.into_token_stream(),
+                const_value_to_c(&binary.right, boolean, deps)?
+            ))
+        }
+        // Another constant, which must be declared first.
+        syn::Expr::Path(ref path) if path.path.segments.len() == 1 => {
+            let name = path.path.segments[0].ident.to_string();
+            deps.push(name.clone());
+            Ok(name)
+        }
+        syn::Expr::Cast(ref cast) => {
+            let ty = anon_rust_to_c(&cast.ty)?;
+            // Null pointers, e.g. `0 as *const c_char`.
+            if let CType::Ptr(..) = ty {
+                if cast.expr.clone().into_token_stream().to_string() == "0" {
+                    return Ok("NULL".to_string());
+                }
+            }
+            deps.extend(ty.dependencies());
+            Ok(format!(
+                "(({}) {})",
+                ty,
+                const_value_to_c(&cast.expr, false, deps)?
+            ))
+        }
+        syn::Expr::Array(ref array) => {
+            let elems = array
+                .elems
+                .iter()
+                .map(|elem| const_value_to_c(elem, boolean, deps))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(format!("{{ {} }}", elems.join(", ")))
+        }
+        syn::Expr::Repeat(ref repeat) => {
+            let len = match *repeat.len {
+                syn::Expr::Lit(ref lit) => common::extract_int_literal(lit),
+                _ => None,
+            };
+            let elem = const_value_to_c(&repeat.expr, boolean, deps)?;
+            let len = len.ok_or_else(unsupported)? as usize;
+            Ok(format!("{{ {} }}", vec![elem; len].join(", ")))
+        }
+        // Struct literals use designated initialisers.
+        syn::Expr::Struct(ref lit) if lit.rest.is_none() => {
+            deps.push(lit.path.clone().into_token_stream().to_string());
+            let fields = lit
+                .fields
+                .iter()
+                .map(|field| {
+                    Ok(format!(
+                        ".{} = {}",
+                        field.member.clone().into_token_stream(),
+                        const_value_to_c(&field.expr, false, deps)?
+                    ))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            Ok(format!("{{ {} }}", fields.join(", ")))
+        }
+        _ => Err(unsupported()),
+    }
+}
+
+/// Convert a Rust literal into a C literal.
+fn const_literal_to_c(lit: &syn::Lit) -> Option<String> {
+    let value = match lit {
+        syn::Lit::Bool(ref lit) => lit.value.to_string(),
+        syn::Lit::Int(ref lit) => {
+            // Integers with a float suffix (e.g. `1f32`) are floats, unlike hexadecimal
+            // integers which happen to end with the same digits (e.g. `0x1f32`).
+            let source = lit.clone().into_token_stream().to_string();
+            if (source.ends_with("f32") || source.ends_with("f64")) && !source.starts_with("0x") {
+                return Some(float_literal_to_c(&source));
+            }
+
+            let value = lit.value();
+            if value > i64::MAX as u64 {
+                format!("{}ULL", value)
+            } else {
+                value.to_string()
+            }
+        }
+        syn::Lit::Float(ref lit) => {
+            float_literal_to_c(&lit.clone().into_token_stream().to_string())
+        }
+        syn::Lit::Byte(ref lit) => lit.value().to_string(),
+        syn::Lit::Char(ref lit) => {
+            let ch = lit.value();
+            if ch.is_ascii() {
+                format!("'{}'", escape_c(&ch.to_string(), '\''))
+            } else {
+                (ch as u32).to_string()
+            }
+        }
+        syn::Lit::Str(ref lit) => format!("\"{}\"", escape_c(&lit.value(), '"')),
+        syn::Lit::ByteStr(ref lit) => format!(
+            "\"{}\"",
+            lit.value()
+                .iter()
+                .map(|&byte| match byte {
+                    b' '..=b'~' if byte != b'"' && byte != b'\\' => (byte as char).to_string(),
+                    byte => format!("\\{:03o}", byte),
+                })
+                .collect::<String>()
+        ),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Convert the source of a Rust float literal into a C literal, keeping it a floating point one
+/// (e.g. `1f32` is `1.0f` rather than `1`).
+fn float_literal_to_c(source: &str) -> String {
+    let value = source.replace('_', "");
+    let digits = value.trim_end_matches("f32").trim_end_matches("f64");
+    let mut literal = digits.to_string();
+    if !digits.contains(['.', 'e', 'E']) {
+        literal.push_str(".0");
+    }
+    if value.ends_with("f32") {
+        literal.push('f');
+    }
+    literal
+}
+
+/// Escape a string for use within a C literal delimited by `quote`.
+///
+/// Non-ASCII characters are written as UTF-8 octal escapes.
+fn escape_c(value: &str, quote: char) -> String {
+    let mut escaped = String::new();
+    for ch in value.chars() {
+        match ch {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            ch if ch == quote => {
+                escaped.push('\\');
+                escaped.push(ch);
+            }
+            ' '..='~' => escaped.push(ch),
+            ch => {
+                let mut bytes = [0; 4];
+                for byte in ch.encode_utf8(&mut bytes).bytes() {
+                    escaped.push_str(&format!("\\{:03o}", byte));
+                }
+            }
+        }
+    }
+    escaped
+}
+
 /// Wrap a block of code with an extern declaration.
 fn wrap_extern(code: &str) -> String {
     format!(
@@ -678,27 +1503,114 @@ extern "C" {{
     )
 }
 
-/// Wrap a block of code with an include-guard.
-fn wrap_guard(code: &str, id: &str) -> String {
+/// Describe the dependency cycle between headers `node` is part of.
+fn cycle_error(depgraph: &Graph<String, String>, node: NodeIndex) -> Error {
+    let scc = unwrap!(algo::kosaraju_scc(depgraph)
+        .into_iter()
+        .find(|scc| scc.contains(&node)));
+
+    // Follow the dependencies within the strongly connected component until a header repeats.
+    let mut path = vec![node];
+    let mut needs = Vec::new();
+    let mut current = node;
+    loop {
+        let edge = unwrap!(depgraph
+            .edges_directed(current, Direction::Incoming)
+            .find(|edge| scc.contains(&edge.source())));
+        needs.push(format!(
+            "`{}` needs `{}` from `{}`",
+            depgraph[current],
+            edge.weight(),
+            depgraph[edge.source()]
+        ));
+        current = edge.source();
+        if let Some(start) = path.iter().position(|&node| node == current) {
+            needs.drain(..start);
+            break;
+        }
+        path.push(current);
+    }
+
+    Error {
+        level: Level::Error,
+        span: None,
+        file: None,
+        item: None,
+        message: format!("headers depend on each other: {}", needs.join(", ")),
+    }
+}
+
+/// Check whether the custom code already declares the type with a typedef of a struct, union or
+/// enum tag (e.g. `typedef struct App App;`).
+fn declares_type(code: &str, name: &str) -> bool {
+    let code = code
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .map(|line| line.split("//").next().unwrap_or(""))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    code.split(';').any(|statement| {
+        let statement = statement.rsplit(['{', '}']).next().unwrap_or("");
+        let words: Vec<_> = statement.split_whitespace().collect();
+        match words.as_slice() {
+            ["typedef", "struct" | "union" | "enum", _, alias] => *alias == name,
+            _ => false,
+        }
+    })
+}
+
+/// Check whether the custom code defines the macro (e.g. `#define KEY_LEN 32`).
+fn defines_macro(code: &str, name: &str) -> bool {
+    code.lines().any(|line| {
+        let mut words = line.trim_start().trim_start_matches('#').split_whitespace();
+        line.trim_start().starts_with('#')
+            && words.next() == Some("define")
+            && words
+                .next()
+                .is_some_and(|word| word == name || word.starts_with(&format!("{}(", name)))
+    })
+}
+
+/// Wrap a block of code with an include-guard, whose macro is `id` prefixed with `prefix`.
+fn wrap_guard(code: &str, id: &str, prefix: &str) -> String {
     format!(
         r"
-#ifndef bindgen_{0}
-#define bindgen_{0}
+#ifndef {0}{1}
+#define {0}{1}
 
-{1}
+{2}
 
 #endif
 ",
+        prefix,
         sanitise_id(id),
         code
     )
 }
 
-/// Transform a module name into a header name
-fn header_name(module: &[String], lib_name: &str) -> Result<String, Error> {
+/// Turn a header name into the path of an `#include` directive, using angle brackets unless the
+/// name is already quoted (e.g. `stdint.h` becomes `<stdint.h>`).
+fn include_path(include: &str) -> String {
+    if include.starts_with('<') || include.starts_with('"') {
+        include.to_string()
+    } else {
+        format!("<{}>", include)
+    }
+}
+
+/// Transform a module name into a header name.
+///
+/// The FFI root containing the module is replaced by the library name (e.g. `api::ffi::ipc`
+/// becomes `safe_app/ipc.h` for the root `api::ffi`).
+fn header_name(module: &[String], roots: &[Vec<String>], lib_name: &str) -> Result<String, Error> {
+    let root = roots
+        .iter()
+        .filter(|root| !root.is_empty() && module.starts_with(root))
+        .max_by_key(|root| root.len());
     let mut module_name: Vec<String> = module.to_vec();
-    if module_name[0] == "ffi" {
-        module_name[0] = lib_name.to_string();
+    if let Some(root) = root {
+        module_name.splice(..root.len(), vec![lib_name.to_string()]);
 
         // Top-level module for a library - e.g. safe_app/safe_app.h
         if module_name.len() == 1 {
@@ -711,10 +1623,118 @@ fn header_name(module: &[String], lib_name: &str) -> Result<String, Error> {
     Ok(header_name)
 }
 
+/// Keywords of C and C++, which can't be used as names in the headers.
+const KEYWORDS: &[&str] = &[
+    "alignas",
+    "alignof",
+    "and",
+    "and_eq",
+    "asm",
+    "auto",
+    "bitand",
+    "bitor",
+    "bool",
+    "break",
+    "case",
+    "catch",
+    "char",
+    "char16_t",
+    "char32_t",
+    "char8_t",
+    "class",
+    "compl",
+    "concept",
+    "const",
+    "const_cast",
+    "consteval",
+    "constexpr",
+    "constinit",
+    "continue",
+    "co_await",
+    "co_return",
+    "co_yield",
+    "decltype",
+    "default",
+    "delete",
+    "do",
+    "double",
+    "dynamic_cast",
+    "else",
+    "enum",
+    "explicit",
+    "export",
+    "extern",
+    "false",
+    "float",
+    "for",
+    "friend",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "mutable",
+    "namespace",
+    "new",
+    "noexcept",
+    "not",
+    "not_eq",
+    "nullptr",
+    "operator",
+    "or",
+    "or_eq",
+    "private",
+    "protected",
+    "public",
+    "register",
+    "reinterpret_cast",
+    "requires",
+    "restrict",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "static_assert",
+    "static_cast",
+    "struct",
+    "switch",
+    "template",
+    "this",
+    "thread_local",
+    "throw",
+    "true",
+    "try",
+    "typedef",
+    "typeid",
+    "typename",
+    "union",
+    "unsigned",
+    "using",
+    "virtual",
+    "void",
+    "volatile",
+    "wchar_t",
+    "while",
+    "xor",
+    "xor_eq",
+];
+
+/// Returns the name of the union member holding the body of an enum variant, which is suffixed
+/// with an underscore if it would be a C or C++ keyword (e.g. `int_` for `Int`).
+fn member_name(variant: &str) -> String {
+    let name = variant.to_snake_case();
+    if KEYWORDS.contains(&name.as_str()) {
+        format!("{}_", name)
+    } else {
+        name
+    }
+}
+
 /// Remove illegal characters from the identifier.
 ///
 /// This is because macros names must be valid C identifiers. Note that the identifier will always
-/// be concatenated onto `cheddar_generated_` so can start with a digit.
+/// be concatenated onto the guard prefix (`bindgen_` by default) so can start with a digit.
 pub fn sanitise_id(id: &str) -> String {
     // `char.is_digit(36)` ensures `char` is in `[A-Za-z0-9]`
     id.chars()
@@ -1,6 +1,9 @@
 use super::types::CType;
+use crate::common::{Lang, Outputs};
 use crate::lang_c::LangC;
-use crate::test_utils::{fetch, ty};
+use crate::parse;
+use crate::test_utils::{self, fetch, ty};
+use crate::Error;
 use indoc::indoc;
 use unwrap::unwrap;
 
@@ -12,6 +15,28 @@ fn sanitise_id() {
     assert!(super::sanitise_id("filename.h") == "filenameh");
 }
 
+#[test]
+fn declares_type() {
+    let code = indoc!(
+        "
+        #include <app.h>
+        typedef struct App App;
+        typedef union  Value
+            Value; // a value
+        typedef struct {
+            int Other;
+        } Pair;
+        void Unrelated(struct Auth *auth);
+        "
+    );
+
+    assert!(super::declares_type(code, "App"));
+    assert!(super::declares_type(code, "Value"));
+    assert!(!super::declares_type(code, "Other"));
+    assert!(!super::declares_type(code, "Auth"));
+    assert!(!super::declares_type("int App;", "App"));
+}
+
 #[test]
 fn pure_rust_types() {
     let type_map = [
@@ -301,6 +326,18 @@ fn enums() {
             One = 1,
             Four = 4,
         }
+
+        #[repr(u8)]
+        pub enum Priority {
+            Low = 1,
+            High,
+        }
+
+        #[repr(C, i64)]
+        pub enum Offset {
+            Start,
+            End = 8,
+        }
     });
 
     let actual = fetch(&outputs, ".h");
@@ -330,6 +367,18 @@ fn enums() {
              Binary_Four = 4,
          } Binary;
 
+         enum {
+             Priority_Low = 1,
+             Priority_High,
+         };
+         typedef uint8_t Priority;
+
+         enum {
+             Offset_Start,
+             Offset_End = 8,
+         };
+         typedef int64_t Offset;
+
 
 
          #ifdef __cplusplus
@@ -342,6 +391,131 @@ fn enums() {
     );
 
     assert_multiline_eq!(actual, expected);
+    test_utils::assert_compiles("c++", &["-x", "c++"], "enums.hpp", actual);
+}
+
+#[test]
+fn tagged_unions() {
+    let outputs = compile!(LangC::default(), {
+        /// A shape.
+        #[repr(C)]
+        pub enum Shape {
+            Point,
+            /// A circle with its radius.
+            Circle(f64),
+            Rect {
+                width: u32,
+                height: u32,
+            },
+        }
+
+        #[repr(u8)]
+        pub enum Event {
+            Closed,
+            Data { bytes: *const u8, len: usize },
+        }
+    });
+
+    let actual = fetch(&outputs, ".h");
+    let expected = indoc!(
+        "
+
+         #ifndef bindgen_h
+         #define bindgen_h
+
+
+         #ifdef __cplusplus
+         extern \"C\" {
+         #endif
+
+         #include <stdint.h>
+         #include <stdbool.h>
+
+         typedef enum Shape_Tag {
+             Shape_Point,
+             /// A circle with its radius.
+             Shape_Circle,
+             Shape_Rect,
+         } Shape_Tag;
+
+         typedef struct Shape_Circle_Body {
+             double _0;
+         } Shape_Circle_Body;
+
+         typedef struct Shape_Rect_Body {
+             uint32_t width;
+             uint32_t height;
+         } Shape_Rect_Body;
+
+         /// A shape.
+         typedef struct Shape {
+             Shape_Tag tag;
+             union {
+                 Shape_Circle_Body circle;
+                 Shape_Rect_Body rect;
+             } payload;
+         } Shape;
+
+         enum {
+             Event_Closed,
+             Event_Data,
+         };
+         typedef uint8_t Event_Tag;
+
+         typedef struct Event_Data_Body {
+             Event_Tag tag;
+             uint8_t const* bytes;
+             uintptr_t len;
+         } Event_Data_Body;
+
+         typedef union Event {
+             Event_Tag tag;
+             Event_Data_Body data;
+         } Event;
+
+
+
+         #ifdef __cplusplus
+         }
+         #endif
+
+
+         #endif
+        "
+    );
+
+    assert_multiline_eq!(actual, expected);
+    test_utils::assert_compiles("c++", &["-x", "c++"], "tagged_unions.hpp", actual);
+}
+
+#[test]
+fn keyword_variants() {
+    let outputs = compile!(LangC::default(), {
+        #[repr(C)]
+        pub enum Value {
+            Int(i32),
+            Float(f32),
+            Bool(bool),
+            Text { ptr: *const c_char },
+        }
+    });
+
+    let actual = fetch(&outputs, ".h");
+    assert!(actual.contains(indoc!(
+        "
+         typedef struct Value {
+             Value_Tag tag;
+             union {
+                 Value_Int_Body int_;
+                 Value_Float_Body float_;
+                 Value_Bool_Body bool_;
+                 Value_Text_Body text;
+             } payload;
+         } Value;
+        "
+    )));
+    test_utils::assert_compiles("cc", &["-x", "c"], "keyword_variants.h", actual);
+    test_utils::assert_compiles("c++", &["-x", "c++"], "keyword_variants.hpp", actual);
 }
 
 #[test]
@@ -368,6 +542,8 @@ extern \"C\" {
 #include <stdint.h>
 #include <stdbool.h>
 
+typedef struct FfiResult FfiResult;
+
 void foo(void* user_data, void (*o_cb)(void* user_data, FfiResult const* result));
 
 
@@ -411,6 +587,10 @@ extern \"C\" {
 #include <stdint.h>
 #include <stdbool.h>
 
+typedef struct Block Block;
+typedef struct FfiResult FfiResult;
+typedef struct Vote Vote;
+
 FfiResult const* new_block(uint8_t const* payload, uint8_t const* const* public_ids, Vote const* const* votes, uintptr_t votes_len, Block const** o_block);
 
 
@@ -427,3 +607,563 @@ FfiResult const* new_block(uint8_t const* payload, uint8_t const* const* public_
 
     assert_multiline_eq!(actual, expected);
 }
+
+#[test]
+fn plain_extern_functions() {
+    // A plain `extern` defaults to the C ABI.
+    let outputs = compile!(LangC::default(), {
+        #[no_mangle]
+        pub extern "C" fn app_free(app: *mut App) {}
+    });
+
+    let actual = fetch(&outputs, ".h");
+    assert!(actual.contains("void app_free(App* app);"));
+}
+
+#[test]
+fn doc_comments() {
+    // Every doc line is put on a line of its own, before the declaration it documents.
+    let outputs = compile!(LangC::default(), {
+        /// A key pair.
+        ///
+        /// Both keys are owned.
+        #[repr(C)]
+        pub struct KeyPair {
+            /// The public key.
+            pub public: u64,
+            pub secret: u64,
+        }
+
+        /// Mode of a key.
+        #[repr(C)]
+        pub enum Mode {
+            /// Read only.
+            Read,
+        }
+
+        /// Frees a key pair.
+        #[no_mangle]
+        pub extern "C" fn key_pair_free(pair: *mut KeyPair) {}
+    });
+
+    let actual = fetch(&outputs, ".h");
+    assert!(actual.contains(
+        "/// A key pair.\n\
+         ///\n\
+         /// Both keys are owned.\n\
+         typedef struct KeyPair {\n\
+         \x20   /// The public key.\n\
+         \x20   uint64_t public;\n\
+         \x20   uint64_t secret;\n\
+         } KeyPair;\n\
+         \n\
+         /// Mode of a key.\n\
+         typedef enum Mode {\n\
+         \x20   /// Read only.\n\
+         \x20   Mode_Read,\n\
+         } Mode;\n\
+         \n\
+         /// Frees a key pair.\n\
+         void key_pair_free(KeyPair* pair);\n"
+    ));
+}
+
+#[test]
+fn error_locations() {
+    let source = indoc!(
+        "
+        #[repr(C)]
+        pub enum Good {
+            A,
+        }
+
+        #[repr(C)]
+        pub enum Bad {
+            A,
+            B((u8, u8)),
+        }
+
+        #[no_mangle]
+        pub extern \"C\" fn foo(a: (u8, u8)) {}
+        "
+    );
+
+    let errors = match test_utils::try_compile(LangC::default(), source.to_string()) {
+        Ok(_) => panic!("compilation should fail"),
+        Err(errors) => errors,
+    };
+    let locations: Vec<_> = errors.iter().map(|e| unwrap!(e.line_column())).collect();
+
+    assert_eq!(locations, vec![(9, 7), (13, 26)]);
+}
+
+#[test]
+fn constants() {
+    let outputs = compile!(LangC::default(), {
+        /// Length of a key.
+        pub const KEY_LEN: usize = 32;
+        pub const NEGATIVE: i32 = -1;
+        pub const MASK: u64 = 0xffff_ffff_ffff_ffff;
+        pub const FLAGS: u32 = KEY_LEN as u32 | 1 << 4;
+        pub const RATIO: f32 = 0.5;
+        pub const ONE: f32 = 1f32;
+        pub const LARGE: f64 = 1e10;
+        pub const ENABLED: bool = true;
+        pub const DISABLED: bool = !ENABLED && !false;
+        pub const INVERTED: u8 = !0;
+        pub const SEPARATOR: char = '\'';
+        pub const NAME: &str = "say \"hi\"\n";
+        pub const NO_NAME: *const c_char = 0 as *const c_char;
+        pub const KEY: [u8; KEY_LEN] = [0; 32];
+        pub const PRIMES: &[u16] = &[2, 3, 5];
+        #[repr(C)]
+        pub struct Point {
+            pub x: i32,
+            pub y: i32,
+        }
+        pub const ORIGIN: Point = Point { x: 0, y: -1 };
+        const PRIVATE: u8 = 1;
+    });
+
+    let actual = fetch(&outputs, ".h");
+    let expected = indoc!(
+        "
+
+         #ifndef bindgen_h
+         #define bindgen_h
+
+
+         #ifdef __cplusplus
+         extern \"C\" {
+         #endif
+
+         #include <stdint.h>
+         #include <stdbool.h>
+
+         /// Length of a key.
+         #define KEY_LEN 32
+
+         #define NEGATIVE -1
+
+         #define MASK 18446744073709551615ULL
+
+         #define FLAGS (((uint32_t) KEY_LEN) | (1 << 4))
+
+         #define RATIO 0.5
+
+         #define ONE 1.0f
+
+         #define LARGE 1e10
+
+         #define ENABLED true
+
+         #define DISABLED (!ENABLED && !false)
+
+         #define INVERTED ~0
+
+         #define SEPARATOR '\\''
+
+         #define NAME \"say \\\"hi\\\"\\n\"
+
+         #define NO_NAME NULL
+
+         static const uint8_t KEY[KEY_LEN] = { 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, \
+         0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0 };
+
+         static const uint16_t PRIMES[] = { 2, 3, 5 };
+
+         typedef struct Point {
+             int32_t x;
+             int32_t y;
+         } Point;
+
+         static const Point ORIGIN = { .x = 0, .y = -1 };
+
+
+
+         #ifdef __cplusplus
+         }
+         #endif
+
+
+         #endif
+        "
+    );
+
+    assert_multiline_eq!(actual, expected);
+}
+
+#[test]
+fn root_constants() {
+    let mut lang = LangC::default();
+    let mut outputs = Outputs::default();
+
+    // Constants of the crate root are parsed with an empty module path.
+    let ast: syn::File = unwrap!(syn::parse_str(
+        "pub const KEY_LEN: usize = 32; pub const ZERO: [u8; 2] = [0; 2];"
+    ));
+    for item in &ast.items {
+        if let syn::Item::Const(ref item) = *item {
+            unwrap!(lang.parse_const(item, &[], &mut outputs));
+        }
+    }
+    let ast: syn::File = unwrap!(syn::parse_str(
+        "#[repr(C)] pub struct Key { pub bytes: [u8; KEY_LEN] }"
+    ));
+    unwrap!(parse::parse_file(
+        &mut lang,
+        &ast,
+        &["ffi".to_string(), "keys".to_string()],
+        &mut outputs
+    ));
+    unwrap!(lang.finalise_output(&mut outputs));
+
+    assert!(!outputs.contains_key("backend/backend.h"));
+    let header = fetch(&outputs, "backend.h");
+    assert!(header.contains(
+        "#define KEY_LEN 32\n\
+         \n\
+         #include \"backend/keys.h\"\n\
+         \n\
+         #include <stdint.h>\n\
+         #include <stdbool.h>\n\
+         static const uint8_t ZERO[2] = { 0, 0 };\n"
+    ));
+}
+
+#[test]
+fn constant_dependencies() {
+    let mut lang = LangC::default();
+    let mut outputs = Outputs::default();
+
+    let ast: syn::File = unwrap!(syn::parse_str("pub const LEN: usize = 32;"));
+    unwrap!(parse::parse_file(
+        &mut lang,
+        &ast,
+        &["ffi".to_string(), "consts".to_string()],
+        &mut outputs
+    ));
+    let ast: syn::File = unwrap!(syn::parse_str("pub const KEY: [u8; 2] = [LEN as u8, 0];"));
+    unwrap!(parse::parse_file(
+        &mut lang,
+        &ast,
+        &["ffi".to_string(), "keys".to_string()],
+        &mut outputs
+    ));
+    let ast: syn::File = unwrap!(syn::parse_str(
+        "#[repr(C)] pub struct Name { pub bytes: [u8; LEN] }"
+    ));
+    unwrap!(parse::parse_file(
+        &mut lang,
+        &ast,
+        &["ffi".to_string(), "names".to_string()],
+        &mut outputs
+    ));
+    unwrap!(lang.finalise_output(&mut outputs));
+
+    let header = fetch(&outputs, "backend.h");
+    let consts = unwrap!(header.find("#include \"backend/consts.h\""));
+    let keys = unwrap!(header.find("#include \"backend/keys.h\""));
+    let names = unwrap!(header.find("#include \"backend/names.h\""));
+    assert!(consts < keys);
+    assert!(consts < names);
+}
+
+#[test]
+fn arrays() {
+    let outputs = compile!(LangC::default(), {
+        pub const XOR_NAME_LEN: usize = 32;
+
+        pub type Key = [u8; 32];
+
+        #[repr(C)]
+        pub struct Names {
+            pub name: [u8; XOR_NAME_LEN],
+            pub matrix: [[i32; 4]; 2],
+            pub key: *const [u8; 32],
+        }
+
+        #[no_mangle]
+        pub extern "C" fn check(name: [u8; XOR_NAME_LEN], cb: extern "C" fn(key: [u8; 32])) {}
+    });
+
+    let actual = fetch(&outputs, ".h");
+    let expected = indoc!(
+        "
+
+         #ifndef bindgen_h
+         #define bindgen_h
+
+
+         #ifdef __cplusplus
+         extern \"C\" {
+         #endif
+
+         #include <stdint.h>
+         #include <stdbool.h>
+
+         #define XOR_NAME_LEN 32
+
+         typedef uint8_t Key[32];
+
+         typedef struct Names {
+             uint8_t name[XOR_NAME_LEN];
+             int32_t matrix[2][4];
+             uint8_t const* key;
+         } Names;
+
+         void check(uint8_t const* name, void (*cb)(uint8_t const* key));
+
+
+
+         #ifdef __cplusplus
+         }
+         #endif
+
+
+         #endif
+        "
+    );
+
+    assert_multiline_eq!(actual, expected);
+}
+
+#[test]
+fn array_parameters() {
+    // Parameters are passed as pointers, so their lengths don't have to be emitted.
+    let outputs = compile!(LangC::default(), {
+        #[no_mangle]
+        pub extern "C" fn keys(
+            key: [u8; other::KEY_LEN],
+            hash: [u8; size_of::<Hash>()],
+            matrix: [[i32; 4]; 2],
+        ) {
+        }
+    });
+    assert!(fetch(&outputs, ".h")
+        .contains("void keys(uint8_t const* key, uint8_t const* hash, int32_t const* matrix);"));
+
+    // Casts in lengths don't make the type a dependency.
+    let cty = unwrap!(super::anon_rust_to_c(&ty("[u8; LEN as usize]")));
+    assert_eq!(cty.to_string(), "uint8_t[((uintptr_t) LEN)]");
+    assert_eq!(cty.dependencies(), vec!["LEN".to_string()]);
+
+    let error = match try_compile!(LangC::default(), {
+        #[repr(C)]
+        pub struct Hash {
+            pub bytes: [u8; size_of::<Hash>()],
+        }
+    }) {
+        Ok(_) => panic!("unsupported array lengths should be rejected"),
+        Err(errors) => unwrap!(errors.into_iter().next()),
+    };
+    assert!(error
+        .message
+        .starts_with("bindgen cannot handle the array length `size_of :: < Hash > ( )`"));
+
+    // `KEY_LEN` is not generated, e.g. because it is imported from another crate.
+    let error = match try_compile!(LangC::default(), {
+        #[repr(C)]
+        pub struct Key {
+            pub bytes: [u8; KEY_LEN],
+        }
+    }) {
+        Ok(_) => panic!("array lengths using unknown constants should be rejected"),
+        Err(errors) => unwrap!(errors.into_iter().next()),
+    };
+    assert!(error
+        .message
+        .contains("uses the constant `KEY_LEN` in an array length"));
+
+    let mut lang = LangC::default();
+    lang.add_custom_code("#define KEY_LEN 32\n");
+    let _ = compile!(lang, {
+        #[repr(C)]
+        pub struct Key {
+            pub bytes: [u8; KEY_LEN],
+        }
+    });
+}
+
+#[test]
+fn opaque_types() {
+    let mut lang = LangC::default();
+    lang.add_custom_code("typedef struct FileContext FileContext;\n");
+    lang.add_opaque_type("Authenticator");
+    lang.add_opaque_type("App");
+    let mut outputs = Outputs::default();
+
+    let ast: syn::File = unwrap!(syn::parse_str(
+        "#[no_mangle]
+        pub extern \"C\" fn app_free(app: *mut App) {}"
+    ));
+    unwrap!(parse::parse_file(
+        &mut lang,
+        &ast,
+        &["ffi".to_string(), "app".to_string()],
+        &mut outputs
+    ));
+    let ast: syn::File = unwrap!(syn::parse_str(
+        "#[no_mangle]
+        pub extern \"C\" fn file_open(app: *const App, ctx: *mut *mut FileContext) {}"
+    ));
+    unwrap!(parse::parse_file(
+        &mut lang,
+        &ast,
+        &["ffi".to_string(), "files".to_string()],
+        &mut outputs
+    ));
+    unwrap!(lang.finalise_output(&mut outputs));
+
+    // Types are declared once, by the first header referring to them.
+    let app = fetch(&outputs, "backend/app.h");
+    assert!(app.contains("#include <stdbool.h>\n\ntypedef struct App App;\n\nvoid app_free"));
+    let files = fetch(&outputs, "backend/files.h");
+    assert!(!files.contains("typedef"));
+
+    let header = fetch(&outputs, "backend.h");
+    assert!(header.contains(
+        "typedef struct FileContext FileContext;\n\n\
+         typedef struct Authenticator Authenticator;\n\n\
+         #include \"backend/app.h\"\n\
+         #include \"backend/files.h\"\n"
+    ));
+}
+
+fn parse_modules(lang: &mut LangC, modules: &[(&str, &str)]) -> Result<Outputs, Error> {
+    let mut outputs = Outputs::default();
+    for &(module, source) in modules {
+        let ast: syn::File = unwrap!(syn::parse_str(source));
+        unwrap!(parse::parse_file(
+            lang,
+            &ast,
+            &["ffi".to_string(), module.to_string()],
+            &mut outputs
+        ));
+    }
+    lang.finalise_output(&mut outputs)?;
+    Ok(outputs)
+}
+
+#[test]
+fn pointer_cycles() {
+    let mut lang = LangC::default();
+    let outputs = unwrap!(parse_modules(
+        &mut lang,
+        &[
+            (
+                "a",
+                "#[repr(C)] pub struct A { pub b: *const B, pub c: *const C, pub kind: Kind }",
+            ),
+            (
+                "b",
+                "#[repr(C)] pub struct B { pub a: *mut A }
+                 #[repr(C)] pub enum Kind { Leaf, Node }",
+            ),
+            ("c", "#[repr(C)] pub struct C { pub a: *const A }"),
+        ]
+    ));
+
+    // `A` is only used through pointers, so it is forward declared to break the cycles, once.
+    let c = fetch(&outputs, "backend/c.h");
+    assert!(c.contains("#include <stdbool.h>\n\ntypedef struct A A;\n\ntypedef struct C {"));
+    let b = fetch(&outputs, "backend/b.h");
+    assert!(b.contains("#include <stdbool.h>\n\ntypedef struct B {"));
+
+    // C99 doesn't allow repeating the typedef in the definition.
+    let a = fetch(&outputs, "backend/a.h");
+    assert!(a.contains("\nstruct A {\n    B const* b;\n    C const* c;\n    Kind kind;\n};\n"));
+    assert!(!a.contains("typedef struct A"));
+    assert!(!a.contains("typedef struct B B;"));
+
+    let header = fetch(&outputs, "backend.h");
+    assert!(header.contains(
+        "#include \"backend/c.h\"\n#include \"backend/b.h\"\n#include \"backend/a.h\"\n"
+    ));
+    test_utils::assert_compiles(
+        "cc",
+        &["-x", "c", "-std=c99", "-pedantic-errors"],
+        "pointer_cycles.h",
+        &format!("{}{}{}", c, b, a),
+    );
+}
+
+#[test]
+fn value_cycles() {
+    let mut lang = LangC::default();
+    let errors = parse_modules(
+        &mut lang,
+        &[
+            (
+                "a",
+                "#[repr(C)] pub struct A { pub b: B }
+                 pub type Handle = u64;",
+            ),
+            ("b", "#[repr(C)] pub struct B { pub handle: Handle }"),
+        ],
+    );
+
+    match errors {
+        Err(error) => assert_eq!(
+            error.message,
+            "headers depend on each other: `backend/b.h` needs `Handle` from `backend/a.h`, \
+             `backend/a.h` needs `B` from `backend/b.h`"
+        ),
+        Ok(_) => panic!("cycle not detected"),
+    }
+}
+
+#[test]
+fn single_header() {
+    let mut lang = LangC::default();
+    lang.set_single_header(true);
+    lang.set_guard_prefix("SAFE_");
+    lang.reset_includes();
+    lang.add_include("stdint.h");
+    lang.add_include("\"types.h\"");
+    lang.add_custom_code("#define VERSION 1\n");
+    let outputs = unwrap!(parse_modules(
+        &mut lang,
+        &[
+            ("a", "#[repr(C)] pub struct A { pub b: B }"),
+            ("b", "#[repr(C)] pub struct B { pub len: usize }"),
+        ]
+    ));
+
+    assert_eq!(outputs.len(), 1);
+    let expected = indoc!(
+        "
+
+         #ifndef SAFE_backendh
+         #define SAFE_backendh
+
+
+         #ifdef __cplusplus
+         extern \"C\" {
+         #endif
+
+         #include <stdint.h>
+         #include \"types.h\"
+
+         #define VERSION 1
+
+         typedef struct B {
+             uintptr_t len;
+         } B;
+
+         typedef struct A {
+             B b;
+         } A;
+
+
+
+         #ifdef __cplusplus
+         }
+         #endif
+
+
+         #endif
+        "
+    );
+    assert_multiline_eq!(fetch(&outputs, "backend.h"), expected);
+}
@@ -12,6 +12,17 @@ impl Display for CTypeNamed {
             // special case
             CType::FnDecl { .. } => write!(f, "{}", self.1),
 
+            // Array lengths follow the name, outermost first (`uint8_t name[2][32]`)
+            CType::Array(..) => {
+                let mut ty = &self.1;
+                let mut lengths = String::new();
+                while let CType::Array(ref elem, ref len, _) = *ty {
+                    lengths.push_str(&format!("[{}]", len));
+                    ty = elem;
+                }
+                write!(f, "{} {}{}", ty, self.0, lengths)
+            }
+
             // For all other cases we add a type prefix
             _ => write!(f, "{} {}", self.1, self.0),
         }
@@ -39,6 +50,9 @@ pub enum CType {
     Mapping(String),
     Native(&'static str),
     Ptr(Box<CType>, CPtrType),
+    /// Fixed-size array with its length, which is either a number or a C expression referring
+    /// to the listed constants.
+    Array(Box<CType>, String, Vec<String>),
     FnDecl {
         inner: String,
         args: Vec<CTypeNamed>,
@@ -64,10 +78,97 @@ impl CType {
                 )
                 .collect(),
             CType::Ptr(ref cty, _) => cty.dependencies(),
+            // Constants used in the length need to be defined as well.
+            CType::Array(ref cty, _, ref consts) => cty
+                .dependencies()
+                .into_iter()
+                .chain(consts.iter().cloned())
+                .collect(),
+            CType::Mapping(ref mapping) => vec![mapping.clone()],
+            _ => Default::default(),
+        }
+    }
+
+    /// Returns a list of user-defined types this `CType` refers to, leaving out the constants
+    /// used in array lengths
+    pub fn type_dependencies(&self) -> Vec<String> {
+        match *self {
+            CType::FnDecl {
+                ref args,
+                ref return_type,
+                ..
+            } => return_type
+                .type_dependencies()
+                .into_iter()
+                .chain(
+                    args.iter()
+                        .flat_map(|CTypeNamed(_, cty)| cty.type_dependencies()),
+                )
+                .collect(),
+            CType::Ptr(ref cty, _) | CType::Array(ref cty, ..) => cty.type_dependencies(),
+            CType::Mapping(ref mapping) => vec![mapping.clone()],
+            _ => Default::default(),
+        }
+    }
+
+    /// Returns a list of user-defined types and constants which have to be defined before this
+    /// `CType` is used, leaving out the types which are only referred to through pointers
+    pub fn value_dependencies(&self) -> Vec<String> {
+        match *self {
+            CType::FnDecl {
+                ref args,
+                ref return_type,
+                ..
+            } => return_type
+                .value_dependencies()
+                .into_iter()
+                .chain(
+                    args.iter()
+                        .flat_map(|CTypeNamed(_, cty)| cty.value_dependencies()),
+                )
+                .collect(),
+            // Constants in array lengths are needed even behind pointers.
+            CType::Ptr(ref cty, _) => {
+                let types = cty.type_dependencies();
+                cty.dependencies()
+                    .into_iter()
+                    .filter(|dep| !types.contains(dep))
+                    .collect()
+            }
+            CType::Array(ref cty, _, ref consts) => cty
+                .value_dependencies()
+                .into_iter()
+                .chain(consts.iter().cloned())
+                .collect(),
             CType::Mapping(ref mapping) => vec![mapping.clone()],
             _ => Default::default(),
         }
     }
+
+    /// Returns a list of the constants used in array lengths
+    pub fn length_dependencies(&self) -> Vec<String> {
+        match *self {
+            CType::FnDecl {
+                ref args,
+                ref return_type,
+                ..
+            } => return_type
+                .length_dependencies()
+                .into_iter()
+                .chain(
+                    args.iter()
+                        .flat_map(|CTypeNamed(_, cty)| cty.length_dependencies()),
+                )
+                .collect(),
+            CType::Ptr(ref cty, _) => cty.length_dependencies(),
+            CType::Array(ref cty, _, ref consts) => cty
+                .length_dependencies()
+                .into_iter()
+                .chain(consts.iter().cloned())
+                .collect(),
+            _ => Default::default(),
+        }
+    }
 }
 
 impl Display for CType {
@@ -77,6 +178,7 @@ impl Display for CType {
             CType::Mapping(ref s) => write!(f, "{}", s),
             CType::Native(s) => write!(f, "{}", s),
             CType::Ptr(ref cty, ref ptrty) => write!(f, "{}{}*", cty, ptrty),
+            CType::Array(ref cty, ref len, _) => write!(f, "{}[{}]", cty, len),
             CType::FnDecl {
                 ref inner,
                 ref args,
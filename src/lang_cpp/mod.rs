@@ -0,0 +1,495 @@
+//! Functions for converting Rust types to C++ types.
+//!
+//! The C declarations are generated by `LangC` and put into a namespace. On top of them, handles
+//! which can be freed get `std::unique_ptr` deleters and functions taking a `user_data` pointer and
+//! a callback receiving an `FfiResult` get wrappers taking a `std::function` or returning a
+//! `std::future`.
+
+#[cfg(test)]
+mod tests;
+
+use crate::common::{self, Lang, Outputs};
+use crate::lang_c::{self, LangC};
+use crate::{Config, Error};
+use inflector::Inflector;
+use std::collections::{BTreeMap, BTreeSet};
+use unwrap::unwrap;
+
+pub struct LangCpp {
+    lib_name: String,
+    namespace: Option<String>,
+    free_suffix: String,
+    /// Generator of the C declarations and the headers they are put into.
+    c: LangC,
+    c_outputs: Outputs,
+    /// Names of the `#[repr(C)]` structs, which results of callbacks can be copied from.
+    structs: BTreeSet<String>,
+    /// Deleters of the handles, by the name of the handle type.
+    deleters: BTreeMap<String, String>,
+    wrappers: String,
+}
+
+/// A value passed to a callback, turned into a value owned by C++.
+struct CallbackValue {
+    ty: String,
+    value: String,
+}
+
+impl LangCpp {
+    pub fn new() -> Self {
+        Self {
+            lib_name: "backend".to_owned(),
+            namespace: None,
+            free_suffix: "_free".to_owned(),
+            c: LangC::new(),
+            c_outputs: Outputs::default(),
+            structs: BTreeSet::new(),
+            deleters: BTreeMap::new(),
+            wrappers: String::new(),
+        }
+    }
+
+    /// Set the name of the native library.
+    pub fn set_lib_name<T: Into<String>>(&mut self, name: T) {
+        self.lib_name = name.into();
+    }
+
+    /// Set the namespace of the declarations. Defaults to the name of the native library.
+    pub fn set_namespace<T: Into<String>>(&mut self, namespace: T) {
+        self.namespace = Some(namespace.into());
+    }
+
+    /// Set the suffix of the functions freeing handles (`_free` by default, as in `app_free`).
+    pub fn set_free_suffix<T: Into<String>>(&mut self, suffix: T) {
+        self.free_suffix = suffix.into();
+    }
+
+    /// Adds manual C code in front of the generated declarations.
+    pub fn add_custom_code(&mut self, code: &str) {
+        self.c.add_custom_code(code);
+    }
+
+    /// Add definition of opaque type (type represented by an opaque pointer).
+    pub fn add_opaque_type<T: Into<String>>(&mut self, name: T) {
+        self.c.add_opaque_type(name);
+    }
+
+    /// Generate a deleter for a function freeing a handle, which takes the handle as its only
+    /// argument (e.g. `app_free(app: *mut App)`).
+    fn transform_free_fn(&mut self, item: &syn::ItemFn, name: &str) -> Result<(), Error> {
+        if !name.ends_with(&self.free_suffix)
+            || item.decl.inputs.len() != 1
+            || item.decl.output != syn::ReturnType::Default
+        {
+            return Ok(());
+        }
+        let handle = match common::transform_fnarg_to_argcap(&item.decl.inputs[0]) {
+            Some(syn::ArgCaptured {
+                ty: syn::Type::Ptr(ref ptr),
+                ..
+            }) if ptr.mutability.is_some() => match *ptr.elem {
+                syn::Type::Path(ref path) if path.path.segments.len() == 1 => {
+                    path.path.segments[0].ident.to_string()
+                }
+                _ => return Ok(()),
+            },
+            _ => return Ok(()),
+        };
+        if handle == "c_void" || self.deleters.contains_key(&handle) {
+            return Ok(());
+        }
+
+        let deleter = format!(
+            "/// Frees `{handle}` handles with `{name}`.\n\
+             struct {handle}Deleter {{\n\
+             \x20   void operator()({handle}* ptr) const {{\n\
+             \x20       {name}(ptr);\n\
+             \x20   }}\n\
+             }};\n\
+             \n\
+             using {handle}Ptr = std::unique_ptr<{handle}, {handle}Deleter>;\n\n",
+            handle = handle,
+            name = name,
+        );
+        let _ = self.deleters.insert(handle, deleter);
+
+        Ok(())
+    }
+
+    /// Generate the wrappers of a function taking `user_data` and a callback which receives the
+    /// `user_data` and an `FfiResult`, followed by the values of the result.
+    ///
+    /// One wrapper takes a `std::function` instead of the callback, so that it can capture state,
+    /// and the other one returns a `std::future` of the values.
+    fn transform_callback_fn(
+        &mut self,
+        item: &syn::ItemFn,
+        name: &str,
+        docs: &str,
+    ) -> Result<(), Error> {
+        if item.decl.output != syn::ReturnType::Default {
+            return Ok(());
+        }
+        let args: Vec<_> = item
+            .decl
+            .inputs
+            .iter()
+            .filter_map(common::transform_fnarg_to_argcap)
+            .collect();
+        if args.len() != item.decl.inputs.len()
+            || args
+                .iter()
+                .filter(|arg| common::is_user_data_arg(arg))
+                .count()
+                != 1
+        {
+            return Ok(());
+        }
+        let mut callbacks = args.iter().filter_map(|arg| match arg.ty {
+            syn::Type::BareFn(ref bare_fn) => Some((*arg, bare_fn)),
+            _ => None,
+        });
+        let (callback, bare_fn) = match (callbacks.next(), callbacks.next()) {
+            (Some(callback), None) => callback,
+            _ => return Ok(()),
+        };
+        let inputs: Vec<_> = bare_fn.inputs.iter().collect();
+        if inputs.len() < 2
+            || !common::is_user_data_arg_barefn(inputs[0])
+            || !common::is_result_arg_barefn(inputs[1])
+        {
+            return Ok(());
+        }
+
+        let callback_name = arg_name(callback);
+        let callback_type = format!("{}Callback", name.to_pascal_case());
+
+        // Parameters of the callback, and the values a future is completed with.
+        let mut cb_params = Vec::new();
+        let mut cb_types = Vec::new();
+        let mut values = Vec::new();
+        let mut index = 0;
+        while index < inputs.len() {
+            let arg = inputs[index];
+            let name = barefn_arg_name(arg, index);
+            cb_params.push(lang_c::param_to_c(&arg.ty, &name)?.to_string());
+            cb_types.push(anon_type(&arg.ty)?);
+
+            if index >= 2 {
+                let next_arg = inputs.get(index + 1).cloned();
+                if common::is_array_arg_barefn(arg, next_arg) {
+                    // Arrays are passed as a pointer followed by the length.
+                    let len_arg = unwrap!(next_arg);
+                    let len = barefn_arg_name(len_arg, index + 1);
+                    cb_params.push(lang_c::param_to_c(&len_arg.ty, &len)?.to_string());
+                    cb_types.push(anon_type(&len_arg.ty)?);
+                    values.push(self.array_value(&arg.ty, &name, &len)?);
+                    index += 1;
+                } else {
+                    values.push(self.callback_value(&arg.ty, &name)?);
+                }
+            }
+            index += 1;
+        }
+
+        let function_type = format!("std::function<void({})>", cb_types[1..].join(", "));
+        let cb_args: Vec<_> = (1..inputs.len())
+            .map(|index| barefn_arg_name(inputs[index], index))
+            .collect();
+
+        // Parameters of the wrappers, and the arguments of the native function.
+        let mut params = Vec::new();
+        let mut param_names = Vec::new();
+        let mut call_args = Vec::new();
+        for arg in &args {
+            if common::is_user_data_arg(arg) {
+                call_args.push("user_data".to_string());
+            } else if let syn::Type::BareFn(..) = arg.ty {
+                call_args.push(format!(
+                    "+[]({}) {{\n\
+                     \x20       std::unique_ptr<{ty}> {cb}(static_cast<{ty}*>(user_data));\n\
+                     \x20       (*{cb})({});\n\
+                     \x20   }}",
+                    cb_params.join(", "),
+                    cb_args.join(", "),
+                    ty = callback_type,
+                    cb = callback_name,
+                ));
+            } else {
+                let name = arg_name(arg);
+                params.push(lang_c::param_to_c(&arg.ty, &name)?.to_string());
+                param_names.push(name.clone());
+                call_args.push(name);
+            }
+        }
+
+        let mut buffer = format!("using {} = {};\n\n", callback_type, function_type);
+        buffer.push_str(docs);
+        buffer.push_str(&format!(
+            "inline void {name}({params}{sep}{ty} {cb}) {{\n\
+             \x20   auto user_data = new {ty}(std::move({cb}));\n\
+             \x20   {name}({call_args});\n\
+             }}\n\n",
+            name = name,
+            params = params.join(", "),
+            sep = if params.is_empty() { "" } else { ", " },
+            ty = callback_type,
+            cb = callback_name,
+            call_args = call_args.join(", "),
+        ));
+
+        let (future_type, set_value) = match values.len() {
+            0 => ("void".to_string(), "promise->set_value();".to_string()),
+            1 => (
+                values[0].ty.clone(),
+                format!("promise->set_value({});", values[0].value),
+            ),
+            _ => {
+                let ty = format!(
+                    "std::tuple<{}>",
+                    values
+                        .iter()
+                        .map(|value| value.ty.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+                let set_value = format!(
+                    "promise->set_value({}({}));",
+                    ty,
+                    values
+                        .iter()
+                        .map(|value| value.value.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+                (ty, set_value)
+            }
+        };
+        buffer.push_str(docs);
+        buffer.push_str(&format!(
+            "inline std::future<{ty}> {name}_async({params}) {{\n\
+             \x20   auto promise = std::make_shared<std::promise<{ty}>>();\n\
+             \x20   {name}({args}{sep}[promise]({cb_params}) {{\n\
+             \x20       if ({result}->error_code != 0) {{\n\
+             \x20           promise->set_exception(std::make_exception_ptr(\n\
+             \x20               FfiError({result}->error_code, {result}->description)));\n\
+             \x20           return;\n\
+             \x20       }}\n\
+             \x20       {set_value}\n\
+             \x20   }});\n\
+             \x20   return promise->get_future();\n\
+             }}\n\n",
+            ty = future_type,
+            name = name,
+            params = params.join(", "),
+            args = param_names.join(", "),
+            sep = if param_names.is_empty() { "" } else { ", " },
+            cb_params = cb_params[1..].join(", "),
+            result = barefn_arg_name(inputs[1], 1),
+            set_value = set_value,
+        ));
+
+        self.wrappers.push_str(&buffer);
+        Ok(())
+    }
+
+    /// Turn a value passed to a callback into a value which outlives the callback.
+    ///
+    /// Strings and structs passed by pointer are copied, other values are kept as they are.
+    fn callback_value(&self, ty: &syn::Type, name: &str) -> Result<CallbackValue, Error> {
+        if let syn::Type::Ptr(ref ptr) = *ty {
+            if ptr.mutability.is_none() {
+                if let syn::Type::Path(ref path) = *ptr.elem {
+                    let pointee = unwrap!(path.path.segments.last()).into_value();
+                    let pointee = pointee.ident.to_string();
+                    if pointee == "c_char" {
+                        return Ok(CallbackValue {
+                            ty: "std::string".to_string(),
+                            value: format!("std::string({0} ? {0} : \"\")", name),
+                        });
+                    }
+                    if self.structs.contains(&pointee) {
+                        return Ok(CallbackValue {
+                            ty: pointee,
+                            value: format!("*{}", name),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(CallbackValue {
+            ty: anon_type(ty)?,
+            value: name.to_string(),
+        })
+    }
+
+    /// Copy an array passed to a callback as a pointer and a length into a `std::vector`.
+    fn array_value(&self, ty: &syn::Type, name: &str, len: &str) -> Result<CallbackValue, Error> {
+        let elem = match *ty {
+            syn::Type::Ptr(ref ptr) => anon_type(&ptr.elem)?,
+            _ => unreachable!("arrays are passed as pointers"),
+        };
+        let ty = format!("std::vector<{}>", elem);
+
+        Ok(CallbackValue {
+            value: format!("{}({}, {} + {})", ty, name, name, len),
+            ty,
+        })
+    }
+}
+
+impl Default for LangCpp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lang for LangCpp {
+    fn apply_config(&mut self, config: &Config) {
+        if let Some(lib_name) = config.lib_name_or(&config.cpp.lib_name) {
+            self.set_lib_name(lib_name);
+        }
+        if let Some(ref namespace) = config.cpp.namespace {
+            self.set_namespace(namespace.as_str());
+        }
+        if let Some(ref suffix) = config.cpp.free_suffix {
+            self.set_free_suffix(suffix.as_str());
+        }
+        self.c.apply_config(config);
+    }
+
+    fn parse_const(
+        &mut self,
+        item: &syn::ItemConst,
+        module: &[String],
+        _outputs: &mut Outputs,
+    ) -> Result<(), Error> {
+        self.c.parse_const(item, module, &mut self.c_outputs)
+    }
+
+    fn parse_ty(
+        &mut self,
+        item: &syn::ItemType,
+        module: &[String],
+        _outputs: &mut Outputs,
+    ) -> Result<(), Error> {
+        self.c.parse_ty(item, module, &mut self.c_outputs)
+    }
+
+    fn parse_enum(
+        &mut self,
+        item: &syn::ItemEnum,
+        module: &[String],
+        _outputs: &mut Outputs,
+    ) -> Result<(), Error> {
+        self.c.parse_enum(item, module, &mut self.c_outputs)
+    }
+
+    fn parse_struct(
+        &mut self,
+        item: &syn::ItemStruct,
+        module: &[String],
+        _outputs: &mut Outputs,
+    ) -> Result<(), Error> {
+        self.c.parse_struct(item, module, &mut self.c_outputs)?;
+
+        if item.attrs.iter().any(common::check_repr_c) {
+            let _ = self.structs.insert(item.ident.to_string());
+        }
+        Ok(())
+    }
+
+    /// Convert a Rust function declaration into a C function declaration, together with a
+    /// deleter if it frees a handle, or with wrappers if it takes a callback.
+    fn parse_fn(
+        &mut self,
+        item: &syn::ItemFn,
+        module: &[String],
+        _outputs: &mut Outputs,
+    ) -> Result<(), Error> {
+        self.c.parse_fn(item, module, &mut self.c_outputs)?;
+
+        let (no_mangle, docs) =
+            common::parse_attr(&item.attrs[..], common::check_no_mangle, |attr| {
+                common::retrieve_docstring(attr, "")
+            });
+        let extern_c = item.abi.clone().is_some_and(common::is_extern);
+        if !no_mangle || !extern_c || !item.decl.generics.params.is_empty() {
+            return Ok(());
+        }
+
+        let name = item.ident.to_string();
+        self.transform_free_fn(item, &name)?;
+        self.transform_callback_fn(item, &name, &docs)
+    }
+
+    fn finalise_output(&mut self, outputs: &mut Outputs) -> Result<(), Error> {
+        let declarations = self.c.amalgamate(&self.c_outputs)?;
+        let namespace = self.namespace.as_ref().unwrap_or(&self.lib_name);
+
+        let mut code = String::new();
+        for include in &[
+            "cstddef",
+            "cstdint",
+            "functional",
+            "future",
+            "memory",
+            "stdexcept",
+            "string",
+            "tuple",
+            "vector",
+        ] {
+            code.push_str(&format!("#include <{}>\n", include));
+        }
+        code.push_str(&format!(
+            "\nnamespace {} {{\n\nextern \"C\" {{\n\n{}}}\n\n",
+            namespace, declarations
+        ));
+
+        if !self.wrappers.is_empty() {
+            code.push_str(
+                "/// Error reported through an `FfiResult`.\n\
+                 class FfiError : public std::runtime_error {\n\
+                 public:\n\
+                 \x20   FfiError(int32_t code, char const* description)\n\
+                 \x20       : std::runtime_error(description ? description : \"\"), code(code) {}\n\
+                 \n\
+                 \x20   int32_t code;\n\
+                 };\n\n",
+            );
+        }
+        for deleter in self.deleters.values() {
+            code.push_str(deleter);
+        }
+        code.push_str(&self.wrappers);
+        code.push_str(&format!("}} // namespace {}\n", namespace));
+
+        let header_name = format!("{}.hpp", self.lib_name);
+        let code = self.c.guard_header(&code, &header_name);
+        let _ = outputs.insert(header_name, code);
+
+        Ok(())
+    }
+}
+
+/// Name of a function argument.
+fn arg_name(arg: &syn::ArgCaptured) -> String {
+    unwrap!(common::take_out_pat(&arg.pat)).ident.to_string()
+}
+
+/// Name of a callback argument, or a name based on its position if it's unnamed.
+fn barefn_arg_name(arg: &syn::BareFnArg, index: usize) -> String {
+    match arg.name {
+        Some((syn::BareFnArgName::Named(ref name), _)) => name.to_string(),
+        _ => format!("arg{}", index),
+    }
+}
+
+/// C++ type of a value, without a name.
+fn anon_type(ty: &syn::Type) -> Result<String, Error> {
+    Ok(lang_c::param_to_c(ty, "")?
+        .to_string()
+        .trim_end()
+        .to_string())
+}
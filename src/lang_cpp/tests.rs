@@ -0,0 +1,250 @@
+use crate::lang_cpp::LangCpp;
+use crate::test_utils::{self, fetch};
+use indoc::indoc;
+use unwrap::unwrap;
+
+#[test]
+fn handles_and_callbacks() {
+    let outputs = compile!(LangCpp::default(), {
+        #[repr(C)]
+        pub struct FfiResult {
+            pub error_code: i32,
+            pub description: *const c_char,
+        }
+
+        #[repr(C)]
+        pub struct Permissions {
+            pub read: bool,
+        }
+
+        #[no_mangle]
+        pub extern "C" fn app_free(app: *mut App) {}
+
+        /// Register an app.
+        #[no_mangle]
+        pub extern "C" fn app_registered(
+            app_id: *const c_char,
+            user_data: *mut c_void,
+            o_cb: extern "C" fn(user_data: *mut c_void, result: *const FfiResult, app: *mut App),
+        ) {
+        }
+
+        #[no_mangle]
+        pub extern "C" fn app_reconnect(
+            user_data: *mut c_void,
+            o_cb: extern "C" fn(user_data: *mut c_void, result: *const FfiResult),
+        ) {
+        }
+
+        #[no_mangle]
+        pub extern "C" fn app_info(
+            app: *const App,
+            user_data: *mut c_void,
+            o_cb: extern "C" fn(
+                user_data: *mut c_void,
+                result: *const FfiResult,
+                name: *const c_char,
+                perms: *const Permissions,
+                keys: *const u8,
+                keys_len: usize,
+            ),
+        ) {
+        }
+    });
+
+    let expected = indoc!(
+        "
+
+         #ifndef bindgen_backendhpp
+         #define bindgen_backendhpp
+
+         #include <cstddef>
+         #include <cstdint>
+         #include <functional>
+         #include <future>
+         #include <memory>
+         #include <stdexcept>
+         #include <string>
+         #include <tuple>
+         #include <vector>
+
+         namespace backend {
+
+         extern \"C\" {
+
+         typedef struct App App;
+
+         typedef struct FfiResult {
+             int32_t error_code;
+             char const* description;
+         } FfiResult;
+
+         typedef struct Permissions {
+             bool read;
+         } Permissions;
+
+         void app_free(App* app);
+
+         /// Register an app.
+         void app_registered(char const* app_id, void* user_data, void (*o_cb)(void* user_data, FfiResult const* result, App* app));
+
+         void app_reconnect(void* user_data, void (*o_cb)(void* user_data, FfiResult const* result));
+
+         void app_info(App const* app, void* user_data, void (*o_cb)(void* user_data, FfiResult const* result, char const* name, Permissions const* perms, uint8_t const* keys, uintptr_t keys_len));
+
+         }
+
+         /// Error reported through an `FfiResult`.
+         class FfiError : public std::runtime_error {
+         public:
+             FfiError(int32_t code, char const* description)
+                 : std::runtime_error(description ? description : \"\"), code(code) {}
+
+             int32_t code;
+         };
+
+         /// Frees `App` handles with `app_free`.
+         struct AppDeleter {
+             void operator()(App* ptr) const {
+                 app_free(ptr);
+             }
+         };
+
+         using AppPtr = std::unique_ptr<App, AppDeleter>;
+
+         using AppRegisteredCallback = std::function<void(FfiResult const*, App*)>;
+
+         /// Register an app.
+         inline void app_registered(char const* app_id, AppRegisteredCallback o_cb) {
+             auto user_data = new AppRegisteredCallback(std::move(o_cb));
+             app_registered(app_id, user_data, +[](void* user_data, FfiResult const* result, App* app) {
+                 std::unique_ptr<AppRegisteredCallback> o_cb(static_cast<AppRegisteredCallback*>(user_data));
+                 (*o_cb)(result, app);
+             });
+         }
+
+         /// Register an app.
+         inline std::future<App*> app_registered_async(char const* app_id) {
+             auto promise = std::make_shared<std::promise<App*>>();
+             app_registered(app_id, [promise](FfiResult const* result, App* app) {
+                 if (result->error_code != 0) {
+                     promise->set_exception(std::make_exception_ptr(
+                         FfiError(result->error_code, result->description)));
+                     return;
+                 }
+                 promise->set_value(app);
+             });
+             return promise->get_future();
+         }
+
+         using AppReconnectCallback = std::function<void(FfiResult const*)>;
+
+         inline void app_reconnect(AppReconnectCallback o_cb) {
+             auto user_data = new AppReconnectCallback(std::move(o_cb));
+             app_reconnect(user_data, +[](void* user_data, FfiResult const* result) {
+                 std::unique_ptr<AppReconnectCallback> o_cb(static_cast<AppReconnectCallback*>(user_data));
+                 (*o_cb)(result);
+             });
+         }
+
+         inline std::future<void> app_reconnect_async() {
+             auto promise = std::make_shared<std::promise<void>>();
+             app_reconnect([promise](FfiResult const* result) {
+                 if (result->error_code != 0) {
+                     promise->set_exception(std::make_exception_ptr(
+                         FfiError(result->error_code, result->description)));
+                     return;
+                 }
+                 promise->set_value();
+             });
+             return promise->get_future();
+         }
+
+         using AppInfoCallback = std::function<void(FfiResult const*, char const*, Permissions const*, uint8_t const*, uintptr_t)>;
+
+         inline void app_info(App const* app, AppInfoCallback o_cb) {
+             auto user_data = new AppInfoCallback(std::move(o_cb));
+             app_info(app, user_data, +[](void* user_data, FfiResult const* result, char const* name, Permissions const* perms, uint8_t const* keys, uintptr_t keys_len) {
+                 std::unique_ptr<AppInfoCallback> o_cb(static_cast<AppInfoCallback*>(user_data));
+                 (*o_cb)(result, name, perms, keys, keys_len);
+             });
+         }
+
+         inline std::future<std::tuple<std::string, Permissions, std::vector<uint8_t>>> app_info_async(App const* app) {
+             auto promise = std::make_shared<std::promise<std::tuple<std::string, Permissions, std::vector<uint8_t>>>>();
+             app_info(app, [promise](FfiResult const* result, char const* name, Permissions const* perms, uint8_t const* keys, uintptr_t keys_len) {
+                 if (result->error_code != 0) {
+                     promise->set_exception(std::make_exception_ptr(
+                         FfiError(result->error_code, result->description)));
+                     return;
+                 }
+                 promise->set_value(std::tuple<std::string, Permissions, std::vector<uint8_t>>(std::string(name ? name : \"\"), *perms, std::vector<uint8_t>(keys, keys + keys_len)));
+             });
+             return promise->get_future();
+         }
+
+         } // namespace backend
+
+
+         #endif
+        "
+    );
+
+    assert_multiline_eq!(fetch(&outputs, "backend.hpp"), expected);
+}
+
+#[test]
+fn repr_enums() {
+    let outputs = compile!(LangCpp::default(), {
+        #[repr(C)]
+        pub struct FfiResult {
+            pub error_code: i32,
+            pub description: *const c_char,
+        }
+
+        #[repr(u8)]
+        pub enum Priority {
+            Low = 1,
+            High,
+        }
+
+        #[repr(u8)]
+        pub enum Event {
+            Closed,
+            Data { bytes: *const u8, len: usize },
+        }
+
+        #[repr(C)]
+        pub enum Value {
+            Int(i32),
+            Bool(bool),
+        }
+
+        #[no_mangle]
+        pub extern "C" fn set_priority(
+            priority: Priority,
+            user_data: *mut c_void,
+            o_cb: extern "C" fn(user_data: *mut c_void, result: *const FfiResult, event: Event),
+        ) {
+        }
+    });
+
+    let actual = fetch(&outputs, "backend.hpp");
+    assert!(actual.contains(indoc!(
+        "
+         enum {
+             Priority_Low = 1,
+             Priority_High,
+         };
+         typedef uint8_t Priority;
+        "
+    )));
+    assert!(actual.contains("typedef uint8_t Event_Tag;\n"));
+    assert!(actual.contains("        Value_Int_Body int_;\n        Value_Bool_Body bool_;\n"));
+    test_utils::assert_compiles(
+        "c++",
+        &["-x", "c++", "-std=c++11"],
+        "repr_enums.hpp",
+        actual,
+    );
+}
@@ -0,0 +1,604 @@
+//! Functions for converting Rust types to Kotlin types.
+//!
+//! The JNI glue is generated by `LangJava`, as the JVM signatures of the Kotlin declarations are
+//! identical to the Java ones, and the `[java]` settings apply to both. `#[repr(C)]` structs become
+//! data classes, enums become enum classes (or sealed classes if they carry data) and the native
+//! functions are declared in the `NativeBindings` object. Functions taking a `user_data` pointer
+//! and a callback receiving an `FfiResult` also get `suspend` wrappers.
+
+#[cfg(test)]
+mod tests;
+
+use crate::common::{self, append_output, Lang, Outputs};
+use crate::java::futures::result_callback_params;
+use crate::java::types::{callback_name, rust_to_java, struct_to_java_classname};
+use crate::java::{
+    callback_params, native_fn_params, transform_struct_into_class_fields, Context, JavaClassField,
+    LangJava,
+};
+use crate::struct_field::transform_struct_fields;
+use crate::{Config, Error};
+use ::jni::signature::{JavaType, Primitive};
+use inflector::Inflector;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use syn::spanned::Spanned;
+use unwrap::unwrap;
+
+const INDENT: &str = "    ";
+
+const FFI_EXCEPTION: &str = "/**\n \
+                             * Error reported by the native library through an `FfiResult`.\n \
+                             */\n\
+                             class FfiException(val errorCode: Int, val description: String?) : \
+                             Exception(description)\n";
+
+pub struct LangKotlin {
+    /// Generates the JNI glue
+    java: LangJava,
+    /// Outputs of `LangJava`, of which only the JNI glue is kept
+    java_outputs: Outputs,
+    /// Sealed classes generated for enums carrying data, which have no default value
+    sealed_classes: HashSet<String>,
+}
+
+impl LangKotlin {
+    pub fn new(type_map: HashMap<&'static str, JavaType>) -> Self {
+        Self {
+            java: LangJava::new(type_map),
+            java_outputs: Outputs::default(),
+            sealed_classes: HashSet::new(),
+        }
+    }
+
+    /// Set the name of the native library. This also sets the name of the object holding the
+    /// `suspend` wrappers.
+    pub fn set_lib_name<T: Into<String>>(&mut self, name: T) {
+        self.java.set_lib_name(name);
+    }
+
+    /// Use the Java type `ty` for the Rust type `name`.
+    pub fn add_type_mapping<T: Into<String>>(&mut self, name: T, ty: JavaType) {
+        self.java.add_type_mapping(name, ty);
+    }
+
+    /// Set the package to put the `NativeBindings` object and the wrappers in.
+    pub fn set_namespace<T: Into<String>>(&mut self, namespace: T) {
+        self.java.set_namespace(namespace);
+    }
+
+    /// Set the package to put all classes in.
+    pub fn set_model_namespace<T: Into<String>>(&mut self, namespace: T) {
+        self.java.set_model_namespace(namespace);
+    }
+
+    fn context(&self) -> &Context {
+        self.java.context()
+    }
+
+    /// Returns the name of the object holding the `suspend` wrappers, which is suffixed like the
+    /// Java wrapper class so that it doesn't clash with a handle type named after the library.
+    fn wrappers_object(&self) -> String {
+        format!("{}Async", self.context().lib_name.to_pascal_case())
+    }
+
+    /// Returns the package declaration of a file, importing the model package into the other one.
+    fn package(&self, namespace: &str) -> String {
+        let context = self.context();
+        if namespace != context.namespace_model {
+            format!(
+                "package {}\n\nimport {}.*\n\n",
+                namespace, context.namespace_model
+            )
+        } else {
+            format!("package {}\n\n", namespace)
+        }
+    }
+
+    /// Returns the Kotlin type of a class field and the value it defaults to.
+    fn field_type_and_default(&self, field: &JavaClassField) -> (String, String) {
+        let ty = kotlin_type(&field.ty);
+        if let Some(ref constant) = field.enum_default {
+            return (ty, constant.clone());
+        }
+
+        let default = match field.ty {
+            JavaType::Primitive(Primitive::Boolean) => "false".to_string(),
+            JavaType::Primitive(Primitive::Long) => "0L".to_string(),
+            JavaType::Primitive(Primitive::Float) => "0f".to_string(),
+            JavaType::Primitive(Primitive::Double) => "0.0".to_string(),
+            JavaType::Primitive(Primitive::Char) => "'\\u0000'".to_string(),
+            JavaType::Primitive(_) => "0".to_string(),
+            JavaType::Array(ref elem) => match **elem {
+                JavaType::Primitive(_) => format!("{}(0)", ty),
+                _ => "emptyArray()".to_string(),
+            },
+            JavaType::Object(_) if ty == "String" => "\"\"".to_string(),
+            JavaType::Object(_) if self.sealed_classes.contains(&ty) => {
+                return (format!("{}?", ty), "null".to_string());
+            }
+            _ => format!("{}()", ty),
+        };
+        (ty, default)
+    }
+
+    /// Generates a data class, which can be created with a no-arg constructor from JNI as all
+    /// its properties have default values.
+    fn data_class(&self, name: &str, fields: &[JavaClassField], supertype: &str) -> String {
+        if fields.is_empty() {
+            return format!("class {}{}\n", name, supertype);
+        }
+
+        let properties: Vec<_> = fields
+            .iter()
+            .map(|field| {
+                let (ty, default) = self.field_type_and_default(field);
+                format!("{}val {}: {} = {}", INDENT, field.name, ty, default)
+            })
+            .collect();
+        format!(
+            "data class {}(\n{}\n){}\n",
+            name,
+            properties.join(",\n"),
+            supertype
+        )
+    }
+
+    /// Generates an enum class for an enum without data, whose constants hold the discriminants
+    /// of the variants (`value`) as expected by the JNI glue.
+    fn transform_enum(&self, item: &syn::ItemEnum, class_name: &str) -> Result<String, Error> {
+        let mut constants = Vec::new();
+        let values = common::enum_variant_values(item)?;
+        for (variant, value) in item.variants.iter().zip(values) {
+            let (_, docs) = common::parse_attr(
+                &variant.attrs,
+                |_| true,
+                |attr| common::retrieve_docstring(attr, ""),
+            );
+            constants.push(format!(
+                "{}{}{}({})",
+                kdoc(&docs, INDENT),
+                INDENT,
+                variant.ident.to_string().to_screaming_snake_case(),
+                value
+            ));
+        }
+
+        Ok(format!(
+            "enum class {name}(val value: Int) {{\n\
+             {constants};\n\
+             \n\
+             {i}companion object {{\n\
+             {i}{i}@JvmStatic\n\
+             {i}{i}fun fromValue(value: Int): {name} =\n\
+             {i}{i}{i}values().firstOrNull {{ it.value == value }}\n\
+             {i}{i}{i}{i}?: throw IllegalArgumentException(\"Invalid {name} value: $value\")\n\
+             {i}}}\n\
+             }}\n",
+            name = class_name,
+            constants = constants.join(",\n"),
+            i = INDENT,
+        ))
+    }
+
+    /// Generates a sealed class for an enum carrying data, with a nested class for every variant.
+    fn transform_sealed_class(
+        &self,
+        item: &syn::ItemEnum,
+        class_name: &str,
+    ) -> Result<String, Error> {
+        let mut variants = Vec::new();
+        for variant in &item.variants {
+            let (_, docs) = common::parse_attr(
+                &variant.attrs,
+                |_| true,
+                |attr| common::retrieve_docstring(attr, ""),
+            );
+            // Fields of tuple variants are named after their position.
+            let fields: Vec<_> = variant
+                .fields
+                .iter()
+                .enumerate()
+                .map(|(index, field)| {
+                    let mut field = field.clone();
+                    if field.ident.is_none() {
+                        field.ident =
+                            Some(syn::Ident::new(&format!("item{}", index), field.span()));
+                    }
+                    field
+                })
+                .collect();
+            let class_fields = transform_struct_into_class_fields(
+                &transform_struct_fields(&fields),
+                self.context(),
+            )?;
+
+            let class = self.data_class(
+                &struct_to_java_classname(variant.ident.to_string()),
+                &class_fields,
+                &format!(" : {}()", class_name),
+            );
+            variants.push(indent(&format!("{}{}", kdoc(&docs, ""), class)));
+        }
+
+        Ok(format!(
+            "sealed class {} {{\n{}}}\n",
+            class_name,
+            variants.join("\n")
+        ))
+    }
+
+    /// Generates the declaration of a callback interface, which the JNI glue calls through its
+    /// `call` method.
+    fn transform_callback(&self, bare_fn: &syn::TypeBareFn, name: &str) -> Result<String, Error> {
+        let params = callback_params(bare_fn, self.context())?
+            .into_iter()
+            .map(|(ty, name)| format!("{}: {}", name, nullable_type(&ty)))
+            .collect::<Vec<_>>();
+
+        Ok(format!(
+            "{}fun interface {} {{\n{}fun call({})\n}}\n",
+            self.package(&self.context().namespace_model),
+            name,
+            INDENT,
+            params.join(", ")
+        ))
+    }
+
+    /// Generates a `suspend` function calling the native function `java_name` and resuming once
+    /// the callback is called, or `None` if the callback doesn't receive an `FfiResult` followed
+    /// by at most one value.
+    fn transform_suspend_fn(
+        &self,
+        java_name: &str,
+        docs: &str,
+        params: &[(String, String)],
+        callback_index: usize,
+        callback: &syn::TypeBareFn,
+    ) -> Result<Option<String>, Error> {
+        let cb_params = match result_callback_params(callback, self.context())? {
+            Some(cb_params) => cb_params,
+            None => return Ok(None),
+        };
+
+        let (value_ty, value) = match cb_params.get(1) {
+            Some((ty, name)) => (nullable_type(ty), name.clone()),
+            None => ("Unit".to_string(), "Unit".to_string()),
+        };
+        let lambda_params: Vec<_> = cb_params.iter().map(|(_, name)| name.as_str()).collect();
+
+        let fn_params: Vec<_> = params
+            .iter()
+            .enumerate()
+            .filter(|&(index, _)| index != callback_index)
+            .map(|(_, (name, ty))| format!("{}: {}", name, ty))
+            .collect();
+        let args_before: Vec<_> = params[..callback_index]
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        let args_after: Vec<_> = params[callback_index + 1..]
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+
+        // The callback is passed as a trailing lambda when it's the last parameter.
+        let (call_start, call_end) = if !args_after.is_empty() {
+            let mut args_before = args_before;
+            args_before.push("{");
+            (
+                format!("({}", args_before.join(", ")),
+                format!("}}, {})", args_after.join(", ")),
+            )
+        } else if !args_before.is_empty() {
+            (format!("({}) {{", args_before.join(", ")), "}".to_string())
+        } else {
+            (" {".to_string(), "}".to_string())
+        };
+
+        Ok(Some(format!(
+            "{docs}suspend fun {name}({params}): {ty} = suspendCancellableCoroutine {{ continuation ->\n\
+             {i}NativeBindings.{name}{call_start} {lambda_params} ->\n\
+             {i}{i}if (result != null && result.errorCode != 0) {{\n\
+             {i}{i}{i}continuation.resumeWithException(\
+             FfiException(result.errorCode, result.description))\n\
+             {i}{i}}} else {{\n\
+             {i}{i}{i}continuation.resume({value})\n\
+             {i}{i}}}\n\
+             {i}{call_end}\n\
+             }}\n\n",
+            docs = kdoc(docs, ""),
+            name = java_name,
+            params = fn_params.join(", "),
+            ty = value_ty,
+            call_start = call_start,
+            lambda_params = lambda_params.join(", "),
+            value = value,
+            call_end = call_end,
+            i = INDENT,
+        )))
+    }
+}
+
+impl Lang for LangKotlin {
+    fn apply_config(&mut self, config: &Config) {
+        self.java.apply_config(config);
+    }
+
+    fn declare_enum(&mut self, item: &syn::ItemEnum) {
+        self.java.declare_enum(item);
+    }
+
+    fn parse_const(
+        &mut self,
+        item: &syn::ItemConst,
+        module: &[String],
+        _outputs: &mut Outputs,
+    ) -> Result<(), Error> {
+        self.java.parse_const(item, module, &mut self.java_outputs)
+    }
+
+    fn parse_ty(
+        &mut self,
+        item: &syn::ItemType,
+        module: &[String],
+        _outputs: &mut Outputs,
+    ) -> Result<(), Error> {
+        self.java.parse_ty(item, module, &mut self.java_outputs)
+    }
+
+    /// Converts a Rust enum into an enum class, or into a sealed class if it carries data.
+    ///
+    /// Enums have to be declared before the items using them.
+    fn parse_enum(
+        &mut self,
+        item: &syn::ItemEnum,
+        module: &[String],
+        outputs: &mut Outputs,
+    ) -> Result<(), Error> {
+        self.java.parse_enum(item, module, &mut self.java_outputs)?;
+
+        let name = item.ident.to_string();
+        if self.java.is_ignored(&name) || !common::enum_repr(&item.attrs).is_defined() {
+            return Ok(());
+        }
+        let (_, docs) = common::parse_attr(
+            &item.attrs,
+            |_| true,
+            |attr| common::retrieve_docstring(attr, ""),
+        );
+
+        let class_name = struct_to_java_classname(&name);
+        let class = if common::has_data_variants(item) {
+            let class = self.transform_sealed_class(item, &class_name)?;
+            let _ = self.sealed_classes.insert(class_name.clone());
+            class
+        } else {
+            self.transform_enum(item, &class_name)?
+        };
+
+        let mut buffer = self.package(&self.context().namespace_model);
+        buffer.push_str(&kdoc(&docs, ""));
+        buffer.push_str(&class);
+        outputs.insert(format!("{}.kt", class_name), buffer);
+
+        Ok(())
+    }
+
+    /// Converts a `#[repr(C)]` Rust struct into a data class.
+    fn parse_struct(
+        &mut self,
+        item: &syn::ItemStruct,
+        module: &[String],
+        outputs: &mut Outputs,
+    ) -> Result<(), Error> {
+        self.java
+            .parse_struct(item, module, &mut self.java_outputs)?;
+
+        let name = item.ident.to_string();
+        let (repr_c, docs) = common::parse_attr(&item.attrs, common::check_repr_c, |attr| {
+            common::retrieve_docstring(attr, "")
+        });
+        if self.java.is_ignored(&name) || !repr_c {
+            return Ok(());
+        }
+
+        let fields: Vec<_> = item.fields.iter().cloned().collect();
+        let class_fields =
+            transform_struct_into_class_fields(&transform_struct_fields(&fields), self.context())?;
+        let class_name = struct_to_java_classname(&name);
+
+        let mut buffer = self.package(&self.context().namespace_model);
+        buffer.push_str(&kdoc(&docs, ""));
+        buffer.push_str(&self.data_class(&class_name, &class_fields, ""));
+        outputs.insert(format!("{}.kt", class_name), buffer);
+
+        Ok(())
+    }
+
+    /// Declares a native function in the `NativeBindings` object, together with the interfaces
+    /// of its callbacks and its `suspend` wrapper.
+    fn parse_fn(
+        &mut self,
+        item: &syn::ItemFn,
+        module: &[String],
+        outputs: &mut Outputs,
+    ) -> Result<(), Error> {
+        self.java.parse_fn(item, module, &mut self.java_outputs)?;
+
+        let name = item.ident.to_string();
+        let (no_mangle, docs) = common::parse_attr(&item.attrs, common::check_no_mangle, |attr| {
+            common::retrieve_docstring(attr, "")
+        });
+        // Functions which `LangJava` skipped have no JNI glue.
+        if self.java.is_ignored(&name)
+            || !no_mangle
+            || item.abi.is_none()
+            || !common::is_extern(unwrap!(item.abi.clone()))
+        {
+            return Ok(());
+        }
+
+        let mut params = Vec::new();
+        let mut callbacks = Vec::new();
+        for param in native_fn_params(&item.decl, self.context())? {
+            params.push((param.name, kotlin_type(&param.ty)));
+
+            if let syn::Type::BareFn(ref bare_fn) = *param.rust_ty {
+                callbacks.push((params.len() - 1, bare_fn));
+
+                let inputs: Vec<_> = bare_fn.inputs.iter().cloned().collect();
+                let cb_name = callback_name(&inputs, self.context())?;
+                if let Entry::Vacant(entry) = outputs.entry(format!("{}.kt", cb_name)) {
+                    entry.insert(self.transform_callback(bare_fn, &cb_name)?);
+                }
+            }
+        }
+
+        let return_type = match item.decl.output {
+            syn::ReturnType::Default => String::new(),
+            syn::ReturnType::Type(_, ref ty) => {
+                format!(": {}", nullable_type(&rust_to_java(ty, self.context())?))
+            }
+        };
+        let java_name = name.to_camel_case();
+        let params_str: Vec<_> = params
+            .iter()
+            .map(|(name, ty)| format!("{}: {}", name, ty))
+            .collect();
+
+        append_output(
+            format!(
+                "{}@JvmStatic\nexternal fun {}({}){}\n\n",
+                kdoc(&docs, ""),
+                java_name,
+                params_str.join(", "),
+                return_type
+            ),
+            "NativeBindings.kt",
+            outputs,
+        );
+
+        let has_user_data =
+            item.decl.inputs.iter().any(|arg| {
+                common::is_user_data_arg(unwrap!(common::transform_fnarg_to_argcap(arg)))
+            });
+        if let (true, [(index, callback)]) = (has_user_data, callbacks.as_slice()) {
+            if let Some(wrapper) =
+                self.transform_suspend_fn(&java_name, &docs, &params, *index, callback)?
+            {
+                append_output(wrapper, &format!("{}.kt", self.wrappers_object()), outputs);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finalise_output(&mut self, outputs: &mut Outputs) -> Result<(), Error> {
+        self.java.finalise_output(&mut self.java_outputs)?;
+        if let Some(jni) = self.java_outputs.remove("jni.rs") {
+            outputs.insert("jni.rs".to_string(), jni);
+        }
+
+        let namespace = self.context().namespace.clone();
+        if let Some(functions) = outputs.get_mut("NativeBindings.kt") {
+            *functions = format!(
+                "{}object NativeBindings {{\n{}}}\n",
+                self.package(&namespace),
+                indent(functions.trim_end()),
+            );
+        }
+
+        let wrappers_object = self.wrappers_object();
+        let wrappers_file = format!("{}.kt", wrappers_object);
+        if let Some(wrappers) = outputs.get_mut(&wrappers_file) {
+            let package = self.package(&namespace);
+            let (package, imports) = package.split_at(package.find("\n\n").unwrap_or(0) + 2);
+            *wrappers = format!(
+                "{}import kotlin.coroutines.resume\n\
+                 import kotlin.coroutines.resumeWithException\n\
+                 import kotlinx.coroutines.suspendCancellableCoroutine\n\
+                 {}{}object {} {{\n{}}}\n",
+                package,
+                imports,
+                if imports.is_empty() { "\n" } else { "" },
+                wrappers_object,
+                indent(wrappers.trim_end()),
+            );
+            outputs.insert(
+                "FfiException.kt".to_string(),
+                format!("package {}\n\n{}", namespace, FFI_EXCEPTION),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Converts a Java type into the Kotlin type it's declared as.
+fn kotlin_type(ty: &JavaType) -> String {
+    match *ty {
+        JavaType::Primitive(primitive) => primitive_type(primitive).to_string(),
+        JavaType::Array(ref elem) => match **elem {
+            JavaType::Primitive(primitive) => format!("{}Array", primitive_type(primitive)),
+            ref elem => format!("Array<{}>", kotlin_type(elem)),
+        },
+        JavaType::Object(ref obj) => match obj.as_str() {
+            "java/lang/String" => "String".to_string(),
+            obj => unwrap!(obj.rsplit('/').next()).to_string(),
+        },
+        JavaType::Method(..) => "Any".to_string(),
+    }
+}
+
+/// Converts a Java type into a Kotlin type, which can be `null` unless it's a primitive.
+fn nullable_type(ty: &JavaType) -> String {
+    match *ty {
+        JavaType::Primitive(_) => kotlin_type(ty),
+        _ => format!("{}?", kotlin_type(ty)),
+    }
+}
+
+fn primitive_type(ty: Primitive) -> &'static str {
+    match ty {
+        Primitive::Boolean => "Boolean",
+        Primitive::Byte => "Byte",
+        Primitive::Char => "Char",
+        Primitive::Double => "Double",
+        Primitive::Float => "Float",
+        Primitive::Int => "Int",
+        Primitive::Long => "Long",
+        Primitive::Short => "Short",
+        Primitive::Void => "Unit",
+    }
+}
+
+/// Converts doc comments (`/// ...` lines) into a KDoc block indented by `indent`.
+fn kdoc(docs: &str, indent: &str) -> String {
+    if docs.is_empty() {
+        return String::new();
+    }
+
+    let mut buffer = format!("{}/**\n", indent);
+    for line in docs.lines() {
+        buffer.push_str(&format!(
+            "{} *{}\n",
+            indent,
+            line.trim_start().trim_start_matches("///")
+        ));
+    }
+    buffer.push_str(&format!("{} */\n", indent));
+    buffer
+}
+
+/// Indents the non-empty lines of `code` by one level.
+fn indent(code: &str) -> String {
+    code.lines().fold(String::new(), |mut output, line| {
+        if !line.is_empty() {
+            output.push_str(INDENT);
+        }
+        output.push_str(line);
+        output.push('\n');
+        output
+    })
+}
@@ -0,0 +1,270 @@
+use crate::lang_kotlin::LangKotlin;
+use indoc::indoc;
+use std::collections::HashMap;
+use unwrap::unwrap;
+
+fn lang() -> LangKotlin {
+    let mut lang = LangKotlin::new(HashMap::new());
+    lang.set_lib_name("safe_app");
+    lang
+}
+
+#[test]
+fn data_classes_and_suspend_functions() {
+    let outputs = compile!(lang(), {
+        /// Priority of a task.
+        #[repr(C)]
+        pub enum Priority {
+            /// Runs last.
+            Low = 1,
+            High,
+        }
+
+        #[repr(C)]
+        pub enum Shape {
+            Point,
+            Circle(f64),
+        }
+
+        /// A scheduled task.
+        #[repr(C)]
+        pub struct Task {
+            pub id: u64,
+            pub name: *const c_char,
+            pub done: bool,
+            pub priority: Priority,
+            pub shape: Shape,
+        }
+
+        /// Returns the version of the app.
+        #[no_mangle]
+        pub extern "C" fn app_version(
+            app: *const App,
+            user_data: *mut c_void,
+            o_cb: extern "C" fn(user_data: *mut c_void, result: *const FfiResult, version: u64),
+        ) {
+        }
+
+        #[no_mangle]
+        pub extern "C" fn app_reconnect(
+            user_data: *mut c_void,
+            o_cb: extern "C" fn(user_data: *mut c_void, result: *const FfiResult),
+            force: bool,
+        ) {
+        }
+
+        #[no_mangle]
+        pub extern "C" fn task_get(
+            user_data: *mut c_void,
+            o_cb: extern "C" fn(
+                user_data: *mut c_void,
+                result: *const FfiResult,
+                task: *const Task,
+            ),
+        ) {
+        }
+    });
+
+    assert_multiline_eq!(
+        &outputs["Task.kt"],
+        indoc!(
+            "
+            package net.maidsafe.model
+
+            /**
+             * A scheduled task.
+             */
+            data class Task(
+                val id: Long = 0L,
+                val name: String = \"\",
+                val done: Boolean = false,
+                val priority: Priority = Priority.LOW,
+                val shape: Shape? = null
+            )
+            "
+        )
+    );
+    assert_multiline_eq!(
+        &outputs["Priority.kt"],
+        indoc!(
+            "
+            package net.maidsafe.model
+
+            /**
+             * Priority of a task.
+             */
+            enum class Priority(val value: Int) {
+                /**
+                 * Runs last.
+                 */
+                LOW(1),
+                HIGH(2);
+
+                companion object {
+                    @JvmStatic
+                    fun fromValue(value: Int): Priority =
+                        values().firstOrNull { it.value == value }
+                            ?: throw IllegalArgumentException(\"Invalid Priority value: $value\")
+                }
+            }
+            "
+        )
+    );
+    assert_multiline_eq!(
+        &outputs["Shape.kt"],
+        indoc!(
+            "
+            package net.maidsafe.model
+
+            sealed class Shape {
+                class Point : Shape()
+
+                data class Circle(
+                    val item0: Double = 0.0
+                ) : Shape()
+            }
+            "
+        )
+    );
+    assert_multiline_eq!(
+        &outputs["CallbackResultLong.kt"],
+        indoc!(
+            "
+            package net.maidsafe.model
+
+            fun interface CallbackResultLong {
+                fun call(result: FfiResult?, version: Long)
+            }
+            "
+        )
+    );
+    assert_multiline_eq!(
+        &outputs["NativeBindings.kt"],
+        indoc!(
+            "
+            package net.maidsafe.bindings
+
+            import net.maidsafe.model.*
+
+            object NativeBindings {
+                /**
+                 * Returns the version of the app.
+                 */
+                @JvmStatic
+                external fun appVersion(app: App, oCb: CallbackResultLong)
+
+                @JvmStatic
+                external fun appReconnect(oCb: CallbackResult, force: Boolean)
+
+                @JvmStatic
+                external fun taskGet(oCb: CallbackResultTask)
+            }
+            "
+        )
+    );
+    assert_multiline_eq!(
+        &outputs["SafeAppAsync.kt"],
+        indoc!(
+            "
+            package net.maidsafe.bindings
+
+            import kotlin.coroutines.resume
+            import kotlin.coroutines.resumeWithException
+            import kotlinx.coroutines.suspendCancellableCoroutine
+            import net.maidsafe.model.*
+
+            object SafeAppAsync {
+                /**
+                 * Returns the version of the app.
+                 */
+                suspend fun appVersion(app: App): Long = suspendCancellableCoroutine { continuation ->
+                    NativeBindings.appVersion(app) { result, version ->
+                        if (result != null && result.errorCode != 0) {
+                            continuation.resumeWithException(FfiException(result.errorCode, result.description))
+                        } else {
+                            continuation.resume(version)
+                        }
+                    }
+                }
+
+                suspend fun appReconnect(force: Boolean): Unit = suspendCancellableCoroutine { continuation ->
+                    NativeBindings.appReconnect({ result ->
+                        if (result != null && result.errorCode != 0) {
+                            continuation.resumeWithException(FfiException(result.errorCode, result.description))
+                        } else {
+                            continuation.resume(Unit)
+                        }
+                    }, force)
+                }
+
+                suspend fun taskGet(): Task? = suspendCancellableCoroutine { continuation ->
+                    NativeBindings.taskGet { result, task ->
+                        if (result != null && result.errorCode != 0) {
+                            continuation.resumeWithException(FfiException(result.errorCode, result.description))
+                        } else {
+                            continuation.resume(task)
+                        }
+                    }
+                }
+            }
+            "
+        )
+    );
+    assert!(outputs["FfiException.kt"].contains("class FfiException(val errorCode: Int"));
+    eprintln!("{}", outputs["jni.rs"]);
+    assert!(outputs["jni.rs"].contains("Java_net_maidsafe_bindings_NativeBindings_appVersion"));
+}
+
+#[test]
+fn enum_discriminants() {
+    let outputs = compile!(lang(), {
+        #[repr(i8)]
+        pub enum Sign {
+            Negative = -1,
+            Zero,
+            Positive,
+        }
+
+        #[no_mangle]
+        pub extern "C" fn set_sign(sign: Sign) {}
+    });
+    assert!(outputs["Sign.kt"].contains("    NEGATIVE(-1),\n    ZERO(0),\n    POSITIVE(1);\n"));
+
+    let errors = unwrap!(try_compile!(lang(), {
+        #[repr(C)]
+        pub enum Flags {
+            None = 0,
+            All = ALL_FLAGS,
+        }
+    })
+    .err());
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].message,
+        "bindgen cannot handle the discriminant of `Flags::All` (use an integer literal)"
+    );
+}
+
+#[test]
+fn enums_used_before_their_declaration() {
+    let outputs = compile!(lang(), {
+        #[repr(C)]
+        pub struct Task {
+            pub priority: Priority,
+        }
+
+        #[no_mangle]
+        pub extern "C" fn set_priority(priority: Priority) {}
+
+        #[repr(C)]
+        pub enum Priority {
+            Low,
+            High,
+        }
+    });
+
+    assert!(outputs["Task.kt"].contains("    val priority: Priority = Priority.LOW\n"));
+    assert!(outputs["NativeBindings.kt"].contains("fun setPriority(priority: Priority)"));
+    assert!(outputs["jni.rs"]
+        .contains("let priority = jni_unwrap!(Priority::from_java(&env, priority));"));
+}
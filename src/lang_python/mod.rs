@@ -0,0 +1,891 @@
+//! Functions for converting Rust types to a Python module using `ctypes`.
+//!
+//! The declarations reuse the intermediate representation of the C# backend. `#[repr(C)]` structs
+//! become `ctypes.Structure` subclasses, enums become `enum.IntEnum`s and callback parameters get
+//! `ctypes.CFUNCTYPE` types. Functions taking `user_data` and a callback which receives the
+//! `user_data` and an `FfiResult` also get `async` wrappers, which complete an `asyncio` future
+//! with the values passed to the callback or raise an `FfiError`.
+
+#[cfg(test)]
+mod tests;
+
+use crate::common::{self, Lang, Outputs};
+use crate::csharp::intermediate::*;
+use crate::csharp::resolve_alias;
+use crate::{Config, Error, Level};
+use inflector::Inflector;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use syn::spanned::Spanned;
+use unwrap::unwrap;
+
+/// Helpers for completing futures from callbacks, added when there are `async` wrappers.
+const RUNTIME: &str = include_str!("../../resources/python/runtime.py");
+
+/// Declaration of `FfiResult`, added when the bindings refer to it but don't define it.
+const FFI_RESULT: &str = "class FfiResult(ctypes.Structure):\n    \
+                          _fields_ = [\n        \
+                          (\"error_code\", ctypes.c_int32),\n        \
+                          (\"description\", ctypes.c_char_p),\n    \
+                          ]\n";
+
+const KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+pub struct LangPython {
+    lib_name: String,
+    consts: Vec<Snippet<Const>>,
+    enums: Vec<Snippet<Enum>>,
+    structs: Vec<Snippet<Struct>>,
+    functions: Vec<Snippet<Function>>,
+    aliases: HashMap<String, Type>,
+}
+
+/// A top-level piece of the module. Classes and functions are surrounded by two blank lines.
+struct Block {
+    code: String,
+    is_def: bool,
+}
+
+impl LangPython {
+    pub fn new() -> Self {
+        Self {
+            lib_name: "backend".to_owned(),
+            consts: Vec::new(),
+            enums: Vec::new(),
+            structs: Vec::new(),
+            functions: Vec::new(),
+            aliases: Default::default(),
+        }
+    }
+
+    /// Set the name of the native library. This is also the name of the generated module.
+    pub fn set_lib_name<T: Into<String>>(&mut self, name: T) {
+        self.lib_name = name.into();
+    }
+
+    fn resolve_aliases(&mut self) {
+        for snippet in &mut self.consts {
+            resolve_alias(&self.aliases, &mut snippet.item.ty);
+        }
+
+        for snippet in &mut self.structs {
+            for field in &mut snippet.item.fields {
+                resolve_alias(&self.aliases, &mut field.ty);
+            }
+        }
+
+        for snippet in &mut self.functions {
+            resolve_alias(&self.aliases, &mut snippet.item.output);
+
+            for &mut (_, ref mut ty) in &mut snippet.item.inputs {
+                resolve_alias(&self.aliases, ty)
+            }
+        }
+    }
+
+    fn is_struct(&self, name: &str) -> bool {
+        self.structs.iter().any(|snippet| snippet.name == name)
+    }
+
+    fn find_enum(&self, name: &str) -> Option<&Enum> {
+        self.enums
+            .iter()
+            .find(|snippet| snippet.name == name)
+            .map(|snippet| &snippet.item)
+    }
+
+    /// Returns the `ctypes` type of a Rust type. Dynamic arrays become pointers to their first
+    /// element, the length is passed separately.
+    fn ctype(&self, ty: &Type) -> String {
+        match *ty {
+            Type::Unit => "None".to_owned(),
+            Type::Bool => "ctypes.c_bool".to_owned(),
+            Type::Char => "ctypes.c_uint32".to_owned(),
+            Type::CChar => "ctypes.c_char".to_owned(),
+            Type::F32 => "ctypes.c_float".to_owned(),
+            Type::F64 => "ctypes.c_double".to_owned(),
+            Type::I8 => "ctypes.c_int8".to_owned(),
+            Type::I16 => "ctypes.c_int16".to_owned(),
+            Type::I32 => "ctypes.c_int32".to_owned(),
+            Type::I64 => "ctypes.c_int64".to_owned(),
+            Type::ISize => "ctypes.c_ssize_t".to_owned(),
+            Type::U8 => "ctypes.c_uint8".to_owned(),
+            Type::U16 => "ctypes.c_uint16".to_owned(),
+            Type::U32 => "ctypes.c_uint32".to_owned(),
+            Type::U64 => "ctypes.c_uint64".to_owned(),
+            Type::USize => "ctypes.c_size_t".to_owned(),
+            Type::String => "ctypes.c_char_p".to_owned(),
+            Type::Pointer(ref ty) => match **ty {
+                Type::Unit => "ctypes.c_void_p".to_owned(),
+                Type::Function(..) => self.ctype(ty),
+                _ => format!("ctypes.POINTER({})", self.ctype(ty)),
+            },
+            Type::Array(ref ty, ArraySize::Lit(len)) => format!("{} * {}", self.ctype(ty), len),
+            Type::Array(ref ty, ArraySize::Const(ref len)) => {
+                format!("{} * {}", self.ctype(ty), len)
+            }
+            Type::Array(ref ty, ArraySize::Dynamic) => {
+                format!("ctypes.POINTER({})", self.ctype(ty))
+            }
+            Type::Function(ref fun) => format!(
+                "ctypes.CFUNCTYPE({})",
+                Some(self.ctype(&fun.output))
+                    .into_iter()
+                    .chain(self.param_ctypes(&fun.inputs))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Type::User(ref name) => match self.find_enum(name) {
+                Some(&Enum {
+                    repr: Some(ref repr),
+                    ..
+                }) => self.ctype(repr),
+                Some(_) => "ctypes.c_int".to_owned(),
+                None => name.clone(),
+            },
+        }
+    }
+
+    /// Returns the `ctypes` types of the parameters, with the lengths of dynamic arrays.
+    fn param_ctypes(&self, inputs: &[(String, Type)]) -> Vec<String> {
+        let mut ctypes = Vec::new();
+        for (_, ty) in inputs {
+            ctypes.push(self.ctype(ty));
+            if ty.is_dynamic_array() {
+                ctypes.push("ctypes.c_size_t".to_owned());
+            }
+        }
+        ctypes
+    }
+
+    /// Returns the Python value of a constant. Values which become fields of structures are
+    /// converted to `ctypes` values.
+    fn const_value(&self, ty: Option<&Type>, value: &ConstValue, in_struct: bool) -> String {
+        match *value {
+            ConstValue::Bool(true) => "True".to_owned(),
+            ConstValue::Bool(false) => "False".to_owned(),
+            ConstValue::Char(value) if in_struct => (value as u32).to_string(),
+            ConstValue::Char(value) => string_literal(&value.to_string(), false),
+            ConstValue::Float(ref value) => value
+                .trim_end_matches("f32")
+                .trim_end_matches("f64")
+                .to_owned(),
+            ConstValue::Int(value) => value.to_string(),
+            ConstValue::String(ref value) => string_literal(value, in_struct),
+            ConstValue::Array(ref elements) => {
+                let elem_ty = match ty {
+                    Some(Type::Array(elem_ty, _)) => Some(&**elem_ty),
+                    _ => None,
+                };
+                let elements = elements
+                    .iter()
+                    .map(|element| self.const_value(elem_ty, element, in_struct))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                match ty {
+                    Some(ty) if in_struct => format!("({})({})", self.ctype(ty), elements),
+                    _ => format!("[{}]", elements),
+                }
+            }
+            ConstValue::Struct(ref name, ref fields) => {
+                let struct_fields = self
+                    .structs
+                    .iter()
+                    .find(|snippet| snippet.name == *name)
+                    .map(|snippet| &snippet.item.fields[..])
+                    .unwrap_or(&[]);
+                let fields = fields
+                    .iter()
+                    .map(|(field, value)| {
+                        let ty = struct_fields
+                            .iter()
+                            .find(|struct_field| struct_field.name == *field)
+                            .map(|struct_field| &struct_field.ty);
+                        format!("{}={}", field, self.const_value(ty, value, true))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{}({})", name, fields)
+            }
+        }
+    }
+
+    /// Returns the user-defined types which are referred to, but neither structs nor enums.
+    fn opaque_types(&self) -> BTreeSet<String> {
+        let mut names = BTreeSet::new();
+        for snippet in &self.structs {
+            for field in &snippet.item.fields {
+                user_types(&field.ty, &mut names);
+            }
+        }
+        for snippet in &self.functions {
+            user_types(&snippet.item.output, &mut names);
+            for (_, ty) in &snippet.item.inputs {
+                user_types(ty, &mut names);
+            }
+        }
+        names
+            .into_iter()
+            .filter(|name| !self.is_struct(name) && self.find_enum(name).is_none())
+            .collect()
+    }
+
+    /// Returns the structs in an order in which the fields of a struct only contain values of
+    /// the structs before it.
+    fn ordered_structs(&self) -> Vec<&Snippet<Struct>> {
+        fn visit<'a>(
+            lang: &'a LangPython,
+            snippet: &'a Snippet<Struct>,
+            visited: &mut HashSet<&'a str>,
+            order: &mut Vec<&'a Snippet<Struct>>,
+        ) {
+            if !visited.insert(&snippet.name) {
+                return;
+            }
+            for field in &snippet.item.fields {
+                let mut ty = &field.ty;
+                while let Type::Array(ref elem, _) = *ty {
+                    ty = elem;
+                }
+                if let Type::User(ref name) = *ty {
+                    if let Some(dep) = lang.structs.iter().find(|dep| dep.name == *name) {
+                        visit(lang, dep, visited, order);
+                    }
+                }
+            }
+            order.push(snippet);
+        }
+
+        let mut visited = HashSet::new();
+        let mut order = Vec::new();
+        for snippet in &self.structs {
+            visit(self, snippet, &mut visited, &mut order);
+        }
+        order
+    }
+
+    /// Returns the name of the `CFUNCTYPE` of a callback parameter.
+    fn callback_type_name(fun_name: &str, fun: &Function, param: &str) -> String {
+        let callbacks = fun
+            .inputs
+            .iter()
+            .filter(|(_, ty)| matches!(*ty, Type::Function(..)))
+            .count();
+        if callbacks == 1 {
+            format!("{}Callback", fun_name.to_pascal_case())
+        } else {
+            format!("{}{}", fun_name.to_pascal_case(), param.to_pascal_case())
+        }
+    }
+
+    fn emit_enum(&self, name: &str, docs: &str, item: &Enum) -> String {
+        let mut code = format!("class {}(enum.IntEnum):\n", name);
+        if !docs.is_empty() {
+            code.push_str(&docstring(docs, "    "));
+            code.push('\n');
+        }
+        let mut value = 0;
+        for variant in &item.variants {
+            value = variant.value.unwrap_or(value);
+            code.push_str(&comment(&variant.docs, "    "));
+            code.push_str(&format!("    {} = {}\n", variant.name, value));
+            value += 1;
+        }
+        code
+    }
+
+    fn emit_fields(&self, name: &str, item: &Struct) -> String {
+        let mut code = format!("{}._fields_ = [\n", name);
+        for field in &item.fields {
+            code.push_str(&comment(&field.docs, "    "));
+            code.push_str(&format!(
+                "    (\"{}\", {}),\n",
+                field.name,
+                self.ctype(&field.ty)
+            ));
+        }
+        code.push_str("]\n");
+        code
+    }
+
+    fn emit_function(&self, name: &str, docs: &str, item: &Function) -> String {
+        let mut inputs = item.inputs.clone();
+        for &mut (ref param, ref mut ty) in &mut inputs {
+            if let Type::Function(..) = *ty {
+                *ty = Type::User(Self::callback_type_name(name, item, param));
+            }
+        }
+
+        let mut code = comment(docs, "");
+        code.push_str(&format!(
+            "_lib.{}.argtypes = [{}]\n",
+            name,
+            self.param_ctypes(&inputs).join(", ")
+        ));
+        code.push_str(&format!(
+            "_lib.{}.restype = {}\n",
+            name,
+            self.ctype(&item.output)
+        ));
+        code.push_str(&format!("{0} = _lib.{0}\n", name));
+        code
+    }
+
+    /// Returns the callback completing a future and the `async` wrapper of a function which
+    /// takes `user_data` and a single callback receiving `user_data` and an `FfiResult`.
+    fn emit_async_wrapper(&self, name: &str, docs: &str, item: &Function) -> Option<[String; 2]> {
+        if let Type::Unit = item.output {
+        } else {
+            return None;
+        }
+        let user_data_params = item
+            .inputs
+            .iter()
+            .filter(|(name, ty)| name == "user_data" && is_user_data(name, ty))
+            .count();
+        if user_data_params != 1 || num_callbacks(&item.inputs) != 1 {
+            return None;
+        }
+        let (callback_param, callback_ty) = unwrap!(item
+            .inputs
+            .iter()
+            .find(|(_, ty)| extract_callback(ty).is_some()));
+        let callback = unwrap!(extract_callback(callback_ty));
+        match callback.inputs.get(1) {
+            Some(&(_, Type::Pointer(ref ty))) => match **ty {
+                Type::User(ref name) if name == "FfiResult" => (),
+                _ => return None,
+            },
+            _ => return None,
+        }
+
+        // Callback completing the future with copies of the values.
+        let callback_name = format!("_on_{}", name);
+        let mut params = vec!["user_data".to_owned(), "result".to_owned()];
+        let mut values = Vec::new();
+        for (index, (param, ty)) in callback.inputs.iter().enumerate().skip(2) {
+            let param = param_name(param, index);
+            let value = match *ty {
+                Type::String => format!("_decode({})", param),
+                Type::Char => format!("chr({})", param),
+                Type::Array(_, ArraySize::Dynamic) => format!("_copy_array({0}, {0}_len)", param),
+                Type::Pointer(ref ty) => match **ty {
+                    Type::User(ref name) if self.is_struct(name) => format!("_copy({})", param),
+                    _ => param.clone(),
+                },
+                Type::User(ref name) if self.find_enum(name).is_some() => {
+                    format!("{}({})", name, param)
+                }
+                _ => param.clone(),
+            };
+            // Arrays are passed as a pointer followed by the length.
+            let len = format!("{}_len", param);
+            params.push(param);
+            if ty.is_dynamic_array() {
+                params.push(len);
+            }
+            values.push(value);
+        }
+        let values = match values.len() {
+            0 => "None".to_owned(),
+            1 => values.remove(0),
+            _ => format!("({})", values.join(", ")),
+        };
+        let on_callback = format!(
+            "@{}\n\
+             def {}({}):\n    \
+             _complete_future(user_data, result, lambda: {})\n",
+            Self::callback_type_name(name, item, callback_param),
+            callback_name,
+            params.join(", "),
+            values,
+        );
+
+        // Wrapper taking Python values and returning the future.
+        let mut params = Vec::new();
+        let mut args = Vec::new();
+        for (index, (param, ty)) in item.inputs.iter().enumerate() {
+            if is_user_data(param, ty) {
+                args.push("user_data".to_owned());
+                continue;
+            }
+            if extract_callback(ty).is_some() {
+                args.push(callback_name.clone());
+                continue;
+            }
+            let param = param_name(param, index);
+            match *ty {
+                Type::String => args.push(format!("_encode({})", param)),
+                Type::Array(ref elem, ArraySize::Dynamic) => {
+                    args.push(format!("({} * len({1}))(*{1})", self.ctype(elem), param));
+                    args.push(format!("len({})", param));
+                }
+                _ => args.push(param.clone()),
+            }
+            params.push(param);
+        }
+        let mut wrapper = format!("async def {}_async({}):\n", name, params.join(", "));
+        if !docs.is_empty() {
+            wrapper.push_str(&docstring(docs, "    "));
+        }
+        wrapper.push_str(&format!(
+            "    future, user_data = _prepare_future()\n    \
+             _lib.{}({})\n    \
+             return await future\n",
+            name,
+            args.join(", ")
+        ));
+
+        Some([on_callback, wrapper])
+    }
+}
+
+impl Default for LangPython {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lang for LangPython {
+    fn apply_config(&mut self, config: &Config) {
+        if let Some(lib_name) = config.lib_name_or(&config.python.lib_name) {
+            self.set_lib_name(lib_name);
+        }
+    }
+
+    fn parse_ty(
+        &mut self,
+        item: &syn::ItemType,
+        _module: &[String],
+        _outputs: &mut Outputs,
+    ) -> Result<(), Error> {
+        // Parameterized type aliases are not supported and reported as skipped.
+        if !item.generics.params.is_empty() {
+            return Ok(());
+        }
+
+        let name = item.ident.to_string();
+        let ty = transform_type(&item.ty).ok_or_else(|| Error {
+            level: Level::Error,
+            span: Some(item.ty.span()),
+            file: None,
+            item: None,
+            message: format!("bindgen cannot handle the type `{}`", name),
+        })?;
+        let _ = self.aliases.insert(name, ty);
+
+        Ok(())
+    }
+
+    fn parse_const(
+        &mut self,
+        item: &syn::ItemConst,
+        _module: &[String],
+        _outputs: &mut Outputs,
+    ) -> Result<(), Error> {
+        let name = item.ident.to_string();
+        let docs = common::parse_attr(&item.attrs, |_| true, retrieve_docstring).1;
+        let item = transform_const(&item.ty, &item.expr).ok_or_else(|| Error {
+            level: Level::Error,
+            span: Some(item.ident.span()),
+            file: None,
+            item: None,
+            message: format!("bindgen cannot handle constant {}", name),
+        })?;
+
+        self.consts.push(Snippet { docs, name, item });
+
+        Ok(())
+    }
+
+    /// Converts a Rust enum into an `enum.IntEnum`.
+    ///
+    /// The Rust enum must be marked with `#[repr(C)]` or a primitive representation (e.g.
+    /// `#[repr(u8)]`), which is used for the values of the enum in structures and functions.
+    /// Bindgen will error if the enum is generic or has data-carrying variants.
+    fn parse_enum(
+        &mut self,
+        item: &syn::ItemEnum,
+        _module: &[String],
+        _outputs: &mut Outputs,
+    ) -> Result<(), Error> {
+        let repr = common::enum_repr(&item.attrs);
+        if !repr.is_defined() {
+            return Ok(());
+        }
+        if !item.generics.params.is_empty() {
+            return Err(Error::unsupported_generics_error("enums"));
+        }
+
+        let name = item.ident.to_string();
+        if common::has_data_variants(item) {
+            return Err(Error {
+                level: Level::Error,
+                span: Some(item.ident.span()),
+                file: None,
+                item: None,
+                message: format!(
+                    "bindgen cannot handle enum {} in Python: variants can't carry data",
+                    name
+                ),
+            });
+        }
+
+        let (_, docs) = common::parse_attr(&item.attrs, |_| true, retrieve_docstring);
+        let variants: Vec<_> = item.variants.iter().cloned().collect();
+        let item = transform_enum(&variants, &repr).ok_or_else(|| Error {
+            level: Level::Error,
+            span: Some(item.ident.span()),
+            file: None,
+            item: None,
+            message: format!("bindgen cannot handle enum {}", name),
+        })?;
+
+        self.enums.push(Snippet { docs, name, item });
+
+        Ok(())
+    }
+
+    /// Converts a `#[repr(C)]` struct into a `ctypes.Structure`.
+    ///
+    /// Unlike in C#, the fields keep the layout of the Rust struct, so pointers and lengths of
+    /// arrays remain separate fields.
+    fn parse_struct(
+        &mut self,
+        item: &syn::ItemStruct,
+        _module: &[String],
+        _outputs: &mut Outputs,
+    ) -> Result<(), Error> {
+        let (repr_c, docs) =
+            common::parse_attr(&item.attrs, common::check_repr_c, retrieve_docstring);
+        if !repr_c {
+            return Ok(());
+        }
+        if !item.generics.params.is_empty() {
+            return Err(Error::unsupported_generics_error("structs"));
+        }
+
+        let name = item.ident.to_string();
+        let fields: Option<Vec<_>> = item
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let (_, docs) = common::parse_attr(&field.attrs, |_| true, retrieve_docstring);
+                let name = match field.ident {
+                    Some(ref ident) => ident.to_string(),
+                    None => format!("item{}", index),
+                };
+                transform_type(&field.ty).map(|ty| StructField {
+                    docs,
+                    name,
+                    ty,
+                    has_cap: false,
+                })
+            })
+            .collect();
+        let fields = fields.ok_or_else(|| Error {
+            level: Level::Error,
+            span: Some(item.ident.span()),
+            file: None,
+            item: None,
+            message: format!("bindgen cannot handle struct {}", name),
+        })?;
+
+        self.structs.push(Snippet {
+            docs,
+            name,
+            item: Struct { fields },
+        });
+
+        Ok(())
+    }
+
+    fn parse_fn(
+        &mut self,
+        item: &syn::ItemFn,
+        _module: &[String],
+        _outputs: &mut Outputs,
+    ) -> Result<(), Error> {
+        let (no_mangle, docs) =
+            common::parse_attr(&item.attrs, common::check_no_mangle, retrieve_docstring);
+        if !no_mangle {
+            return Ok(());
+        }
+        match item.abi {
+            Some(ref abi) if common::is_extern(abi.clone()) => (),
+            _ => return Ok(()),
+        }
+
+        let name = item.ident.to_string();
+        let function = transform_function(*item.decl.clone()).ok_or_else(|| Error {
+            level: Level::Error,
+            span: Some(item.ident.span()),
+            file: None,
+            item: None,
+            message: format!("bindgen cannot handle function {}", name),
+        })?;
+
+        self.functions.push(Snippet {
+            docs,
+            name,
+            item: function,
+        });
+
+        Ok(())
+    }
+
+    fn finalise_output(&mut self, outputs: &mut Outputs) -> Result<(), Error> {
+        self.resolve_aliases();
+
+        let mut blocks = Vec::new();
+        let async_wrappers: Vec<_> = self
+            .functions
+            .iter()
+            .filter_map(|snippet| {
+                self.emit_async_wrapper(&snippet.name, &snippet.docs, &snippet.item)
+            })
+            .collect();
+
+        let mut imports = Vec::new();
+        if !async_wrappers.is_empty() {
+            imports.push("asyncio");
+        }
+        imports.extend(&["ctypes", "ctypes.util"]);
+        if !self.enums.is_empty() {
+            imports.push("enum");
+        }
+        if !async_wrappers.is_empty() {
+            imports.push("itertools");
+        }
+        blocks.push(Block::statements(
+            imports
+                .iter()
+                .map(|module| format!("import {}\n", module))
+                .collect(),
+        ));
+        blocks.push(Block::statements(format!(
+            "_lib = ctypes.CDLL(ctypes.util.find_library(\"{0}\") or \"{0}\")\n",
+            self.lib_name
+        )));
+        if !async_wrappers.is_empty() {
+            blocks.extend(RUNTIME.split("\n\n\n").map(|code| Block {
+                code: code.trim_end().to_owned() + "\n",
+                is_def: true,
+            }));
+        }
+
+        // Constants of structures have to follow the declaration of the fields.
+        let (struct_consts, consts): (Vec<_>, Vec<_>) = self
+            .consts
+            .iter()
+            .partition(|snippet| matches!(snippet.item.value, ConstValue::Struct(..)));
+        let emit_consts = |consts: Vec<&Snippet<Const>>| -> String {
+            consts
+                .iter()
+                .map(|snippet| {
+                    format!(
+                        "{}{} = {}\n",
+                        comment(&snippet.docs, ""),
+                        snippet.name,
+                        self.const_value(Some(&snippet.item.ty), &snippet.item.value, false)
+                    )
+                })
+                .collect()
+        };
+        if !consts.is_empty() {
+            blocks.push(Block::statements(emit_consts(consts)));
+        }
+
+        for snippet in &self.enums {
+            blocks.push(Block::def(self.emit_enum(
+                &snippet.name,
+                &snippet.docs,
+                &snippet.item,
+            )));
+        }
+
+        // Structures are declared before their fields, so that they can point to each other.
+        let opaque_types = self.opaque_types();
+        for name in &opaque_types {
+            if name == "FfiResult" {
+                blocks.push(Block::def(FFI_RESULT.to_owned()));
+            } else {
+                blocks.push(Block::def(format!(
+                    "class {}(ctypes.Structure):\n    pass\n",
+                    name
+                )));
+            }
+        }
+        for snippet in &self.structs {
+            let mut code = format!("class {}(ctypes.Structure):\n", snippet.name);
+            if snippet.docs.is_empty() {
+                code.push_str("    pass\n");
+            } else {
+                code.push_str(&docstring(&snippet.docs, "    "));
+            }
+            blocks.push(Block::def(code));
+        }
+        for snippet in self.ordered_structs() {
+            blocks.push(Block::statements(
+                self.emit_fields(&snippet.name, &snippet.item),
+            ));
+        }
+        if !struct_consts.is_empty() {
+            blocks.push(Block::statements(emit_consts(struct_consts)));
+        }
+
+        let mut callback_types = String::new();
+        for snippet in &self.functions {
+            for (param, ty) in &snippet.item.inputs {
+                if let Type::Function(..) = *ty {
+                    callback_types.push_str(&format!(
+                        "{} = {}\n",
+                        Self::callback_type_name(&snippet.name, &snippet.item, param),
+                        self.ctype(ty)
+                    ));
+                }
+            }
+        }
+        if !callback_types.is_empty() {
+            blocks.push(Block::statements(callback_types));
+        }
+
+        for snippet in &self.functions {
+            blocks.push(Block::statements(self.emit_function(
+                &snippet.name,
+                &snippet.docs,
+                &snippet.item,
+            )));
+        }
+
+        for [on_callback, wrapper] in async_wrappers {
+            blocks.push(Block::def(on_callback));
+            blocks.push(Block::def(wrapper));
+        }
+
+        let mut code = String::new();
+        for (index, block) in blocks.iter().enumerate() {
+            if index > 0 {
+                code.push('\n');
+                if block.is_def || blocks[index - 1].is_def {
+                    code.push('\n');
+                }
+            }
+            code.push_str(&block.code);
+        }
+
+        let _ = outputs.insert(format!("{}.py", self.lib_name), code);
+
+        Ok(())
+    }
+}
+
+impl Block {
+    fn def(code: String) -> Self {
+        Block { code, is_def: true }
+    }
+
+    fn statements(code: String) -> Self {
+        Block {
+            code,
+            is_def: false,
+        }
+    }
+}
+
+/// Collects the names of the user-defined types referred to by the type.
+fn user_types(ty: &Type, names: &mut BTreeSet<String>) {
+    match *ty {
+        Type::Pointer(ref ty) | Type::Array(ref ty, _) => user_types(ty, names),
+        Type::Function(ref fun) => {
+            user_types(&fun.output, names);
+            for (_, ty) in &fun.inputs {
+                user_types(ty, names);
+            }
+        }
+        Type::User(ref name) => {
+            let _ = names.insert(name.clone());
+        }
+        _ => (),
+    }
+}
+
+/// Returns the name of a parameter which is valid in Python.
+fn param_name(name: &str, index: usize) -> String {
+    if name.is_empty() {
+        format!("arg{}", index)
+    } else if KEYWORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_owned()
+    }
+}
+
+/// Returns the lines of the docs, without the leading `///`.
+fn doc_lines(docs: &str) -> Vec<&str> {
+    docs.lines()
+        .map(|line| {
+            let line = line.trim_start_matches("///");
+            line.strip_prefix(' ').unwrap_or(line)
+        })
+        .collect()
+}
+
+fn docstring(docs: &str, indent: &str) -> String {
+    let lines = doc_lines(docs);
+    if lines.len() == 1 {
+        return format!("{}\"\"\"{}\"\"\"\n", indent, lines[0]);
+    }
+
+    let mut code = format!("{}\"\"\"{}\n", indent, lines[0]);
+    for line in &lines[1..] {
+        if line.is_empty() {
+            code.push('\n');
+        } else {
+            code.push_str(&format!("{}{}\n", indent, line));
+        }
+    }
+    code.push_str(&format!("{}\"\"\"\n", indent));
+    code
+}
+
+fn comment(docs: &str, indent: &str) -> String {
+    doc_lines(docs)
+        .iter()
+        .map(|line| format!("{}# {}\n", indent, line).replace(" \n", "\n"))
+        .collect()
+}
+
+/// Returns a Python string literal, or a bytes literal for `c_char_p` fields of structures.
+fn string_literal(value: &str, bytes: bool) -> String {
+    let mut literal = String::from(if bytes { "b\"" } else { "\"" });
+    let escape = |literal: &mut String, ch: char| match ch {
+        '\\' => literal.push_str("\\\\"),
+        '"' => literal.push_str("\\\""),
+        '\n' => literal.push_str("\\n"),
+        '\r' => literal.push_str("\\r"),
+        '\t' => literal.push_str("\\t"),
+        ch if ch.is_ascii_control() => literal.push_str(&format!("\\x{:02x}", ch as u32)),
+        ch => literal.push(ch),
+    };
+    if bytes {
+        for byte in value.bytes() {
+            if byte.is_ascii() {
+                escape(&mut literal, byte as char);
+            } else {
+                literal.push_str(&format!("\\x{:02x}", byte));
+            }
+        }
+    } else {
+        for ch in value.chars() {
+            escape(&mut literal, ch);
+        }
+    }
+    literal.push('"');
+    literal
+}
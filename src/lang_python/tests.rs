@@ -0,0 +1,230 @@
+use super::RUNTIME;
+use crate::lang_python::LangPython;
+use crate::test_utils::fetch;
+use indoc::indoc;
+use unwrap::unwrap;
+
+#[test]
+fn types() {
+    let outputs = compile!(LangPython::default(), {
+        /// Maximum length of a name.
+        pub const NAME_LEN: usize = 4;
+
+        pub const DEFAULT_INFO: AppInfo = AppInfo {
+            id: "default",
+            name: [1, 2, 3, 4],
+            keys_len: 0,
+        };
+
+        /// Status of an app.
+        #[repr(u8)]
+        pub enum Status {
+            /// Registered with the network.
+            Registered = 1,
+            Revoked,
+        }
+
+        #[repr(C)]
+        pub struct AppList {
+            pub first: AppInfo,
+            pub next: *mut AppList,
+        }
+
+        /// Information about an app.
+        #[repr(C)]
+        pub struct AppInfo {
+            pub id: *const c_char,
+            /// Name of the app.
+            pub name: [u8; NAME_LEN],
+            pub status: Status,
+            pub owner: *mut App,
+            pub keys_ptr: *const u8,
+            pub keys_len: usize,
+        }
+
+        #[no_mangle]
+        pub extern "C" fn app_free(app: *mut App) {}
+    });
+
+    assert_multiline_eq!(
+        fetch(&outputs, "backend.py"),
+        indoc!(
+            r#"import ctypes
+               import ctypes.util
+               import enum
+
+               _lib = ctypes.CDLL(ctypes.util.find_library("backend") or "backend")
+
+               # Maximum length of a name.
+               NAME_LEN = 4
+
+
+               class Status(enum.IntEnum):
+                   """Status of an app."""
+
+                   # Registered with the network.
+                   Registered = 1
+                   Revoked = 2
+
+
+               class App(ctypes.Structure):
+                   pass
+
+
+               class AppList(ctypes.Structure):
+                   pass
+
+
+               class AppInfo(ctypes.Structure):
+                   """Information about an app."""
+
+
+               AppInfo._fields_ = [
+                   ("id", ctypes.c_char_p),
+                   # Name of the app.
+                   ("name", ctypes.c_uint8 * NAME_LEN),
+                   ("status", ctypes.c_uint8),
+                   ("owner", ctypes.POINTER(App)),
+                   ("keys_ptr", ctypes.POINTER(ctypes.c_uint8)),
+                   ("keys_len", ctypes.c_size_t),
+               ]
+
+               AppList._fields_ = [
+                   ("first", AppInfo),
+                   ("next", ctypes.POINTER(AppList)),
+               ]
+
+               DEFAULT_INFO = AppInfo(id=b"default", keys_len=0, name=(ctypes.c_uint8 * NAME_LEN)(1, 2, 3, 4))
+
+               _lib.app_free.argtypes = [ctypes.POINTER(App)]
+               _lib.app_free.restype = None
+               app_free = _lib.app_free
+               "#
+        )
+    );
+}
+
+#[test]
+fn async_functions() {
+    let outputs = compile!(LangPython::default(), {
+        #[repr(C)]
+        pub struct AppInfo {
+            pub id: *const c_char,
+        }
+
+        /// Registers an app.
+        #[no_mangle]
+        pub extern "C" fn app_register(
+            app_id: *const c_char,
+            key_ptr: *const u8,
+            key_len: usize,
+            user_data: *mut c_void,
+            o_cb: extern "C" fn(
+                user_data: *mut c_void,
+                result: *const FfiResult,
+                app: *mut App,
+                info: *const AppInfo,
+            ),
+        ) {
+        }
+
+        #[no_mangle]
+        pub extern "C" fn app_keys(
+            app: *const App,
+            user_data: *mut c_void,
+            o_cb: extern "C" fn(
+                user_data: *mut c_void,
+                result: *const FfiResult,
+                keys_ptr: *const u8,
+                keys_len: usize,
+            ),
+        ) {
+        }
+
+        // Two callbacks, so there is no `async` wrapper.
+        #[no_mangle]
+        pub extern "C" fn app_watch(
+            user_data: *mut c_void,
+            o_event: extern "C" fn(user_data: *mut c_void, event: i32),
+            o_cb: extern "C" fn(user_data: *mut c_void, result: *const FfiResult),
+        ) {
+        }
+    });
+
+    let header = indoc!(
+        r#"import asyncio
+           import ctypes
+           import ctypes.util
+           import itertools
+
+           _lib = ctypes.CDLL(ctypes.util.find_library("backend") or "backend")
+           "#
+    );
+    let declarations = indoc!(
+        r#"class App(ctypes.Structure):
+               pass
+
+
+           class FfiResult(ctypes.Structure):
+               _fields_ = [
+                   ("error_code", ctypes.c_int32),
+                   ("description", ctypes.c_char_p),
+               ]
+
+
+           class AppInfo(ctypes.Structure):
+               pass
+
+
+           AppInfo._fields_ = [
+               ("id", ctypes.c_char_p),
+           ]
+
+           AppRegisterCallback = ctypes.CFUNCTYPE(None, ctypes.c_void_p, ctypes.POINTER(FfiResult), ctypes.POINTER(App), ctypes.POINTER(AppInfo))
+           AppKeysCallback = ctypes.CFUNCTYPE(None, ctypes.c_void_p, ctypes.POINTER(FfiResult), ctypes.POINTER(ctypes.c_uint8), ctypes.c_size_t)
+           AppWatchOEvent = ctypes.CFUNCTYPE(None, ctypes.c_void_p, ctypes.c_int32)
+           AppWatchOCb = ctypes.CFUNCTYPE(None, ctypes.c_void_p, ctypes.POINTER(FfiResult))
+
+           # Registers an app.
+           _lib.app_register.argtypes = [ctypes.c_char_p, ctypes.POINTER(ctypes.c_uint8), ctypes.c_size_t, ctypes.c_void_p, AppRegisterCallback]
+           _lib.app_register.restype = None
+           app_register = _lib.app_register
+
+           _lib.app_keys.argtypes = [ctypes.POINTER(App), ctypes.c_void_p, AppKeysCallback]
+           _lib.app_keys.restype = None
+           app_keys = _lib.app_keys
+
+           _lib.app_watch.argtypes = [ctypes.c_void_p, AppWatchOEvent, AppWatchOCb]
+           _lib.app_watch.restype = None
+           app_watch = _lib.app_watch
+
+
+           @AppRegisterCallback
+           def _on_app_register(user_data, result, app, info):
+               _complete_future(user_data, result, lambda: (app, _copy(info)))
+
+
+           async def app_register_async(app_id, key):
+               """Registers an app."""
+               future, user_data = _prepare_future()
+               _lib.app_register(_encode(app_id), (ctypes.c_uint8 * len(key))(*key), len(key), user_data, _on_app_register)
+               return await future
+
+
+           @AppKeysCallback
+           def _on_app_keys(user_data, result, keys, keys_len):
+               _complete_future(user_data, result, lambda: _copy_array(keys, keys_len))
+
+
+           async def app_keys_async(app):
+               future, user_data = _prepare_future()
+               _lib.app_keys(app, user_data, _on_app_keys)
+               return await future
+           "#
+    );
+
+    assert_multiline_eq!(
+        fetch(&outputs, "backend.py"),
+        format!("{}\n\n{}\n\n\n{}", header, RUNTIME.trim_end(), declarations)
+    );
+}
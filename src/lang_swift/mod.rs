@@ -0,0 +1,788 @@
+//! Functions for converting Rust types to Swift types.
+//!
+//! The C declarations are generated by `LangC` into a single header, which is imported into Swift
+//! as a clang module. On top of them, `#[repr(C)]` structs and enums get Swift counterparts which
+//! can be created from the native values, and functions taking a `user_data` pointer and a
+//! callback receiving an `FfiResult` get `async throws` wrappers.
+
+#[cfg(test)]
+mod tests;
+
+use crate::common::{self, Lang, Outputs};
+use crate::lang_c::LangC;
+use crate::struct_field::{transform_struct_fields, StructField};
+use crate::{Config, Error, Level};
+use inflector::Inflector;
+use std::collections::BTreeMap;
+use syn::spanned::Spanned;
+use unwrap::unwrap;
+
+const SUPPORT_CODE: &str = r#"/// Error reported by the native library through an `FfiResult`.
+public struct FfiError: Error {
+    public let code: Int32
+    public let description: String
+
+    init?(_ result: UnsafePointer<@C.FfiResult>?) {
+        guard let result = result?.pointee, result.error_code != 0 else {
+            return nil
+        }
+        code = result.error_code
+        description = result.description.map { String(cString: $0) } ?? ""
+    }
+}
+
+/// Keeps the continuation of an `async` wrapper alive until the callback receives the result.
+final class Continuation<T> {
+    let continuation: CheckedContinuation<T, Error>
+
+    init(_ continuation: CheckedContinuation<T, Error>) {
+        self.continuation = continuation
+    }
+
+    static func retain(_ continuation: CheckedContinuation<T, Error>) -> UnsafeMutableRawPointer {
+        return Unmanaged.passRetained(Continuation(continuation)).toOpaque()
+    }
+
+    static func release(_ userData: UnsafeMutableRawPointer?) -> CheckedContinuation<T, Error> {
+        return Unmanaged<Continuation<T>>.fromOpaque(userData!).takeRetainedValue().continuation
+    }
+}
+
+"#;
+
+/// Declaration of `FfiResult`, added when the bindings refer to it but don't define it.
+const FFI_RESULT: &str = "typedef struct FfiResult {\n    \
+                          int32_t error_code;\n    \
+                          char const* description;\n\
+                          } FfiResult;\n";
+
+pub struct LangSwift {
+    lib_name: String,
+    c_module: Option<String>,
+    /// Generator of the C header imported into Swift.
+    c: LangC,
+    c_outputs: Outputs,
+    /// `#[repr(C)]` structs with their docs.
+    structs: Vec<(syn::ItemStruct, String)>,
+    /// Fieldless enums, by name.
+    enums: BTreeMap<String, SwiftEnum>,
+    /// Functions with their docs.
+    functions: Vec<(syn::ItemFn, String)>,
+}
+
+struct SwiftEnum {
+    docs: String,
+    /// Whether the enum has a primitive representation, which C declares as a plain integer.
+    int_repr: bool,
+    variants: Vec<syn::Variant>,
+}
+
+/// A native value turned into a Swift value.
+struct SwiftValue {
+    ty: String,
+    value: String,
+}
+
+impl LangSwift {
+    pub fn new() -> Self {
+        let mut c = LangC::new();
+        c.set_single_header(true);
+
+        Self {
+            lib_name: "backend".to_owned(),
+            c_module: None,
+            c,
+            c_outputs: Outputs::default(),
+            structs: Vec::new(),
+            enums: BTreeMap::new(),
+            functions: Vec::new(),
+        }
+    }
+
+    /// Set the name of the native library. This is also the name of the generated header and
+    /// Swift file.
+    pub fn set_lib_name<T: Into<String>>(&mut self, name: T) {
+        self.lib_name = name.into();
+        self.c.set_lib_name(self.lib_name.as_str());
+    }
+
+    /// Set the name of the clang module of the C header. Defaults to the name of the native
+    /// library in Pascal case, prefixed with `C` (e.g. `CSafeApp`).
+    pub fn set_c_module<T: Into<String>>(&mut self, name: T) {
+        self.c_module = Some(name.into());
+    }
+
+    /// Add definition of opaque type (type represented by an opaque pointer).
+    pub fn add_opaque_type<T: Into<String>>(&mut self, name: T) {
+        self.c.add_opaque_type(name);
+    }
+
+    fn c_module(&self) -> String {
+        match self.c_module {
+            Some(ref name) => name.clone(),
+            None => format!("C{}", self.lib_name.to_pascal_case()),
+        }
+    }
+
+    fn is_struct(&self, name: &str) -> bool {
+        self.structs
+            .iter()
+            .any(|(item, _)| item.ident == name && item.ident != "FfiResult")
+    }
+
+    /// Swift type of a native value, as it is imported from C.
+    fn native_type(&self, ty: &syn::Type) -> Result<String, Error> {
+        match *ty {
+            syn::Type::Path(ref path) => {
+                let name = unwrap!(path.path.segments.last())
+                    .into_value()
+                    .ident
+                    .to_string();
+                Ok(match primitive_type(&name) {
+                    Some(ty) => ty.to_owned(),
+                    None => format!("{}.{}", self.c_module(), name),
+                })
+            }
+            syn::Type::Ptr(ref ptr) => {
+                let mutable = ptr.mutability.is_some();
+                let pointee = match *ptr.elem {
+                    syn::Type::Path(ref path) => Some(
+                        unwrap!(path.path.segments.last())
+                            .into_value()
+                            .ident
+                            .to_string(),
+                    ),
+                    _ => None,
+                };
+                Ok(match pointee {
+                    Some(ref name) if name == "c_void" && mutable => {
+                        "UnsafeMutableRawPointer?".to_owned()
+                    }
+                    Some(ref name) if name == "c_void" => "UnsafeRawPointer?".to_owned(),
+                    // Types which C doesn't know the layout of are imported as opaque pointers.
+                    Some(ref name)
+                        if primitive_type(name).is_none()
+                            && !self.is_struct(name)
+                            && name != "FfiResult"
+                            && !self.enums.contains_key(name) =>
+                    {
+                        "OpaquePointer?".to_owned()
+                    }
+                    _ => format!(
+                        "Unsafe{}Pointer<{}>?",
+                        if mutable { "Mutable" } else { "" },
+                        self.native_type(&ptr.elem)?
+                    ),
+                })
+            }
+            _ => Err(Error {
+                level: Level::Error,
+                span: Some(ty.span()),
+                file: None,
+                item: None,
+                message: "bindgen cannot handle this type in Swift".into(),
+            }),
+        }
+    }
+
+    /// Turn a native value into a Swift value which doesn't refer to native memory.
+    ///
+    /// Strings, fixed-size arrays, structs and enums are converted, other values are kept as
+    /// they are.
+    fn swift_value(&self, ty: &syn::Type, value: &str) -> Result<SwiftValue, Error> {
+        match *ty {
+            syn::Type::Path(ref path) => {
+                let name = unwrap!(path.path.segments.last())
+                    .into_value()
+                    .ident
+                    .to_string();
+                if self.is_struct(&name) {
+                    return Ok(SwiftValue {
+                        value: format!("{}(native: {})", name, value),
+                        ty: name,
+                    });
+                }
+                if let Some(item) = self.enums.get(&name) {
+                    let raw_value = if item.int_repr {
+                        value.to_owned()
+                    } else {
+                        format!("{}.rawValue", value)
+                    };
+                    return Ok(SwiftValue {
+                        value: format!("{}(rawValue: Int({}))!", name, raw_value),
+                        ty: name,
+                    });
+                }
+            }
+            syn::Type::Ptr(ref ptr) => {
+                if let syn::Type::Path(ref path) = *ptr.elem {
+                    let pointee = unwrap!(path.path.segments.last())
+                        .into_value()
+                        .ident
+                        .to_string();
+                    if pointee == "c_char" {
+                        return Ok(SwiftValue {
+                            ty: "String".to_owned(),
+                            value: format!("{}.map {{ String(cString: $0) }} ?? \"\"", value),
+                        });
+                    }
+                }
+            }
+            // C arrays are imported as tuples.
+            syn::Type::Array(ref array) => {
+                let elem = self.swift_value(&array.elem, "$0")?;
+                let mut value = format!(
+                    "withUnsafeBytes(of: {}) {{ Array($0.bindMemory(to: {}.self)) }}",
+                    value,
+                    self.native_type(&array.elem)?
+                );
+                if elem.value != "$0" {
+                    value.push_str(&format!(".map {{ {} }}", elem.value));
+                }
+                return Ok(SwiftValue {
+                    ty: format!("[{}]", elem.ty),
+                    value,
+                });
+            }
+            _ => (),
+        }
+
+        Ok(SwiftValue {
+            ty: self.native_type(ty)?,
+            value: value.to_owned(),
+        })
+    }
+
+    /// Copy an array given by a pointer and a length into a Swift array.
+    fn array_value(&self, ty: &syn::Type, ptr: &str, len: &str) -> Result<SwiftValue, Error> {
+        let elem_ty = match *ty {
+            syn::Type::Ptr(ref ptr) => &*ptr.elem,
+            _ => unreachable!("arrays are passed as pointers"),
+        };
+        let elem = self.swift_value(elem_ty, "$0")?;
+        let mut value = format!(
+            "Array(UnsafeBufferPointer(start: {}, count: Int({})))",
+            ptr, len
+        );
+        if elem.value != "$0" {
+            value.push_str(&format!(".map {{ {} }}", elem.value));
+        }
+
+        Ok(SwiftValue {
+            ty: format!("[{}]", elem.ty),
+            value,
+        })
+    }
+
+    fn transform_enum(&self, name: &str, item: &SwiftEnum) -> String {
+        let mut buffer = item.docs.clone();
+        buffer.push_str(&format!("public enum {}: Int {{\n", name));
+
+        let mut value = 0;
+        for variant in &item.variants {
+            let (_, docs) = common::parse_attr(
+                &variant.attrs,
+                |_| true,
+                |attr| common::retrieve_docstring(attr, "    "),
+            );
+            value = common::extract_enum_variant_value(variant).unwrap_or(value);
+            buffer.push_str(&docs);
+            buffer.push_str(&format!(
+                "    case {} = {}\n",
+                variant.ident.to_string().to_camel_case(),
+                value
+            ));
+            value += 1;
+        }
+
+        buffer.push_str("}\n\n");
+        buffer
+    }
+
+    /// Generate a Swift struct with the same fields as the native one, except for the lengths
+    /// and capacities of arrays, and an initialiser copying the native struct.
+    fn transform_struct(&self, item: &syn::ItemStruct, docs: &str) -> Result<String, Error> {
+        let name = item.ident.to_string();
+        let fields: Vec<_> = item.fields.iter().cloned().collect();
+
+        let mut declarations = String::new();
+        let mut assignments = String::new();
+        for field in transform_struct_fields(&fields) {
+            let name = field.name();
+            let native = format!("native.{}", name);
+            let (name, value) = match field {
+                StructField::Primitive(ref field) | StructField::String(ref field) => {
+                    (name, self.swift_value(&field.ty, &native)?)
+                }
+                StructField::Array {
+                    ref field,
+                    ref len_field,
+                    ..
+                } => {
+                    let len = format!("native.{}", len_field);
+                    (
+                        name.trim_end_matches("_ptr").to_owned(),
+                        self.array_value(&field.ty, &native, &len)?,
+                    )
+                }
+                StructField::StructPtr { ref field, .. } => (
+                    name,
+                    SwiftValue {
+                        ty: self.native_type(&field.ty)?,
+                        value: native,
+                    },
+                ),
+                StructField::LenField(..) => continue,
+            };
+
+            let (_, docs) = common::parse_attr(
+                &field.struct_field().attrs,
+                |_| true,
+                |attr| common::retrieve_docstring(attr, "    "),
+            );
+            let name = name.to_camel_case();
+            declarations.push_str(&docs);
+            declarations.push_str(&format!("    public var {}: {}\n", name, value.ty));
+            assignments.push_str(&format!("        {} = {}\n", name, value.value));
+        }
+
+        Ok(format!(
+            "{docs}public struct {name} {{\n\
+             {declarations}\
+             \n\
+             \x20   public init(native: {module}.{name}) {{\n\
+             {assignments}\
+             \x20   }}\n\
+             }}\n\n",
+            docs = docs,
+            name = name,
+            declarations = declarations,
+            module = self.c_module(),
+            assignments = assignments,
+        ))
+    }
+
+    /// Generate an `async throws` wrapper of a function taking `user_data` and a callback which
+    /// receives the `user_data` and an `FfiResult`, followed by the values of the result.
+    fn transform_callback_fn(&self, item: &syn::ItemFn, docs: &str) -> Result<String, Error> {
+        if item.decl.output != syn::ReturnType::Default {
+            return Ok(String::new());
+        }
+        let args: Vec<_> = item
+            .decl
+            .inputs
+            .iter()
+            .filter_map(common::transform_fnarg_to_argcap)
+            .collect();
+        if args.len() != item.decl.inputs.len()
+            || args
+                .iter()
+                .filter(|arg| common::is_user_data_arg(arg))
+                .count()
+                != 1
+        {
+            return Ok(String::new());
+        }
+        let mut callbacks = args.iter().filter_map(|arg| match arg.ty {
+            syn::Type::BareFn(ref bare_fn) => Some(bare_fn),
+            _ => None,
+        });
+        let bare_fn = match (callbacks.next(), callbacks.next()) {
+            (Some(bare_fn), None) => bare_fn,
+            _ => return Ok(String::new()),
+        };
+        let inputs: Vec<_> = bare_fn.inputs.iter().collect();
+        if inputs.len() < 2
+            || !common::is_user_data_arg_barefn(inputs[0])
+            || !common::is_result_arg_barefn(inputs[1])
+        {
+            return Ok(String::new());
+        }
+
+        // Parameters of the callback, and the values the continuation is resumed with.
+        let mut cb_params = Vec::new();
+        let mut values = Vec::new();
+        let mut index = 0;
+        while index < inputs.len() {
+            let arg = inputs[index];
+            let name = barefn_arg_name(arg, index);
+            cb_params.push(name.clone());
+
+            if index >= 2 {
+                let next_arg = inputs.get(index + 1).cloned();
+                if common::is_array_arg_barefn(arg, next_arg) {
+                    // Arrays are passed as a pointer followed by the length.
+                    let len = barefn_arg_name(unwrap!(next_arg), index + 1);
+                    cb_params.push(len.clone());
+                    values.push(self.array_value(&arg.ty, &name, &len)?);
+                    index += 1;
+                } else {
+                    values.push(self.callback_value(&arg.ty, &name)?);
+                }
+            }
+            index += 1;
+        }
+        let (return_type, return_value) = match values.len() {
+            0 => ("Void".to_owned(), "()".to_owned()),
+            1 => {
+                let value = values.remove(0);
+                (value.ty, value.value)
+            }
+            _ => (
+                format!(
+                    "({})",
+                    values
+                        .iter()
+                        .map(|value| value.ty.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                format!(
+                    "({})",
+                    values
+                        .iter()
+                        .map(|value| value.value.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            ),
+        };
+
+        // Parameters of the wrapper, and the arguments of the native function.
+        let mut params = Vec::new();
+        let mut call_args = Vec::new();
+        let mut index = 0;
+        while index < args.len() {
+            let arg = args[index];
+            let name = arg_name(arg);
+            if common::is_user_data_arg(arg) {
+                call_args.push("userData".to_owned());
+            } else if let syn::Type::BareFn(..) = arg.ty {
+                call_args.push(format!(
+                    "{{ {params} in\n\
+                     \x20           let continuation = Continuation<{ty}>.release(userData)\n\
+                     \x20           if let error = FfiError({result}) {{\n\
+                     \x20               continuation.resume(throwing: error)\n\
+                     \x20           }} else {{\n\
+                     \x20               continuation.resume(returning: {value})\n\
+                     \x20           }}\n\
+                     \x20       }}",
+                    params = cb_params.join(", "),
+                    ty = return_type,
+                    result = cb_params[1],
+                    value = return_value,
+                ));
+            } else if is_const_array_arg(arg, args.get(index + 1).cloned()) {
+                // Swift arrays are passed to C as pointers to their elements.
+                let name = name.trim_end_matches("Ptr").to_owned();
+                let elem_ty = match arg.ty {
+                    syn::Type::Ptr(ref ptr) => self.native_type(&ptr.elem)?,
+                    _ => unreachable!("arrays are passed as pointers"),
+                };
+                params.push(format!("{}: [{}]", name, elem_ty));
+                call_args.push(name.clone());
+                call_args.push(format!("UInt({}.count)", name));
+                index += 1;
+            } else {
+                let (ty, value) = self.native_arg(&arg.ty, &name)?;
+                params.push(format!("{}: {}", name, ty));
+                call_args.push(value);
+            }
+            index += 1;
+        }
+
+        let mut call = call_args.join(", ");
+        if let Some(syn::Type::BareFn(..)) = args.last().map(|arg| &arg.ty) {
+            // Pass the callback as a trailing closure.
+            let callback = unwrap!(call_args.pop());
+            call = format!("({}) {}", call_args.join(", "), callback);
+        } else {
+            call = format!("({})", call);
+        }
+
+        let return_clause = if return_type == "Void" {
+            String::new()
+        } else {
+            format!(" -> {}", return_type)
+        };
+
+        Ok(format!(
+            "{docs}public func {name}({params}) async throws{return_clause} {{\n\
+             \x20   return try await withCheckedThrowingContinuation {{ \
+             (continuation: CheckedContinuation<{ty}, Error>) in\n\
+             \x20       let userData = Continuation.retain(continuation)\n\
+             \x20       {native}{call}\n\
+             \x20   }}\n\
+             }}\n\n",
+            docs = docs,
+            name = item.ident.to_string().to_camel_case(),
+            params = params.join(", "),
+            return_clause = return_clause,
+            ty = return_type,
+            native = item.ident,
+            call = call,
+        ))
+    }
+
+    /// Turn a value passed to a callback into a Swift value which outlives the callback.
+    ///
+    /// In addition to the conversions of `swift_value`, structs passed by pointer are copied.
+    fn callback_value(&self, ty: &syn::Type, name: &str) -> Result<SwiftValue, Error> {
+        if let syn::Type::Ptr(ref ptr) = *ty {
+            if ptr.mutability.is_none() {
+                if let syn::Type::Path(ref path) = *ptr.elem {
+                    let pointee = unwrap!(path.path.segments.last())
+                        .into_value()
+                        .ident
+                        .to_string();
+                    if self.is_struct(&pointee) {
+                        return Ok(SwiftValue {
+                            value: format!("{}(native: {}!.pointee)", pointee, name),
+                            ty: pointee,
+                        });
+                    }
+                }
+            }
+        }
+
+        self.swift_value(ty, name)
+    }
+
+    /// Returns the type of a wrapper parameter and the argument passed to the native function.
+    ///
+    /// Strings and enums are taken as Swift values, other values as native ones.
+    fn native_arg(&self, ty: &syn::Type, name: &str) -> Result<(String, String), Error> {
+        match *ty {
+            syn::Type::Ptr(ref ptr) if ptr.mutability.is_none() => {
+                if let syn::Type::Path(ref path) = *ptr.elem {
+                    if unwrap!(path.path.segments.last()).into_value().ident == "c_char" {
+                        return Ok(("String".to_owned(), name.to_owned()));
+                    }
+                }
+            }
+            syn::Type::Path(ref path) => {
+                let enum_name = unwrap!(path.path.segments.last())
+                    .into_value()
+                    .ident
+                    .to_string();
+                if let Some(item) = self.enums.get(&enum_name) {
+                    let value = if item.int_repr {
+                        format!("{}.{}({}.rawValue)", self.c_module(), enum_name, name)
+                    } else {
+                        format!(
+                            "{}.{}(rawValue: .init({}.rawValue))",
+                            self.c_module(),
+                            enum_name,
+                            name
+                        )
+                    };
+                    return Ok((enum_name, value));
+                }
+            }
+            _ => (),
+        }
+
+        Ok((self.native_type(ty)?, name.to_owned()))
+    }
+}
+
+impl Default for LangSwift {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lang for LangSwift {
+    fn apply_config(&mut self, config: &Config) {
+        self.c.apply_config(config);
+        self.c.set_single_header(true);
+
+        if let Some(lib_name) = config.lib_name_or(&config.swift.lib_name) {
+            self.set_lib_name(lib_name);
+        }
+        if let Some(ref c_module) = config.swift.c_module {
+            self.set_c_module(c_module.as_str());
+        }
+    }
+
+    fn parse_const(
+        &mut self,
+        item: &syn::ItemConst,
+        module: &[String],
+        _outputs: &mut Outputs,
+    ) -> Result<(), Error> {
+        self.c.parse_const(item, module, &mut self.c_outputs)
+    }
+
+    fn parse_ty(
+        &mut self,
+        item: &syn::ItemType,
+        module: &[String],
+        _outputs: &mut Outputs,
+    ) -> Result<(), Error> {
+        self.c.parse_ty(item, module, &mut self.c_outputs)
+    }
+
+    /// Convert a Rust enum into a C enum, together with a Swift enum if it has no data-carrying
+    /// variants.
+    fn parse_enum(
+        &mut self,
+        item: &syn::ItemEnum,
+        module: &[String],
+        _outputs: &mut Outputs,
+    ) -> Result<(), Error> {
+        self.c.parse_enum(item, module, &mut self.c_outputs)?;
+
+        let repr = common::enum_repr(&item.attrs);
+        if repr.is_defined() && !common::has_data_variants(item) {
+            let (_, docs) = common::parse_attr(
+                &item.attrs,
+                |_| true,
+                |attr| common::retrieve_docstring(attr, ""),
+            );
+            let _ = self.enums.insert(
+                item.ident.to_string(),
+                SwiftEnum {
+                    docs,
+                    int_repr: repr.int.is_some(),
+                    variants: item.variants.iter().cloned().collect(),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Convert a Rust struct into a C struct, together with a Swift struct which copies it.
+    fn parse_struct(
+        &mut self,
+        item: &syn::ItemStruct,
+        module: &[String],
+        _outputs: &mut Outputs,
+    ) -> Result<(), Error> {
+        self.c.parse_struct(item, module, &mut self.c_outputs)?;
+
+        let (repr_c, docs) = common::parse_attr(&item.attrs, common::check_repr_c, |attr| {
+            common::retrieve_docstring(attr, "")
+        });
+        if repr_c {
+            if let syn::Fields::Named(..) = item.fields {
+                self.structs.push((item.clone(), docs));
+            }
+        }
+        Ok(())
+    }
+
+    /// Convert a Rust function declaration into a C function declaration, together with an
+    /// `async` wrapper if it takes a callback.
+    fn parse_fn(
+        &mut self,
+        item: &syn::ItemFn,
+        module: &[String],
+        _outputs: &mut Outputs,
+    ) -> Result<(), Error> {
+        self.c.parse_fn(item, module, &mut self.c_outputs)?;
+
+        let (no_mangle, docs) = common::parse_attr(&item.attrs, common::check_no_mangle, |attr| {
+            common::retrieve_docstring(attr, "")
+        });
+        let extern_c = item.abi.clone().is_some_and(common::is_extern);
+        if no_mangle && extern_c && item.decl.generics.params.is_empty() {
+            self.functions.push((item.clone(), docs));
+        }
+        Ok(())
+    }
+
+    fn finalise_output(&mut self, outputs: &mut Outputs) -> Result<(), Error> {
+        let c_module = self.c_module();
+
+        let mut code = format!("import {}\n\n", c_module);
+        let mut wrappers = String::new();
+        for (item, docs) in &self.functions {
+            wrappers.push_str(&self.transform_callback_fn(item, docs)?);
+        }
+        if !wrappers.is_empty() {
+            code.push_str(&SUPPORT_CODE.replace("@C", &c_module));
+
+            // The fields of the result are needed to report errors.
+            if !self
+                .structs
+                .iter()
+                .any(|(item, _)| item.ident == "FfiResult")
+            {
+                self.c.add_custom_code(FFI_RESULT);
+            }
+        }
+        for (name, item) in &self.enums {
+            code.push_str(&self.transform_enum(name, item));
+        }
+        for (item, docs) in &self.structs {
+            if item.ident != "FfiResult" {
+                code.push_str(&self.transform_struct(item, docs)?);
+            }
+        }
+        code.push_str(&wrappers);
+        let _ = outputs.insert(
+            format!("{}.swift", self.lib_name),
+            code.trim_end().to_owned() + "\n",
+        );
+
+        let header_name = format!("{}.h", self.lib_name);
+        let _ = outputs.insert(
+            "module.modulemap".to_owned(),
+            format!(
+                "module {} {{\n    header \"{}\"\n    export *\n}}\n",
+                c_module, header_name
+            ),
+        );
+
+        self.c.finalise_output(&mut self.c_outputs)?;
+        outputs.extend(self.c_outputs.drain());
+
+        Ok(())
+    }
+}
+
+/// Swift type of a primitive Rust or C type.
+fn primitive_type(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "bool" => "Bool",
+        "f32" | "c_float" => "Float",
+        "f64" | "c_double" => "Double",
+        "i8" | "c_schar" => "Int8",
+        "i16" | "c_short" => "Int16",
+        "i32" | "c_int" => "Int32",
+        "i64" | "c_longlong" => "Int64",
+        "isize" | "c_long" => "Int",
+        "u8" | "c_uchar" => "UInt8",
+        "u16" | "c_ushort" => "UInt16",
+        "u32" | "c_uint" => "UInt32",
+        "u64" | "c_ulonglong" => "UInt64",
+        "usize" | "c_ulong" => "UInt",
+        "c_char" => "CChar",
+        _ => return None,
+    })
+}
+
+/// Whether the argument is a pointer to constant elements followed by their number.
+fn is_const_array_arg(arg: &syn::ArgCaptured, next_arg: Option<&syn::ArgCaptured>) -> bool {
+    match arg.ty {
+        syn::Type::Ptr(ref ptr) if ptr.mutability.is_none() => common::is_array_arg(arg, next_arg),
+        _ => false,
+    }
+}
+
+/// Name of a function argument, in camel case.
+fn arg_name(arg: &syn::ArgCaptured) -> String {
+    unwrap!(common::take_out_pat(&arg.pat))
+        .ident
+        .to_string()
+        .to_camel_case()
+}
+
+/// Name of a callback argument in camel case, or a name based on its position if it's unnamed.
+fn barefn_arg_name(arg: &syn::BareFnArg, index: usize) -> String {
+    match arg.name {
+        Some((syn::BareFnArgName::Named(ref name), _)) => name.to_string().to_camel_case(),
+        _ => format!("arg{}", index),
+    }
+}
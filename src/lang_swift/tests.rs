@@ -0,0 +1,198 @@
+use super::SUPPORT_CODE;
+use crate::lang_swift::LangSwift;
+use crate::test_utils::fetch;
+use indoc::indoc;
+use unwrap::unwrap;
+
+#[test]
+fn structs_and_async_functions() {
+    let outputs = compile!(LangSwift::default(), {
+        /// Status of an app.
+        #[repr(u8)]
+        pub enum Status {
+            /// Registered with the network.
+            Registered = 1,
+            Revoked,
+        }
+
+        #[repr(C)]
+        pub enum Kind {
+            Plain,
+            Fancy,
+        }
+
+        /// Information about an app.
+        #[repr(C)]
+        pub struct AppInfo {
+            pub id: *const c_char,
+            /// Name of the app.
+            pub name: [u8; 4],
+            pub status: Status,
+            pub kind: Kind,
+            pub owner: *mut App,
+            pub keys_ptr: *const u8,
+            pub keys_len: usize,
+            pub keys_cap: usize,
+        }
+
+        #[repr(C)]
+        pub struct AppList {
+            pub first: AppInfo,
+            pub next: *mut AppList,
+        }
+
+        /// Registers an app.
+        #[no_mangle]
+        pub extern "C" fn app_register(
+            app_id: *const c_char,
+            key_ptr: *const u8,
+            key_len: usize,
+            status: Status,
+            user_data: *mut c_void,
+            o_cb: extern "C" fn(
+                user_data: *mut c_void,
+                result: *const FfiResult,
+                app: *mut App,
+                info: *const AppInfo,
+            ),
+        ) {
+        }
+
+        #[no_mangle]
+        pub extern "C" fn app_keys(
+            app: *const App,
+            user_data: *mut c_void,
+            o_cb: extern "C" fn(
+                user_data: *mut c_void,
+                result: *const FfiResult,
+                keys_ptr: *const u8,
+                keys_len: usize,
+            ),
+        ) {
+        }
+
+        #[no_mangle]
+        pub extern "C" fn app_reconnect(
+            app: *mut App,
+            user_data: *mut c_void,
+            o_cb: extern "C" fn(user_data: *mut c_void, result: *const FfiResult),
+        ) {
+        }
+    });
+
+    let declarations = indoc!(
+        r#"public enum Kind: Int {
+               case plain = 0
+               case fancy = 1
+           }
+
+           /// Status of an app.
+           public enum Status: Int {
+               /// Registered with the network.
+               case registered = 1
+               case revoked = 2
+           }
+
+           /// Information about an app.
+           public struct AppInfo {
+               public var id: String
+               /// Name of the app.
+               public var name: [UInt8]
+               public var status: Status
+               public var kind: Kind
+               public var owner: OpaquePointer?
+               public var keys: [UInt8]
+
+               public init(native: CBackend.AppInfo) {
+                   id = native.id.map { String(cString: $0) } ?? ""
+                   name = withUnsafeBytes(of: native.name) { Array($0.bindMemory(to: UInt8.self)) }
+                   status = Status(rawValue: Int(native.status))!
+                   kind = Kind(rawValue: Int(native.kind.rawValue))!
+                   owner = native.owner
+                   keys = Array(UnsafeBufferPointer(start: native.keys_ptr, count: Int(native.keys_len)))
+               }
+           }
+
+           public struct AppList {
+               public var first: AppInfo
+               public var next: UnsafeMutablePointer<CBackend.AppList>?
+
+               public init(native: CBackend.AppList) {
+                   first = AppInfo(native: native.first)
+                   next = native.next
+               }
+           }
+
+           /// Registers an app.
+           public func appRegister(appId: String, key: [UInt8], status: Status) async throws -> (OpaquePointer?, AppInfo) {
+               return try await withCheckedThrowingContinuation { (continuation: CheckedContinuation<(OpaquePointer?, AppInfo), Error>) in
+                   let userData = Continuation.retain(continuation)
+                   app_register(appId, key, UInt(key.count), CBackend.Status(status.rawValue), userData) { userData, result, app, info in
+                       let continuation = Continuation<(OpaquePointer?, AppInfo)>.release(userData)
+                       if let error = FfiError(result) {
+                           continuation.resume(throwing: error)
+                       } else {
+                           continuation.resume(returning: (app, AppInfo(native: info!.pointee)))
+                       }
+                   }
+               }
+           }
+
+           public func appKeys(app: OpaquePointer?) async throws -> [UInt8] {
+               return try await withCheckedThrowingContinuation { (continuation: CheckedContinuation<[UInt8], Error>) in
+                   let userData = Continuation.retain(continuation)
+                   app_keys(app, userData) { userData, result, keysPtr, keysLen in
+                       let continuation = Continuation<[UInt8]>.release(userData)
+                       if let error = FfiError(result) {
+                           continuation.resume(throwing: error)
+                       } else {
+                           continuation.resume(returning: Array(UnsafeBufferPointer(start: keysPtr, count: Int(keysLen))))
+                       }
+                   }
+               }
+           }
+
+           public func appReconnect(app: OpaquePointer?) async throws {
+               return try await withCheckedThrowingContinuation { (continuation: CheckedContinuation<Void, Error>) in
+                   let userData = Continuation.retain(continuation)
+                   app_reconnect(app, userData) { userData, result in
+                       let continuation = Continuation<Void>.release(userData)
+                       if let error = FfiError(result) {
+                           continuation.resume(throwing: error)
+                       } else {
+                           continuation.resume(returning: ())
+                       }
+                   }
+               }
+           }
+           "#
+    );
+    assert_multiline_eq!(
+        fetch(&outputs, "backend.swift"),
+        format!(
+            "import CBackend\n\n{}{}",
+            SUPPORT_CODE.replace("@C", "CBackend"),
+            declarations
+        )
+    );
+
+    assert_multiline_eq!(
+        fetch(&outputs, "module.modulemap"),
+        indoc!(
+            r#"module CBackend {
+                   header "backend.h"
+                   export *
+               }
+               "#
+        )
+    );
+
+    // The header defines `FfiResult`, so that Swift can read the error.
+    assert!(fetch(&outputs, "backend.h").contains(indoc!(
+        "typedef struct FfiResult {
+             int32_t error_code;
+             char const* description;
+         } FfiResult;
+         "
+    )));
+}
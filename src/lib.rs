@@ -27,31 +27,46 @@
 )]
 
 pub use common::FilterMode;
+pub use config::Config;
 pub use csharp::LangCSharp;
 pub use errors::Error;
 pub use errors::Level;
-pub use java::LangJava;
+pub use errors::MessageFormat;
+pub use java::{java_type_from_str, LangJava};
 pub use lang_c::LangC;
+pub use lang_cpp::LangCpp;
+pub use lang_kotlin::LangKotlin;
+pub use lang_python::LangPython;
+pub use lang_swift::LangSwift;
 
 use common::{Lang, Outputs};
-use std::collections::{BTreeSet, HashMap};
+use std::collections::HashMap;
+use std::env;
 use std::fs;
-use std::fs::File;
-use std::io::Error as IoError;
+use std::io::{self, Error as IoError};
 use std::io::{Read, Write};
-use std::path::{self, Component, Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use unwrap::unwrap;
 
 #[cfg(test)]
 #[macro_use]
 mod test_utils;
+mod check;
 mod common;
+pub mod config;
 mod csharp;
 mod errors;
 mod java;
 mod lang_c;
+mod lang_cpp;
+mod lang_kotlin;
+mod lang_python;
+mod lang_swift;
+mod lint;
+mod manifest;
 mod output;
 mod parse;
+mod resolve;
 mod struct_field;
 
 enum Input {
@@ -87,9 +102,35 @@ enum Input {
 ///     .source_file("src/root.rs")
 ///     .run_build("include/my_header.h");
 /// ```
+///
+/// Bindings are generated for all modules located within the FFI roots (by default just the
+/// top-level `ffi` module). Use `.add_root("...")` if the FFI is exposed from somewhere else.
+///
+/// ```ignore
+/// Bindgen::new().expect("unable to read cargo manifest")
+///     .add_root("api::ffi")
+///     .add_root("capi")
+///     .run_build("include/my_header.h");
+/// ```
 pub struct Bindgen {
     /// The root source file of the crate.
     input: Input,
+    /// Paths of the modules containing the FFI (e.g. `["api", "ffi"]`).
+    roots: Vec<Vec<String>>,
+    /// How errors are reported.
+    message_format: MessageFormat,
+    /// Whether skipped items are reported as errors rather than warnings.
+    strict: bool,
+    /// Warnings reported by the last compilation.
+    warnings: Vec<Error>,
+    /// Settings for the target languages.
+    config: Config,
+    /// Whether files generated by earlier runs, but not by the current one, are removed.
+    remove_stale: bool,
+    /// Whether `run_build` prints `cargo:rerun-if-changed` lines.
+    rerun_if_changed: bool,
+    /// Source files read by the last compilation.
+    source_files: Vec<PathBuf>,
 }
 
 impl Bindgen {
@@ -98,10 +139,59 @@ impl Bindgen {
     /// This can only fail if there are issues reading the cargo manifest. If there is no cargo
     /// manifest available then the source file defaults to `src/lib.rs`.
     pub fn new() -> Result<Self, Error> {
-        let source_path = source_file_from_cargo()?;
+        let manifest = read_cargo_manifest()?;
+        let source_path = source_file_from_cargo(manifest.as_ref());
         let input = Input::File(PathBuf::from(source_path));
 
-        Ok(Bindgen { input })
+        Ok(Bindgen {
+            input,
+            roots: vec![vec!["ffi".to_string()]],
+            message_format: MessageFormat::default(),
+            strict: false,
+            warnings: Vec::new(),
+            config: Config::default(),
+            remove_stale: true,
+            // Cargo only sets `OUT_DIR` when running build scripts.
+            rerun_if_changed: env::var_os("OUT_DIR").is_some(),
+            source_files: Vec::new(),
+        })
+    }
+
+    /// Create a new bindgen instance configured by the `bindgen.toml` file next to the cargo
+    /// manifest, or by the `[package.metadata.bindgen]` table of the manifest itself.
+    ///
+    /// The FFI roots are taken from the configuration. The settings for the target languages are
+    /// applied with `configure`.
+    pub fn with_config() -> Result<Self, Error> {
+        let manifest = read_cargo_manifest()?;
+        let config = Config::load(&manifest_dir(), manifest.as_ref())?;
+
+        let mut bindgen = Self::new()?;
+        if !config.roots.is_empty() {
+            let _ = bindgen.set_roots(&config.roots);
+        }
+        bindgen.config = config;
+        Ok(bindgen)
+    }
+
+    /// The configuration read by `with_config`.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Mutable access to the configuration, e.g. to override it with command line arguments.
+    pub fn config_mut(&mut self) -> &mut Config {
+        &mut self.config
+    }
+
+    /// Apply the configuration for the target language to `lang`.
+    ///
+    /// Anything set on `lang` afterwards takes precedence over the configuration.
+    pub fn configure<L: Lang>(&self, lang: &mut L) {
+        // The roots may have been changed since the configuration was read.
+        let mut config = self.config.clone();
+        config.roots = self.roots.iter().map(|root| root.join("::")).collect();
+        lang.apply_config(&config);
     }
 
     /// Set the path to the root source file of the crate.
@@ -115,6 +205,75 @@ impl Bindgen {
         self
     }
 
+    /// Add a module (e.g. `api::ffi`) to the FFI roots.
+    ///
+    /// All modules located within a root are parsed, including the ones declared in separate
+    /// files (`mod foo;`).
+    pub fn add_root<S: AsRef<str>>(&mut self, path: S) -> &mut Self {
+        let root = resolve::split_module_path(path.as_ref());
+        if !self.roots.contains(&root) {
+            self.roots.push(root);
+        }
+        self
+    }
+
+    /// Replace the FFI roots with the given modules.
+    pub fn set_roots<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.roots.clear();
+        for path in paths {
+            self.add_root(path);
+        }
+        self
+    }
+
+    /// Set the format errors are reported in.
+    ///
+    /// With `MessageFormat::Json` every error is printed to stdout as a single line JSON object,
+    /// in the same shape `cargo --message-format=json` uses for compiler messages.
+    pub fn set_message_format(&mut self, format: MessageFormat) -> &mut Self {
+        self.message_format = format;
+        self
+    }
+
+    /// Report public items which look like they are meant to be part of the FFI, but are
+    /// skipped (e.g. an `extern "C"` function without `#[no_mangle]`), as errors instead of
+    /// warnings.
+    pub fn set_strict(&mut self, strict: bool) -> &mut Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Set whether `write_outputs` removes files it generated on earlier runs which are not
+    /// generated any more (the default), or only reports them as warnings.
+    pub fn set_remove_stale(&mut self, remove: bool) -> &mut Self {
+        self.remove_stale = remove;
+        self
+    }
+
+    /// Set whether `run_build` tells cargo to rerun the build script only when one of the files
+    /// the bindings were generated from changes: the root source file, every module file and
+    /// the configuration and custom code files.
+    ///
+    /// This is enabled by default when running from a build script.
+    pub fn set_rerun_if_changed(&mut self, enable: bool) -> &mut Self {
+        self.rerun_if_changed = enable;
+        self
+    }
+
+    /// Source files read by the last call to `compile`.
+    pub fn source_files(&self) -> &[PathBuf] {
+        &self.source_files
+    }
+
+    /// Warnings reported by the last call to `compile`.
+    pub fn warnings(&self) -> &[Error] {
+        &self.warnings
+    }
+
     /// Use custom code as input.
     pub fn source_code<S>(&mut self, file_name: S, code: S) -> &mut Self
     where
@@ -138,13 +297,33 @@ impl Bindgen {
         outputs: &mut Outputs,
         finalise: bool,
     ) -> Result<(), Vec<Error>> {
-        match &self.input {
-            Input::Code { file_name, code } => {
-                self.compile_from_source(lang, outputs, file_name.clone(), code.clone())?;
-            }
+        let mut warnings = Vec::new();
+        let mut source_files = Vec::new();
+        let result = match &self.input {
+            Input::Code { file_name, code } => self.compile_from_source(
+                lang,
+                outputs,
+                file_name.clone(),
+                code.clone(),
+                &mut warnings,
+            ),
             Input::File(path) => {
-                self.compile_from_path(lang, outputs, path)?;
+                self.compile_from_path(lang, outputs, path, &mut warnings, &mut source_files)
             }
+        };
+        self.source_files = source_files;
+
+        let mut errors = result.err().unwrap_or_default();
+        if self.strict {
+            errors.extend(warnings.drain(..).map(|mut warning| {
+                warning.level = Level::Error;
+                warning
+            }));
+        }
+        self.warnings = warnings;
+
+        if !errors.is_empty() {
+            return Err(errors);
         }
         if finalise {
             lang.finalise_output(outputs)?;
@@ -157,53 +336,67 @@ impl Bindgen {
         lang: &mut L,
         outputs: &mut Outputs,
         path: &Path,
+        warnings: &mut Vec<Error>,
+        source_files: &mut Vec<PathBuf>,
     ) -> Result<(), Vec<Error>> {
-        let base_path = unwrap!(path.parent());
-        let mod_path: String = unwrap!(path.to_str()).to_string();
-
-        // Parse the top level mod.
-        // Creates AST for the entire file
-        let mut file = unwrap!(File::open(path));
-        let mut content = String::new();
-        unwrap!(file.read_to_string(&mut content));
-        let ast = unwrap!(syn::parse_file(&content));
-        let mut imported: BTreeSet<Vec<String>> = Default::default();
-        for item in ast.items {
-            match &item {
-                syn::Item::Use(ref itemuse) => {
-                    if parse::imported_mods(itemuse).is_some() {
-                        imported.insert(unwrap!(parse::imported_mods(itemuse)));
+        let (modules, mut errors, resolve_warnings) = resolve::resolve_modules(path, &self.roots);
+
+        // Files which could not be parsed are included, so that fixing them triggers a rebuild.
+        source_files.push(path.to_path_buf());
+        let files = modules.iter().map(|module| &module.file);
+        let failures = errors.iter().chain(&resolve_warnings);
+        for file in files.chain(failures.filter_map(|error| error.file.as_ref())) {
+            if !source_files.contains(file) {
+                source_files.push(file.clone());
+            }
+        }
+        warnings.extend(resolve_warnings);
+
+        let mut repr_c = HashMap::new();
+        for module in &modules {
+            lint::collect_repr_c(&module.ast.items, &mut repr_c);
+            if !module.path.is_empty() && resolve::in_roots(&module.path, &self.roots) {
+                parse::declare_enums(lang, &module.ast.items);
+            }
+        }
+
+        for module in modules {
+            if module.path.is_empty() {
+                // Constants in the crate root are parsed with an empty module path.
+                for item in &module.ast.items {
+                    if let syn::Item::Const(ref item) = item {
+                        if let Err(error) = lang.parse_const(item, &[], outputs) {
+                            let error = error.with_item(&item.ident);
+                            errors.push(error.with_file(&module.file));
+                        }
                     }
                 }
-                // Parsing const in lib.rs for CSharp
-                syn::Item::Const(ref item) => {
-                    lang.parse_const(item, &[mod_path.clone()], outputs)?;
-                }
-                _ => {}
+                continue;
             }
-        }
-        for module in imported {
-            let mut mod_path = base_path.join(&format!(
-                "{}.rs",
-                module.join(&path::MAIN_SEPARATOR.to_string())
-            ));
-
-            if !mod_path.exists() {
-                mod_path = base_path.join(&format!(
-                    "{}/mod.rs",
-                    module.join(&path::MAIN_SEPARATOR.to_string())
-                ));
+
+            if !resolve::in_roots(&module.path, &self.roots) {
+                continue;
             }
 
-            println!("Parsing {} ({:?})", module.join("::"), mod_path);
+            if self.message_format == MessageFormat::Human {
+                // Progress goes to stderr, which keeps stdout free for diffs in check mode.
+                eprintln!("Parsing {} ({:?})", module.path.join("::"), module.file);
+            }
 
-            let mut file = unwrap!(File::open(mod_path));
-            let mut content = String::new();
-            unwrap!(file.read_to_string(&mut content));
-            let ast = unwrap!(syn::parse_file(&content));
-            parse::parse_file(lang, &ast, &module, outputs)?;
+            let file = &module.file;
+            if let Err(module_errors) = parse::parse_file(lang, &module.ast, &module.path, outputs)
+            {
+                errors.extend(module_errors.into_iter().map(|error| error.with_file(file)));
+            }
+            let skipped = lint::skipped_items(&module.ast.items, &repr_c);
+            warnings.extend(skipped.into_iter().map(|warning| warning.with_file(file)));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
         }
-        Ok(())
     }
 
     fn compile_from_source<L: Lang>(
@@ -212,36 +405,63 @@ impl Bindgen {
         outputs: &mut Outputs,
         file_name: String,
         source: String,
+        warnings: &mut Vec<Error>,
     ) -> Result<(), Vec<Error>> {
-        let module = convert_lib_path_to_module(&PathBuf::from(file_name));
+        let module = convert_lib_path_to_module(&PathBuf::from(&file_name));
+
+        let _ast: syn::File = syn::parse_str(&source)
+            .map_err(|err| Error::unparsable_file(Path::new(&file_name), &err))?;
 
-        let _ast: syn::File = unwrap!(syn::parse_str(&source));
+        let mut repr_c = HashMap::new();
+        lint::collect_repr_c(&_ast.items, &mut repr_c);
+        let skipped = lint::skipped_items(&_ast.items, &repr_c);
+        warnings.extend(
+            skipped
+                .into_iter()
+                .map(|warning| warning.with_file(&file_name)),
+        );
+        parse::declare_enums(lang, &_ast.items);
+
+        let mut errors = Vec::new();
 
         for item in _ast.items {
-            match &item {
+            let res = match &item {
                 syn::Item::Mod(ref item) => {
-                    parse::parse_mod(lang, item, &module[..], outputs)?;
-                }
-                syn::Item::Const(ref item) => {
-                    lang.parse_const(item, &module[..], outputs)?;
-                }
-                syn::Item::Type(ref item) => {
-                    lang.parse_ty(item, &module[..], outputs)?;
-                }
-                syn::Item::Enum(ref item) => {
-                    lang.parse_enum(item, &module[..], outputs)?;
-                }
-                syn::Item::Fn(ref item) => {
-                    lang.parse_fn(item, &module[..], outputs)?;
-                }
-                syn::Item::Struct(ref item) => {
-                    lang.parse_struct(item, &module[..], outputs)?;
+                    if let Err(mod_errors) = parse::parse_mod(lang, item, &module[..], outputs) {
+                        errors.extend(mod_errors);
+                    }
+                    Ok(())
                 }
-                _ => {}
+                syn::Item::Const(ref item) => lang
+                    .parse_const(item, &module[..], outputs)
+                    .map_err(|error| error.with_item(&item.ident)),
+                syn::Item::Type(ref item) => lang
+                    .parse_ty(item, &module[..], outputs)
+                    .map_err(|error| error.with_item(&item.ident)),
+                syn::Item::Enum(ref item) => lang
+                    .parse_enum(item, &module[..], outputs)
+                    .map_err(|error| error.with_item(&item.ident)),
+                syn::Item::Fn(ref item) => lang
+                    .parse_fn(item, &module[..], outputs)
+                    .map_err(|error| error.with_item(&item.ident)),
+                syn::Item::Struct(ref item) => lang
+                    .parse_struct(item, &module[..], outputs)
+                    .map_err(|error| error.with_item(&item.ident)),
+                _ => Ok(()),
+            };
+            if let Err(error) = res {
+                errors.push(error);
             }
         }
 
-        Ok(())
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors
+                .into_iter()
+                .map(|error| error.with_file(&file_name))
+                .collect())
+        }
     }
 
     pub fn compile_or_panic<L: Lang>(
@@ -250,7 +470,12 @@ impl Bindgen {
         outputs: &mut Outputs,
         finalise: bool,
     ) {
-        if let Err(errors) = self.compile(lang, outputs, finalise) {
+        let result = self.compile(lang, outputs, finalise);
+        for warning in &self.warnings {
+            self.print_error(warning);
+        }
+
+        if let Err(errors) = result {
             for error in &errors {
                 self.print_error(error);
             }
@@ -260,22 +485,76 @@ impl Bindgen {
     }
 
     /// Writes virtual files to the file system
+    ///
+    /// Files whose contents did not change are left untouched. The generated files are listed in
+    /// a manifest in `root`, which is used to find files generated by earlier runs that are not
+    /// generated any more. These are removed, or reported if `set_remove_stale(false)` was used.
     pub fn write_outputs<P: AsRef<Path>>(&self, root: P, outputs: &Outputs) -> Result<(), IoError> {
         let root = root.as_ref();
 
         for (path, contents) in outputs {
-            let full_path = root.join(PathBuf::from(path));
+            write_if_changed(&root.join(PathBuf::from(path)), contents)?;
+        }
 
-            if let Some(parent_dirs) = full_path.parent() {
-                fs::create_dir_all(parent_dirs)?;
+        let stale = manifest::stale_files(root, outputs)?;
+        for path in &stale {
+            if self.remove_stale {
+                manifest::remove_file(root, path)?;
+            } else {
+                self.print_error(
+                    &Error::warning(&format!("`{}` is not generated any more", path))
+                        .with_file(root.join(path)),
+                );
             }
+        }
 
-            let mut f = fs::File::create(full_path)?;
-            f.write_all(contents.as_bytes())?;
-            f.sync_all()?;
+        let listed = if self.remove_stale {
+            &[][..]
+        } else {
+            &stale[..]
+        };
+        write_if_changed(
+            &root.join(manifest::MANIFEST_FILE),
+            &manifest::render(outputs.keys().chain(listed)),
+        )
+    }
+
+    /// Compares virtual files with the files already written to the file system, without
+    /// writing anything.
+    ///
+    /// Returns a unified diff for every file which is missing or differs, sorted by path, followed
+    /// by one for every file generated by an earlier run which would be removed. An empty list
+    /// means the files on disk are up to date.
+    pub fn check_outputs<P: AsRef<Path>>(
+        &self,
+        root: P,
+        outputs: &Outputs,
+    ) -> Result<Vec<String>, IoError> {
+        let root = root.as_ref();
+        let mut paths: Vec<_> = outputs.keys().collect();
+        paths.sort();
+
+        let mut diffs = Vec::new();
+        for path in paths {
+            let full_path = root.join(PathBuf::from(path));
+            let name = full_path.display().to_string();
+            let (old_name, old) = match fs::read_to_string(&full_path) {
+                Ok(old) => (name.as_str(), Some(old)),
+                Err(ref err) if err.kind() == io::ErrorKind::NotFound => ("/dev/null", None),
+                Err(err) => return Err(err),
+            };
+            let new = Some(outputs[path].as_str());
+            diffs.extend(check::unified_diff(old_name, &name, old.as_deref(), new));
         }
 
-        Ok(())
+        for path in manifest::stale_files(root, outputs)? {
+            let full_path = root.join(path);
+            let old = fs::read_to_string(&full_path)?;
+            let name = full_path.display().to_string();
+            diffs.extend(check::unified_diff(&name, "/dev/null", Some(&old), None));
+        }
+
+        Ok(diffs)
     }
 
     pub fn write_outputs_or_panic<P: AsRef<Path>>(&self, root: P, outputs: &Outputs) {
@@ -288,7 +567,8 @@ impl Bindgen {
     /// Write the header to a file, panicking on error.
     ///
     /// This is a convenience method for use in build scripts. If errors occur during compilation
-    /// they will be printed then the function will panic.
+    /// they will be printed then the function will panic. Otherwise cargo is told which files to
+    /// watch for changes, see `set_rerun_if_changed`.
     ///
     /// # Panics
     ///
@@ -296,13 +576,71 @@ impl Bindgen {
     pub fn run_build<P: AsRef<Path>, L: Lang>(&mut self, lang: &mut L, output_dir: P) {
         let mut outputs = HashMap::new();
         self.compile_or_panic(lang, &mut outputs, true);
+        // A build script which failed is rerun anyway, so this is only needed on success.
+        if self.rerun_if_changed {
+            self.print_rerun_if_changed();
+        }
 
         self.write_outputs_or_panic(output_dir, &outputs);
     }
 
+    /// Prints a `cargo:rerun-if-changed` line for every file the bindings were generated from.
+    fn print_rerun_if_changed(&self) {
+        for file in self.source_files.iter().chain(&self.config.files) {
+            println!("cargo:rerun-if-changed={}", file.display());
+        }
+    }
+
+    /// Check the files in a directory are up to date, panicking on error.
+    ///
+    /// Compiles the bindings like `run_build`, but instead of writing them prints a unified diff
+    /// to stdout for every file that is missing or out of date. Returns whether all files are
+    /// up to date.
+    ///
+    /// # Panics
+    ///
+    /// Panics on any compilation error, or if the existing files cannot be read.
+    pub fn run_check<P: AsRef<Path>, L: Lang>(&mut self, lang: &mut L, output_dir: P) -> bool {
+        let mut outputs = HashMap::new();
+        self.compile_or_panic(lang, &mut outputs, true);
+
+        match self.check_outputs(output_dir, &outputs) {
+            Ok(diffs) => {
+                for diff in &diffs {
+                    print!("{}", diff);
+                }
+                diffs.is_empty()
+            }
+            Err(err) => {
+                self.print_error(&From::from(err));
+                panic!("Failed to read output.");
+            }
+        }
+    }
+
     /// Print an error
+    ///
+    /// Errors are written to stderr in the style of rustc, quoting the offending source line
+    /// where possible, or to stdout as JSON if requested with `set_message_format`.
     pub fn print_error(&self, error: &Error) {
-        error.print();
+        let source = self.error_source(error);
+        match self.message_format {
+            MessageFormat::Human => eprint!("{}", error.render(source.as_deref())),
+            MessageFormat::Json => println!("{}", error.to_json(source.as_deref())),
+        }
+    }
+
+    /// Returns the contents of the source file the error was found in.
+    fn error_source(&self, error: &Error) -> Option<String> {
+        let file = error.file()?;
+        match self.input {
+            // Source passed in as a string doesn't exist on disk.
+            Input::Code {
+                ref file_name,
+                ref code,
+            } if file == Path::new(file_name) => Some(code.clone()),
+            _ => fs::read_to_string(file).ok(),
+        }
     }
 }
 
@@ -326,18 +664,37 @@ fn convert_lib_path_to_module<P: AsRef<Path>>(path: &P) -> Vec<String> {
     res
 }
 
-/// Extract the path to the root source file from a `Cargo.toml`.
-fn source_file_from_cargo() -> Result<String, Error> {
-    let cargo_toml = path::Path::new(
-        &std::env::var_os("CARGO_MANIFEST_DIR").unwrap_or_else(|| std::ffi::OsString::from("")),
-    )
-    .join("Cargo.toml");
-
-    // If no `Cargo.toml` assume `src/lib.rs` until told otherwise.
-    let default = "src/lib.rs";
-    let mut cargo_toml = match std::fs::File::open(&cargo_toml) {
+/// Writes `contents` to the file at `path`, unless it already contains exactly that.
+///
+/// Leaving unchanged files alone keeps their modification times, so that tools depending on the
+/// generated files don't rebuild needlessly.
+fn write_if_changed(path: &Path, contents: &str) -> Result<(), IoError> {
+    match fs::read(path) {
+        Ok(ref existing) if existing == contents.as_bytes() => return Ok(()),
+        _ => {}
+    }
+
+    if let Some(parent_dirs) = path.parent() {
+        fs::create_dir_all(parent_dirs)?;
+    }
+
+    let mut f = fs::File::create(path)?;
+    f.write_all(contents.as_bytes())?;
+    f.sync_all()
+}
+
+/// Directory containing the cargo manifest of the crate being built.
+fn manifest_dir() -> PathBuf {
+    PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap_or_default())
+}
+
+/// Read and parse the `Cargo.toml`, if there is one.
+fn read_cargo_manifest() -> Result<Option<toml::Value>, Error> {
+    let cargo_toml = manifest_dir().join("Cargo.toml");
+
+    let mut cargo_toml = match fs::File::open(&cargo_toml) {
         Ok(value) => value,
-        Err(..) => return Ok(default.to_owned()),
+        Err(..) => return Ok(None),
     };
 
     let mut buf = String::new();
@@ -347,27 +704,107 @@ fn source_file_from_cargo() -> Result<String, Error> {
             return Err(Error {
                 level: Level::Fatal,
                 span: None,
+                file: None,
+                item: None,
                 message: "could not read cargo manifest".into(),
             });
         }
     };
 
-    let table = match (&buf).parse::<toml::Value>() {
-        Ok(value) => value,
-        Err(..) => {
-            return Err(Error {
-                level: Level::Fatal,
-                span: None,
-                message: "could not parse cargo manifest".into(),
-            });
-        }
-    };
+    match (&buf).parse::<toml::Value>() {
+        Ok(value) => Ok(Some(value)),
+        Err(..) => Err(Error {
+            level: Level::Fatal,
+            span: None,
+            file: None,
+            item: None,
+            message: "could not parse cargo manifest".into(),
+        }),
+    }
+}
 
-    // If not explicitly stated then defaults to `src/lib.rs`.
-    Ok(table
-        .get("lib")
+/// Extract the path to the root source file from a `Cargo.toml`.
+fn source_file_from_cargo(manifest: Option<&toml::Value>) -> String {
+    // If no `Cargo.toml` or not explicitly stated then defaults to `src/lib.rs`.
+    manifest
+        .and_then(|table| table.get("lib"))
         .and_then(|t| t.get("path"))
         .and_then(|s| s.as_str())
-        .unwrap_or(default)
-        .into())
+        .unwrap_or("src/lib.rs")
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_files() {
+        let root = env::temp_dir().join("sn_bindgen_source_files");
+        let _ = fs::remove_dir_all(&root);
+        unwrap!(fs::create_dir_all(root.join("src/ffi")));
+        unwrap!(fs::write(root.join("src/lib.rs"), "pub mod ffi;"));
+        unwrap!(fs::write(
+            root.join("src/ffi/mod.rs"),
+            "mod fine;\nmod broken;"
+        ));
+        unwrap!(fs::write(root.join("src/ffi/fine.rs"), ""));
+        unwrap!(fs::write(root.join("src/ffi/broken.rs"), "pub struct;"));
+
+        let mut bindgen = unwrap!(Bindgen::new());
+        let _ = bindgen.source_file(root.join("src/lib.rs"));
+        assert!(bindgen
+            .compile(&mut LangC::new(), &mut Outputs::default(), true)
+            .is_err());
+        assert_eq!(
+            bindgen.source_files(),
+            &[
+                root.join("src/lib.rs"),
+                root.join("src/ffi/mod.rs"),
+                root.join("src/ffi/fine.rs"),
+                root.join("src/ffi/broken.rs"),
+            ][..]
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn inline_roots() {
+        let root = env::temp_dir().join("sn_bindgen_inline_roots");
+        let _ = fs::remove_dir_all(&root);
+        unwrap!(fs::create_dir_all(root.join("src/api/ffi")));
+        unwrap!(fs::write(
+            root.join("src/lib.rs"),
+            "pub mod api {\n\
+             pub mod ffi {\n\
+             #[no_mangle]\n\
+             pub extern \"C\" fn api_version() -> u32 { 1 }\n\
+             pub mod ipc;\n\
+             }\n\
+             }"
+        ));
+        unwrap!(fs::write(
+            root.join("src/api/ffi/ipc.rs"),
+            "#[no_mangle]\npub extern \"C\" fn ipc_reset() {}"
+        ));
+
+        let mut bindgen = unwrap!(Bindgen::new());
+        let _ = bindgen
+            .source_file(root.join("src/lib.rs"))
+            .set_roots(["api::ffi"]);
+        let mut lang = LangC::new();
+        bindgen.configure(&mut lang);
+        lang.set_lib_name("safe_app");
+        let mut outputs = Outputs::default();
+        unwrap!(bindgen.compile(&mut lang, &mut outputs, false));
+
+        let mut headers: Vec<_> = outputs.keys().cloned().collect();
+        headers.sort();
+        assert_eq!(headers, vec!["safe_app/ipc.h", "safe_app/safe_app.h"]);
+        assert!(outputs["safe_app/safe_app.h"].contains("uint32_t api_version(void);"));
+        assert!(outputs["safe_app/ipc.h"].contains("void ipc_reset(void);"));
+
+        let _ = fs::remove_dir_all(&root);
+    }
 }
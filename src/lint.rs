@@ -0,0 +1,271 @@
+//! Detection of items which look like they are meant to be part of the FFI, but are skipped by
+//! the code generators.
+
+use crate::common::{self, check_no_mangle, check_repr_c};
+use crate::Error;
+use std::collections::HashMap;
+use syn::spanned::Spanned;
+
+/// Collects the names of all structs and enums defined in `items`, together with whether they are
+/// `#[repr(C)]`.
+///
+/// A name counts as `#[repr(C)]` if at least one of its definitions is.
+pub fn collect_repr_c(items: &[syn::Item], repr_c: &mut HashMap<String, bool>) {
+    for item in items {
+        let (ident, is_repr_c) = match item {
+            syn::Item::Struct(ref item) => (&item.ident, item.attrs.iter().any(check_repr_c)),
+            // Enums are also exported with a primitive representation, e.g. `#[repr(u8)]`.
+            syn::Item::Enum(ref item) => (&item.ident, common::enum_repr(&item.attrs).is_defined()),
+            syn::Item::Mod(ref item) => {
+                if let Some((_, ref items)) = item.content {
+                    collect_repr_c(items, repr_c);
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        *repr_c.entry(ident.to_string()).or_insert(false) |= is_repr_c;
+    }
+}
+
+/// Returns warnings for the public items of a module which are skipped although they look like
+/// they are meant to be exported.
+///
+/// `repr_c` holds all structs and enums of the crate, as collected by `collect_repr_c`.
+pub fn skipped_items(items: &[syn::Item], repr_c: &HashMap<String, bool>) -> Vec<Error> {
+    let mut warnings = Vec::new();
+
+    for item in items {
+        match item {
+            syn::Item::Fn(ref item) if is_public(&item.vis) => {
+                let no_mangle = item.attrs.iter().any(check_no_mangle);
+                let c_abi = item.abi.iter().any(|abi| common::is_extern(abi.clone()));
+
+                if c_abi && !no_mangle {
+                    warnings.push(
+                        Error::warning(&format!(
+                            "function `{}` is skipped because it is not `#[no_mangle]`",
+                            item.ident
+                        ))
+                        .with_item(&item.ident),
+                    );
+                } else if no_mangle && !c_abi {
+                    warnings.push(
+                        Error::warning(&format!(
+                            "function `{}` is skipped because it does not have a C ABI \
+                             (declare it as `extern \"C\"`)",
+                            item.ident
+                        ))
+                        .with_item(&item.ident),
+                    );
+                } else if no_mangle && c_abi {
+                    for ty in signature_types(&item.decl.inputs, &item.decl.output) {
+                        check_repr_c_type(ty, &item.ident, repr_c, &mut warnings);
+                    }
+                }
+            }
+            syn::Item::Type(ref item)
+                if is_public(&item.vis) && !item.generics.params.is_empty() =>
+            {
+                warnings.push(
+                    Error::warning(&format!(
+                        "type alias `{}` is skipped because it has generic parameters",
+                        item.ident
+                    ))
+                    .with_item(&item.ident),
+                );
+            }
+            syn::Item::Mod(ref item) if is_public(&item.vis) => {
+                if let Some((_, ref items)) = item.content {
+                    warnings.extend(skipped_items(items, repr_c));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    warnings
+}
+
+/// Checks the visibility is public to the outside of the crate (the same rule the code
+/// generators apply).
+pub(crate) fn is_public(vis: &syn::Visibility) -> bool {
+    !matches!(vis, syn::Visibility::Inherited | syn::Visibility::Crate(_))
+}
+
+/// Returns the types of the arguments and the return type of a function.
+fn signature_types<'a>(
+    inputs: impl IntoIterator<Item = &'a syn::FnArg>,
+    output: &'a syn::ReturnType,
+) -> Vec<&'a syn::Type> {
+    let mut types: Vec<_> = inputs
+        .into_iter()
+        .filter_map(common::transform_fnarg_to_argcap)
+        .map(|arg| &arg.ty)
+        .collect();
+    if let syn::ReturnType::Type(_, ref ty) = output {
+        types.push(ty);
+    }
+    types
+}
+
+/// Warns about structs and enums which are not `#[repr(C)]`, but are passed by value (directly
+/// or as array elements) by the type `ty` used in the signature of the function `function`.
+///
+/// Pointers to such types are not reported, as they are used as opaque handles (e.g.
+/// `*mut App`).
+fn check_repr_c_type(
+    ty: &syn::Type,
+    function: &syn::Ident,
+    repr_c: &HashMap<String, bool>,
+    warnings: &mut Vec<Error>,
+) {
+    match ty {
+        syn::Type::Array(ref array) => check_repr_c_type(&array.elem, function, repr_c, warnings),
+        syn::Type::Paren(ref paren) => check_repr_c_type(&paren.elem, function, repr_c, warnings),
+        // Callbacks passed to the function.
+        syn::Type::BareFn(ref bare_fn) => {
+            let inputs = bare_fn.inputs.iter().map(|arg| &arg.ty);
+            let output = match bare_fn.output {
+                syn::ReturnType::Type(_, ref ty) => Some(&**ty),
+                syn::ReturnType::Default => None,
+            };
+            for ty in inputs.chain(output) {
+                check_repr_c_type(ty, function, repr_c, warnings);
+            }
+        }
+        syn::Type::Path(ref path) if path.path.segments.len() == 1 => {
+            let name = path.path.segments[0].ident.to_string();
+            if repr_c.get(&name) == Some(&false) {
+                warnings.push(
+                    Error::warning(&format!(
+                        "type `{}` used by function `{}` is skipped because it is not \
+                         `#[repr(C)]`",
+                        name, function
+                    ))
+                    .with_span(ty.span())
+                    .with_item(function),
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Outputs;
+    use crate::{Bindgen, LangC, Level};
+    use std::path::Path;
+    use unwrap::unwrap;
+
+    #[test]
+    fn skipped() {
+        let source = "
+            #[repr(C)]
+            pub struct Good;
+            pub struct Bad;
+            pub enum AlsoBad { A }
+            #[repr(u8)]
+            pub enum Small { A }
+
+            pub extern \"C\" fn not_mangled() {}
+            #[no_mangle]
+            pub fn not_extern() {}
+            #[no_mangle]
+            pub extern \"C\" fn exported(
+                a: *const Good, b: *mut Bad, cb: extern \"C\" fn(AlsoBad), c: Small,
+                d: [Bad; 2], e: &AlsoBad,
+            ) {}
+            pub fn rust_only(b: Bad) {}
+            extern \"C\" fn private() {}
+
+            pub type Generic<T> = *mut T;
+            pub type NotGeneric = *mut Good;
+
+            pub mod nested {
+                pub extern fn not_mangled_either() -> Bad {}
+            }
+        ";
+        let ast: syn::File = unwrap!(syn::parse_str(source));
+
+        let mut repr_c = HashMap::new();
+        collect_repr_c(&ast.items, &mut repr_c);
+        let warnings: Vec<_> = skipped_items(&ast.items, &repr_c)
+            .into_iter()
+            .map(|warning| (unwrap!(warning.item()).to_string(), warning.message))
+            .collect();
+
+        assert_eq!(
+            warnings,
+            vec![
+                (
+                    "not_mangled".to_string(),
+                    "function `not_mangled` is skipped because it is not `#[no_mangle]`"
+                        .to_string()
+                ),
+                (
+                    "not_extern".to_string(),
+                    "function `not_extern` is skipped because it does not have a C ABI \
+                     (declare it as `extern \"C\"`)"
+                        .to_string()
+                ),
+                (
+                    "exported".to_string(),
+                    "type `AlsoBad` used by function `exported` is skipped because it is not \
+                     `#[repr(C)]`"
+                        .to_string()
+                ),
+                (
+                    "exported".to_string(),
+                    "type `Bad` used by function `exported` is skipped because it is not \
+                     `#[repr(C)]`"
+                        .to_string()
+                ),
+                (
+                    "Generic".to_string(),
+                    "type alias `Generic` is skipped because it has generic parameters".to_string()
+                ),
+                (
+                    "not_mangled_either".to_string(),
+                    "function `not_mangled_either` is skipped because it is not `#[no_mangle]`"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn strict() {
+        let source = "
+            #[no_mangle]
+            pub extern \"C\" fn exported() {}
+            pub extern \"C\" fn not_mangled() {}
+        ";
+        let mut bindgen = unwrap!(Bindgen::new());
+        let _ = bindgen.source_code("src/ffi.rs", source);
+
+        let mut outputs = Outputs::default();
+        unwrap!(bindgen.compile(&mut LangC::new(), &mut outputs, true));
+        assert_eq!(bindgen.warnings().len(), 1);
+        assert!(outputs
+            .values()
+            .any(|output| output.contains("void exported(void);")));
+
+        let _ = bindgen.set_strict(true);
+        let mut outputs = Outputs::default();
+        let errors = match bindgen.compile(&mut LangC::new(), &mut outputs, true) {
+            Ok(()) => panic!("strict mode should turn warnings into errors"),
+            Err(errors) => errors,
+        };
+        assert!(bindgen.warnings().is_empty());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].item(), Some("not_mangled"));
+        assert_eq!(errors[0].file(), Some(Path::new("src/ffi.rs")));
+        match errors[0].level {
+            Level::Error => {}
+            level => panic!("unexpected level {}", level),
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! List of the files generated into an output directory.
+//!
+//! The manifest is stored next to the generated files so that files which are no longer
+//! generated (e.g. for a deleted struct) can be found and removed by the next run.
+
+use crate::common::Outputs;
+use std::fs;
+use std::io::{self, Error as IoError};
+use std::path::{Component, Path};
+
+/// Name of the manifest file within the output directory.
+pub const MANIFEST_FILE: &str = ".bindgen-manifest";
+
+const HEADER: &str = "# Files generated by sn_bindgen. Do not edit.";
+
+/// Renders the manifest listing the given files.
+pub fn render<'a>(paths: impl IntoIterator<Item = &'a String>) -> String {
+    let mut paths: Vec<_> = paths.into_iter().collect();
+    paths.sort();
+    paths.dedup();
+
+    let mut output = format!("{}\n", HEADER);
+    for path in paths {
+        output.push_str(path);
+        output.push('\n');
+    }
+    output
+}
+
+/// Returns the files listed in the manifest of the output directory `root` which are not part
+/// of `outputs`, but still exist.
+///
+/// Paths which point outside of `root` are ignored, so that a tampered manifest can never cause
+/// unrelated files to be removed.
+pub fn stale_files(root: &Path, outputs: &Outputs) -> Result<Vec<String>, IoError> {
+    let contents = match fs::read_to_string(root.join(MANIFEST_FILE)) {
+        Ok(contents) => contents,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+
+    Ok(contents
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter(|path| is_within_root(path) && !outputs.contains_key(*path))
+        .filter(|path| root.join(path).is_file())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Removes a stale file, together with the directories containing it if they become empty.
+pub fn remove_file(root: &Path, path: &str) -> Result<(), IoError> {
+    let full_path = root.join(path);
+    fs::remove_file(&full_path)?;
+
+    let mut dir = full_path.parent();
+    while let Some(path) = dir {
+        if path == root || fs::remove_dir(path).is_err() {
+            break;
+        }
+        dir = path.parent();
+    }
+    Ok(())
+}
+
+/// Checks the relative path does not lead outside of the directory it is relative to.
+fn is_within_root(path: &str) -> bool {
+    Path::new(path)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Bindgen;
+    use std::env;
+    use std::thread;
+    use std::time::Duration;
+    use unwrap::unwrap;
+
+    fn outputs(files: &[(&str, &str)]) -> Outputs {
+        files
+            .iter()
+            .map(|&(path, contents)| (path.to_string(), contents.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn write_outputs() {
+        let root = env::temp_dir().join("sn_bindgen_manifest_write_outputs");
+        let _ = fs::remove_dir_all(&root);
+        unwrap!(fs::create_dir_all(&root));
+        unwrap!(fs::write(root.join("unrelated.txt"), "keep me"));
+
+        let mut bindgen = unwrap!(Bindgen::new());
+        unwrap!(bindgen.write_outputs(
+            &root,
+            &outputs(&[("a.h", "int a;\n"), ("nested/dir/b.h", "int b;\n")]),
+        ));
+        assert_eq!(
+            unwrap!(fs::read_to_string(root.join(MANIFEST_FILE))),
+            format!("{}\na.h\nnested/dir/b.h\n", HEADER)
+        );
+
+        // Unchanged files are not rewritten.
+        let modified = unwrap!(unwrap!(fs::metadata(root.join("a.h"))).modified());
+        thread::sleep(Duration::from_millis(10));
+        unwrap!(bindgen.write_outputs(&root, &outputs(&[("a.h", "int a;\n"), ("c.h", "")])));
+        assert_eq!(
+            unwrap!(unwrap!(fs::metadata(root.join("a.h"))).modified()),
+            modified
+        );
+
+        // Files from the previous run are removed, together with their empty directories.
+        assert!(!root.join("nested").exists());
+        assert!(root.join("c.h").exists());
+        assert!(root.join("unrelated.txt").exists());
+
+        // Stale files can be kept, in which case they remain listed in the manifest.
+        let _ = bindgen.set_remove_stale(false);
+        unwrap!(bindgen.write_outputs(&root, &outputs(&[("a.h", "int a;\n")])));
+        assert!(root.join("c.h").exists());
+        assert_eq!(
+            unwrap!(stale_files(&root, &outputs(&[("a.h", "int a;\n")]))),
+            vec!["c.h"]
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn outside_root() {
+        let root = env::temp_dir().join("sn_bindgen_manifest_outside_root");
+        let _ = fs::remove_dir_all(&root);
+        unwrap!(fs::create_dir_all(root.join("out")));
+        unwrap!(fs::write(root.join("precious.h"), ""));
+        unwrap!(fs::write(
+            root.join("out").join(MANIFEST_FILE),
+            "../precious.h\n/etc/passwd\n"
+        ));
+
+        assert!(unwrap!(stale_files(&root.join("out"), &Outputs::default())).is_empty());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -1,45 +1,10 @@
 //! Functions for actually parsing the source file.
 
 use crate::common::{Lang, Outputs};
+use crate::lint::is_public;
 use crate::Error;
 use unwrap::unwrap;
 
-pub fn parse_usetree(usetree: &syn::UseTree) -> Vec<String> {
-    let mut modules = Vec::new();
-
-    if let syn::UseTree::Path(ref path) = usetree {
-        modules.push(path.ident.to_owned().to_string());
-        for x in parse_usetree(&*path.tree) {
-            modules.push(x);
-        }
-    };
-    if let syn::UseTree::Name(ref name) = usetree {
-        modules.push(name.ident.to_owned().to_string());
-    };
-    if let syn::UseTree::Glob(ref _glob) = usetree {
-        return modules;
-    };
-    modules
-}
-
-/// Returns a list of FFI submodules imported in a top-level module
-pub fn imported_mods(import: &syn::ItemUse) -> Option<Vec<String>> {
-    // If it's not visible it can't be called from C.
-    if let syn::Visibility::Inherited = import.vis {
-        None
-    } else {
-        let mut segments = parse_usetree(&import.tree);
-        if segments[0] == "crate" {
-            segments.remove(0);
-        }
-        if &segments[0] == "ffi" {
-            Some(segments)
-        } else {
-            None
-        }
-    }
-}
-
 /// The manager of bindgen and entry point when the crate is the module.
 ///
 /// Iterates through all items in the module and dispatches to correct methods, then pulls all
@@ -109,29 +74,26 @@ pub fn parse_file<L: Lang>(
         // Dispatch to correct method.
         let res = match item {
             syn::Item::Mod(ref item) => {
-                parse_mod(lang, item, mod_path, outputs)?;
-                Ok(())
-            }
-            syn::Item::Const(ref item) => {
-                lang.parse_const(item, mod_path, outputs)?;
-                Ok(())
-            }
-            syn::Item::Type(ref item) => {
-                lang.parse_ty(item, mod_path, outputs)?;
-                Ok(())
-            }
-            syn::Item::Enum(ref item) => {
-                lang.parse_enum(item, mod_path, outputs)?;
-                Ok(())
-            }
-            syn::Item::Fn(ref item) => {
-                lang.parse_fn(item, mod_path, outputs)?;
-                Ok(())
-            }
-            syn::Item::Struct(ref item) => {
-                lang.parse_struct(&item, mod_path, outputs)?;
+                if let Err(mod_errors) = parse_mod(lang, item, mod_path, outputs) {
+                    errors.extend(mod_errors);
+                }
                 Ok(())
             }
+            syn::Item::Const(ref item) => lang
+                .parse_const(item, mod_path, outputs)
+                .map_err(|error| error.with_item(&item.ident)),
+            syn::Item::Type(ref item) => lang
+                .parse_ty(item, mod_path, outputs)
+                .map_err(|error| error.with_item(&item.ident)),
+            syn::Item::Enum(ref item) => lang
+                .parse_enum(item, mod_path, outputs)
+                .map_err(|error| error.with_item(&item.ident)),
+            syn::Item::Fn(ref item) => lang
+                .parse_fn(item, mod_path, outputs)
+                .map_err(|error| error.with_item(&item.ident)),
+            syn::Item::Struct(ref item) => lang
+                .parse_struct(&item, mod_path, outputs)
+                .map_err(|error| error.with_item(&item.ident)),
             _ => Ok(()),
         };
 
@@ -147,6 +109,22 @@ pub fn parse_file<L: Lang>(
     }
 }
 
+/// Declares the public enums of the module and of its public inline modules, ahead of
+/// `parse_file`.
+pub fn declare_enums<L: Lang>(lang: &mut L, items: &[syn::Item]) {
+    for item in items {
+        match item {
+            syn::Item::Enum(ref item) if is_public(&item.vis) => lang.declare_enum(item),
+            syn::Item::Mod(ref item) if is_public(&item.vis) => {
+                if let Some((_, ref items)) = item.content {
+                    declare_enums(lang, items);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
 pub fn parse_mod<L: Lang>(
     lang: &mut L,
     module: &syn::ItemMod,
@@ -212,29 +190,26 @@ pub fn parse_mod<L: Lang>(
             // Dispatch to correct method.
             let res = match item {
                 syn::Item::Mod(ref item) => {
-                    parse_mod(lang, item, mod_path, outputs)?;
-                    Ok(())
-                }
-                syn::Item::Const(ref item) => {
-                    lang.parse_const(item, mod_path, outputs)?;
-                    Ok(())
-                }
-                syn::Item::Type(ref item) => {
-                    lang.parse_ty(item, mod_path, outputs)?;
-                    Ok(())
-                }
-                syn::Item::Enum(ref item) => {
-                    lang.parse_enum(item, mod_path, outputs)?;
-                    Ok(())
-                }
-                syn::Item::Fn(ref item) => {
-                    lang.parse_fn(item, mod_path, outputs)?;
-                    Ok(())
-                }
-                syn::Item::Struct(ref item) => {
-                    lang.parse_struct(&item, mod_path, outputs)?;
+                    if let Err(mod_errors) = parse_mod(lang, item, mod_path, outputs) {
+                        errors.extend(mod_errors);
+                    }
                     Ok(())
                 }
+                syn::Item::Const(ref item) => lang
+                    .parse_const(item, mod_path, outputs)
+                    .map_err(|error| error.with_item(&item.ident)),
+                syn::Item::Type(ref item) => lang
+                    .parse_ty(item, mod_path, outputs)
+                    .map_err(|error| error.with_item(&item.ident)),
+                syn::Item::Enum(ref item) => lang
+                    .parse_enum(item, mod_path, outputs)
+                    .map_err(|error| error.with_item(&item.ident)),
+                syn::Item::Fn(ref item) => lang
+                    .parse_fn(item, mod_path, outputs)
+                    .map_err(|error| error.with_item(&item.ident)),
+                syn::Item::Struct(ref item) => lang
+                    .parse_struct(&item, mod_path, outputs)
+                    .map_err(|error| error.with_item(&item.ident)),
                 _ => Ok(()),
             };
 
@@ -0,0 +1,458 @@
+//! Resolution of the crate module tree.
+//!
+//! Starting from the root source file, follows every `mod foo;` declaration to the file backing
+//! it (`foo.rs`, `foo/mod.rs` or the file named by a `#[path = "..."]` attribute). Only the
+//! modules leading to the FFI roots and the ones located within them are visited, so that the
+//! rest of the crate doesn't have to be parsable by bindgen.
+//!
+//! Inline modules (`mod foo { ... }`) outside of the roots get a module of their own, so that a
+//! root can be declared inline. Inline modules within a root are parsed as part of the module
+//! containing them.
+
+use crate::{Error, Level};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use unwrap::unwrap;
+
+/// A source file backing a module of the crate.
+pub struct SourceModule {
+    /// Path of the module relative to the crate root (e.g. `["ffi", "ipc"]`).
+    /// Empty for the crate root itself.
+    pub path: Vec<String>,
+    /// Location of the source file (the file containing the module if it is inline).
+    pub file: PathBuf,
+    /// Parsed contents of the source file, or of the inline module.
+    pub ast: syn::File,
+}
+
+/// Returns the modules of the crate rooted at `root` which are either located within one of the
+/// FFI `roots` or are ancestors of one, in depth-first declaration order.
+///
+/// The first entry is always the crate root. Modules which cannot be found, read or parsed are
+/// skipped (together with their submodules) and reported as errors, so that a single run reports
+/// every broken file. They are reported as warnings instead if they are gated by `#[cfg]`.
+pub fn resolve_modules(
+    root: &Path,
+    roots: &[Vec<String>],
+) -> (Vec<SourceModule>, Vec<Error>, Vec<Error>) {
+    let ast = match parse_source_file(root) {
+        Ok(ast) => ast,
+        Err(error) => return (Vec::new(), vec![error], Vec::new()),
+    };
+    let dir = unwrap!(root.parent()).to_path_buf();
+    let items = ast.items.clone();
+
+    let mut resolver = Resolver {
+        roots,
+        modules: vec![SourceModule {
+            path: Vec::new(),
+            file: root.to_path_buf(),
+            ast,
+        }],
+        errors: Vec::new(),
+        warnings: Vec::new(),
+        files: vec![canonical(root)],
+    };
+    resolver.resolve_items(&items, root, &dir, &dir, &[]);
+
+    (resolver.modules, resolver.errors, resolver.warnings)
+}
+
+/// Checks whether the module is located within one of the given roots, or contains one of them.
+fn leads_to_roots(module: &[String], roots: &[Vec<String>]) -> bool {
+    in_roots(module, roots) || roots.iter().any(|root| root.starts_with(module))
+}
+
+/// Checks whether the module is located within one of the given roots.
+pub fn in_roots(module: &[String], roots: &[Vec<String>]) -> bool {
+    roots
+        .iter()
+        .any(|root| !root.is_empty() && module.starts_with(root))
+}
+
+/// Splits a module path (e.g. `api::ffi` or `crate::api::ffi`) into its segments.
+pub fn split_module_path(path: &str) -> Vec<String> {
+    let mut segments: Vec<String> = path
+        .split("::")
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_string)
+        .collect();
+    if segments.first().map(String::as_str) == Some("crate") {
+        segments.remove(0);
+    }
+    segments
+}
+
+/// Collects the modules visited while walking through the crate.
+struct Resolver<'a> {
+    roots: &'a [Vec<String>],
+    modules: Vec<SourceModule>,
+    errors: Vec<Error>,
+    warnings: Vec<Error>,
+    /// Files of the module being resolved and of its ancestors, to detect `#[path]` cycles.
+    files: Vec<PathBuf>,
+}
+
+impl<'a> Resolver<'a> {
+    /// Reports a module which can't be resolved. Modules gated by `#[cfg]` are skipped with a
+    /// warning instead, as they might not be part of the current configuration.
+    fn report(&mut self, mut error: Error, cfg_gated: bool) {
+        if cfg_gated {
+            error.level = Level::Warning;
+            error.message = format!("{} (skipping the `#[cfg]`-gated module)", error.message);
+            self.warnings.push(error);
+        } else {
+            self.errors.push(error);
+        }
+    }
+
+    /// Walks through `items` of the source file `file`, resolving module declarations relative to
+    /// `dir`.
+    ///
+    /// `path_dir` is the directory `#[path]` attributes are relative to, which differs from `dir`
+    /// for modules declared at the top level of a non-`mod.rs` file.
+    fn resolve_items(
+        &mut self,
+        items: &[syn::Item],
+        file: &Path,
+        dir: &Path,
+        path_dir: &Path,
+        mod_path: &[String],
+    ) {
+        for item in items {
+            let item = match item {
+                syn::Item::Mod(ref item) => item,
+                _ => continue,
+            };
+            if is_cfg_test(&item.attrs) {
+                continue;
+            }
+            // Modules which are only compiled in some configurations might not exist in others.
+            let cfg_gated = item.attrs.iter().any(|attr| attr.path.is_ident("cfg"));
+
+            let name = module_name(&item.ident);
+            let mut child_path = mod_path.to_vec();
+            child_path.push(name.clone());
+            if !leads_to_roots(&child_path, self.roots) {
+                continue;
+            }
+
+            match item.content {
+                // Inline module: nested declarations are looked up in a subdirectory.
+                Some((_, ref items)) => {
+                    if !in_roots(mod_path, self.roots) {
+                        self.modules.push(SourceModule {
+                            path: child_path.clone(),
+                            file: file.to_path_buf(),
+                            ast: syn::File {
+                                shebang: None,
+                                attrs: Vec::new(),
+                                items: items.clone(),
+                            },
+                        });
+                    }
+                    let child_dir = dir.join(&name);
+                    self.resolve_items(items, file, &child_dir, &child_dir, &child_path);
+                }
+                None => {
+                    let (child_file, child_dir) = match path_attr(&item.attrs) {
+                        // Files named with `#[path]` behave like `mod.rs` files.
+                        Some(path) => {
+                            let child_file = path_dir.join(path);
+                            let child_dir = unwrap!(child_file.parent()).to_path_buf();
+                            (child_file, child_dir)
+                        }
+                        None => {
+                            let flat = dir.join(format!("{}.rs", name));
+                            let nested = dir.join(&name).join("mod.rs");
+                            if flat.exists() {
+                                (flat, dir.join(&name))
+                            } else if nested.exists() {
+                                (nested, dir.join(&name))
+                            } else {
+                                self.report(
+                                    Error {
+                                        level: Level::Error,
+                                        span: Some(item.ident.span()),
+                                        file: Some(file.to_path_buf()),
+                                        item: None,
+                                        message: format!(
+                                        "file not found for module `{}` (looked for `{}` and `{}`)",
+                                        child_path.join("::"),
+                                        flat.display(),
+                                        nested.display()
+                                    ),
+                                    },
+                                    cfg_gated,
+                                );
+                                continue;
+                            }
+                        }
+                    };
+
+                    let canonical_file = canonical(&child_file);
+                    if self.files.contains(&canonical_file) {
+                        self.report(
+                            Error {
+                                level: Level::Error,
+                                span: Some(item.ident.span()),
+                                file: Some(file.to_path_buf()),
+                                item: None,
+                                message: format!(
+                                    "module `{}` includes `{}`, which contains the module itself",
+                                    child_path.join("::"),
+                                    child_file.display()
+                                ),
+                            },
+                            cfg_gated,
+                        );
+                        continue;
+                    }
+
+                    let ast = match parse_source_file(&child_file) {
+                        Ok(ast) => ast,
+                        Err(error) => {
+                            self.report(error, cfg_gated);
+                            continue;
+                        }
+                    };
+                    let file_dir = unwrap!(child_file.parent()).to_path_buf();
+                    let items = ast.items.clone();
+
+                    self.modules.push(SourceModule {
+                        path: child_path.clone(),
+                        file: child_file.clone(),
+                        ast,
+                    });
+                    self.files.push(canonical_file);
+                    self.resolve_items(&items, &child_file, &child_dir, &file_dir, &child_path);
+                    let _ = self.files.pop();
+                }
+            }
+        }
+    }
+}
+
+/// Returns the canonical form of an existing path, so that files can be compared.
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn parse_source_file(path: &Path) -> Result<syn::File, Error> {
+    let mut content = String::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut content))
+        .map_err(|err| Error::unreadable_file(path, &err))?;
+    syn::parse_file(&content).map_err(|err| Error::unparsable_file(path, &err))
+}
+
+/// Returns the module name with a possible raw identifier prefix (`r#`) removed.
+fn module_name(ident: &syn::Ident) -> String {
+    let name = ident.to_string();
+    name.trim_start_matches("r#").to_string()
+}
+
+/// Extracts the value of a `#[path = "..."]` attribute, if present.
+fn path_attr(attrs: &[syn::Attribute]) -> Option<String> {
+    attrs.iter().find_map(|attr| match attr.parse_meta() {
+        Ok(syn::Meta::NameValue(ref meta)) if meta.ident == "path" => match meta.lit {
+            syn::Lit::Str(ref path) => Some(path.value()),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// Checks whether the item is only compiled for tests, e.g. `#[cfg(test)]` or
+/// `#[cfg(all(test, feature = "..."))]`.
+fn is_cfg_test(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attr| match attr.parse_meta() {
+        Ok(syn::Meta::List(ref list)) if list.ident == "cfg" => {
+            list.nested.iter().any(requires_test)
+        }
+        _ => false,
+    })
+}
+
+/// Checks whether a `cfg` predicate can only be true when compiling tests.
+fn requires_test(predicate: &syn::NestedMeta) -> bool {
+    match *predicate {
+        syn::NestedMeta::Meta(syn::Meta::Word(ref ident)) => ident == "test",
+        syn::NestedMeta::Meta(syn::Meta::List(ref list)) if list.ident == "all" => {
+            list.nested.iter().any(requires_test)
+        }
+        syn::NestedMeta::Meta(syn::Meta::List(ref list)) if list.ident == "any" => {
+            !list.nested.is_empty() && list.nested.iter().all(requires_test)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    fn write_file(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        unwrap!(fs::create_dir_all(unwrap!(path.parent())));
+        unwrap!(fs::write(path, content));
+    }
+
+    #[test]
+    fn module_tree() {
+        let root = env::temp_dir().join("sn_bindgen_resolve_module_tree");
+        let _ = fs::remove_dir_all(&root);
+
+        write_file(
+            &root,
+            "src/lib.rs",
+            "pub mod api; mod capi; #[cfg(test)] mod tests; mod inline { pub mod nested; }\n\
+             mod unrelated; mod missing; mod inline_unrelated { mod missing; }",
+        );
+        write_file(&root, "src/api/mod.rs", "pub mod ffi;");
+        write_file(
+            &root,
+            "src/api/ffi.rs",
+            "pub mod ipc; #[path = \"../custom/location.rs\"] mod other;\n\
+             #[cfg(all(test, unix))] mod unix_tests;\n\
+             #[cfg(feature = \"extra\")] mod extra;",
+        );
+        write_file(&root, "src/api/ffi/ipc.rs", "");
+        write_file(&root, "src/custom/location.rs", "");
+        write_file(&root, "src/capi.rs", "");
+        write_file(&root, "src/inline/nested.rs", "");
+        // Modules outside the roots are not parsed, even if bindgen can't parse them.
+        write_file(&root, "src/unrelated.rs", "pub struct;");
+
+        let roots = vec![
+            split_module_path("api::ffi"),
+            split_module_path("capi"),
+            split_module_path("inline::nested"),
+        ];
+        let (modules, errors, warnings) = resolve_modules(&root.join("src/lib.rs"), &roots);
+        assert!(errors.is_empty());
+        // The missing `#[cfg]`-gated module is skipped.
+        assert_eq!(warnings.len(), 1);
+        assert!(matches!(warnings[0].level, Level::Warning));
+        assert!(warnings[0].message.contains("api::ffi::extra"));
+        let paths: Vec<_> = modules.iter().map(|m| m.path.join("::")).collect();
+
+        assert_eq!(
+            paths,
+            vec![
+                "",
+                "api",
+                "api::ffi",
+                "api::ffi::ipc",
+                "api::ffi::other",
+                "capi",
+                "inline",
+                "inline::nested",
+            ]
+        );
+        assert_eq!(modules[4].file, root.join("src/api/../custom/location.rs"));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn broken_modules() {
+        let root = env::temp_dir().join("sn_bindgen_resolve_broken_modules");
+        let _ = fs::remove_dir_all(&root);
+
+        write_file(&root, "src/lib.rs", "mod ffi;");
+        write_file(
+            &root,
+            "src/ffi/mod.rs",
+            "mod missing;\nmod broken;\nmod fine;",
+        );
+        write_file(&root, "src/ffi/broken.rs", "pub const X u8 = 1;");
+        write_file(&root, "src/ffi/fine.rs", "mod also_broken;");
+        write_file(&root, "src/ffi/fine/also_broken.rs", "pub struct;");
+
+        let (modules, errors, _) =
+            resolve_modules(&root.join("src/lib.rs"), &[vec!["ffi".to_string()]]);
+        let paths: Vec<_> = modules.iter().map(|m| m.path.join("::")).collect();
+        assert_eq!(paths, vec!["", "ffi", "ffi::fine"]);
+
+        let locations: Vec<_> = errors
+            .iter()
+            .map(|e| (unwrap!(e.file()).to_path_buf(), unwrap!(e.line_column())))
+            .collect();
+        assert_eq!(
+            locations,
+            vec![
+                (root.join("src/ffi/mod.rs"), (1, 5)),
+                (root.join("src/ffi/broken.rs"), (1, 13)),
+                (root.join("src/ffi/fine/also_broken.rs"), (1, 11)),
+            ]
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn inline_modules() {
+        let root = env::temp_dir().join("sn_bindgen_resolve_inline_modules");
+        let _ = fs::remove_dir_all(&root);
+
+        write_file(
+            &root,
+            "src/lib.rs",
+            "pub mod ffi { pub mod nested {} pub mod ipc; }\n\
+             mod api { pub mod capi { pub fn f() {} } }",
+        );
+        write_file(&root, "src/ffi/ipc.rs", "");
+
+        let roots = vec![split_module_path("ffi"), split_module_path("api::capi")];
+        let (modules, errors, _) = resolve_modules(&root.join("src/lib.rs"), &roots);
+        assert!(errors.is_empty());
+        let paths: Vec<_> = modules.iter().map(|m| m.path.join("::")).collect();
+
+        // `ffi::nested` is parsed as part of `ffi`.
+        assert_eq!(paths, vec!["", "ffi", "ffi::ipc", "api", "api::capi"]);
+        assert_eq!(modules[1].file, root.join("src/lib.rs"));
+        assert_eq!(modules[1].ast.items.len(), 2);
+        assert_eq!(modules[4].ast.items.len(), 1);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn path_cycles() {
+        let root = env::temp_dir().join("sn_bindgen_resolve_path_cycles");
+        let _ = fs::remove_dir_all(&root);
+
+        write_file(&root, "src/lib.rs", "mod ffi;");
+        write_file(&root, "src/ffi.rs", "#[path = \"other.rs\"] mod other;");
+        write_file(&root, "src/other.rs", "#[path = \"ffi.rs\"] mod again;");
+
+        let (modules, errors, _) =
+            resolve_modules(&root.join("src/lib.rs"), &[vec!["ffi".to_string()]]);
+        let paths: Vec<_> = modules.iter().map(|m| m.path.join("::")).collect();
+        assert_eq!(paths, vec!["", "ffi", "ffi::other"]);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("`ffi::other::again`"));
+        assert_eq!(errors[0].file(), Some(root.join("src/other.rs").as_path()));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn roots() {
+        let roots = vec![
+            split_module_path("crate::api::ffi"),
+            split_module_path("capi"),
+        ];
+
+        assert!(in_roots(&split_module_path("api::ffi"), &roots));
+        assert!(in_roots(&split_module_path("api::ffi::ipc"), &roots));
+        assert!(in_roots(&split_module_path("capi"), &roots));
+        assert!(!in_roots(&split_module_path("api"), &roots));
+        assert!(!in_roots(&split_module_path("ffi"), &roots));
+    }
+}
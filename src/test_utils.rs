@@ -2,7 +2,11 @@ use crate::common::{Lang, Outputs};
 use crate::{parse, Error};
 use colored::*;
 use std::collections::HashMap;
+use std::env;
 use std::fmt::Write;
+use std::fs;
+use std::io::ErrorKind;
+use std::process::Command;
 use unwrap::unwrap;
 
 macro_rules! compile {
@@ -53,6 +57,7 @@ pub fn try_compile(
 ) -> Result<HashMap<String, String>, Vec<Error>> {
     let ast: syn::File = unwrap!(syn::parse_str(&rust_src));
     let mut outputs = Outputs::default();
+    parse::declare_enums(&mut lang, &ast.items);
     parse::parse_file(&mut lang, &ast, &[Default::default()], &mut outputs)?;
     lang.finalise_output(&mut outputs)?;
 
@@ -78,3 +83,37 @@ pub fn format_diff(left: &str, right: &str) -> String {
 pub fn fetch<'a>(outputs: &'a HashMap<String, String>, name: &str) -> &'a str {
     outputs.get(name).map(String::as_str).unwrap_or("")
 }
+
+/// Check that `source` is accepted by `compiler` when run with `flags`. The check is skipped
+/// when the compiler is not installed.
+pub fn assert_compiles(compiler: &str, flags: &[&str], name: &str, source: &str) {
+    let path = env::temp_dir().join(format!("sn_bindgen_{}", name));
+    unwrap!(fs::write(&path, source));
+
+    let output = match Command::new(compiler)
+        .args(flags)
+        .arg("-fsyntax-only")
+        .arg(&path)
+        .output()
+    {
+        Ok(output) => output,
+        Err(ref e) if e.kind() == ErrorKind::NotFound => {
+            eprintln!(
+                "skipping the {} check: `{}` is not installed",
+                name, compiler
+            );
+            return;
+        }
+        Err(e) => panic!("failed to run `{}`: {}", compiler, e),
+    };
+
+    if !output.status.success() {
+        panic!(
+            "`{}` rejected {}:\n{}\n```\n{}```\n",
+            compiler,
+            name,
+            String::from_utf8_lossy(&output.stderr),
+            source
+        );
+    }
+}